@@ -0,0 +1,271 @@
+// This module moves years that are over out of the data file into files of
+// their own, so the live file stays small
+//
+// A data file `expenses.csv` archives 2024 to `archive/expenses-2024.csv`
+// next to it, and the config remembers which years have been archived so
+// `load_with_archives` can read them back when a date range reaches them.
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::error::ExpenseError;
+use crate::expense::Expense;
+use crate::storage::{FileStorage, Storage, load_from_file, save_to_file};
+
+/// What `archive_year` did
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchiveResult {
+    pub year: String,
+    /// How many expenses left the live file
+    pub moved: usize,
+    /// The archive file for the year
+    pub path: PathBuf,
+}
+
+/// Why `archive_year` refused or failed
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArchiveError {
+    /// The year isn't over yet; archiving it needs `force`
+    NotOver(String),
+    /// The year isn't a `YYYY` year
+    BadYear(String),
+    /// Reading or writing a file failed
+    Storage(ExpenseError),
+}
+
+impl fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArchiveError::NotOver(year) => {
+                write!(f, "{} isn't over yet (use force to archive it)", year)
+            }
+            ArchiveError::BadYear(year) => write!(f, "`{}` isn't a YYYY year", year),
+            ArchiveError::Storage(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for ArchiveError {}
+
+impl From<ExpenseError> for ArchiveError {
+    fn from(err: ExpenseError) -> Self {
+        ArchiveError::Storage(err)
+    }
+}
+
+impl From<ArchiveError> for ExpenseError {
+    fn from(err: ArchiveError) -> Self {
+        match err {
+            ArchiveError::Storage(err) => err,
+            ArchiveError::BadYear(_) => ExpenseError::InvalidDate(err.to_string()),
+            ArchiveError::NotOver(_) => ExpenseError::PeriodClosed(err.to_string()),
+        }
+    }
+}
+
+/// Where the data file at `path` archives `year`
+pub fn archive_path(path: &str, year: &str) -> PathBuf {
+    let dir = Path::new(path).parent().unwrap_or(Path::new(""));
+    dir.join("archive").join(format!("expenses-{}.csv", year))
+}
+
+/// Move every expense dated in `year` from the data file to its archive
+/// file, and record the year in `config.archived_years`
+///
+/// `year` must be over by `today` unless `force` is set. Archiving a year
+/// again moves only what was added to the live file since, merging it into
+/// the archive; with nothing to move, no file is touched. The caller saves
+/// the config.
+pub fn archive_year(
+    storage: &mut FileStorage,
+    config: &mut Config,
+    year: &str,
+    today: &str,
+    force: bool,
+) -> Result<ArchiveResult, ArchiveError> {
+    if year.len() != 4 || !year.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(ArchiveError::BadYear(year.to_string()));
+    }
+    if !force && year >= today.get(..4).unwrap_or(today) {
+        return Err(ArchiveError::NotOver(year.to_string()));
+    }
+
+    let path = archive_path(&storage.path, year);
+    let prefix = format!("{}-", year);
+    let (leaving, staying): (Vec<Expense>, Vec<Expense>) = storage
+        .load()?
+        .into_iter()
+        .partition(|e| e.date.starts_with(&prefix));
+    if !leaving.is_empty() {
+        // Write the archive first, so a failure leaves the live file whole
+        let mut archived = if path.exists() {
+            load_from_file(&path.to_string_lossy())?
+        } else {
+            std::fs::create_dir_all(path.parent().unwrap_or(Path::new("")))
+                .map_err(ExpenseError::from)?;
+            Vec::new()
+        };
+        let moved: BTreeSet<u64> = leaving.iter().map(|e| e.id).collect();
+        archived.retain(|e| !moved.contains(&e.id));
+        archived.extend(leaving.iter().cloned());
+        save_to_file(&archived, &path.to_string_lossy())?;
+        storage.save(&staying)?;
+        log::debug!("archived {} expenses to {}", leaving.len(), path.display());
+    }
+    if path.exists() {
+        config.archived_years.insert(year.to_string());
+    }
+    Ok(ArchiveResult {
+        year: year.to_string(),
+        moved: leaving.len(),
+        path,
+    })
+}
+
+/// Load the data file along with every archived year from `from` to `to`
+/// (both `YYYY-MM-DD`, both included)
+///
+/// Archived years come first, oldest first, then the live file. Nothing is
+/// filtered by date; the archives are only read when the range needs them.
+pub fn load_with_archives(
+    storage: &FileStorage,
+    config: &Config,
+    from: &str,
+    to: &str,
+) -> Result<Vec<Expense>, ExpenseError> {
+    let (first, last) = (from.get(..4).unwrap_or(from), to.get(..4).unwrap_or(to));
+    let mut expenses = Vec::new();
+    for year in &config.archived_years {
+        if year.as_str() >= first && year.as_str() <= last {
+            let path = archive_path(&storage.path, year);
+            let archived = load_from_file(&path.to_string_lossy())
+                .map_err(|err| err.with_context(&path.display().to_string()))?;
+            expenses.extend(archived);
+        }
+    }
+    expenses.extend(storage.load()?);
+    Ok(expenses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> TempDir {
+            let dir = std::env::temp_dir().join(format!("archive_{}_{}", name, std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+
+        fn storage(&self) -> FileStorage {
+            FileStorage {
+                path: self.0.join("expenses.csv").to_str().unwrap().to_string(),
+            }
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn sample() -> Vec<Expense> {
+        vec![
+            Expense::with_id(1, 45.5, "food", "2024-03-08"),
+            Expense::with_id(2, 500.0, "rent", "2025-01-01"),
+            Expense::with_id(3, 20.0, "transport", "2024-12-31"),
+            Expense::with_id(4, 12.0, "food", "2026-02-01"),
+        ]
+    }
+
+    #[test]
+    fn test_archive_year_moves_its_expenses() {
+        let dir = TempDir::new("move");
+        let mut storage = dir.storage();
+        storage.save(&sample()).unwrap();
+        let mut config = Config::default();
+
+        let result = archive_year(&mut storage, &mut config, "2024", "2026-03-01", false).unwrap();
+        assert_eq!(result.moved, 2);
+        assert_eq!(result.path, dir.0.join("archive").join("expenses-2024.csv"));
+        let ids = |expenses: Vec<Expense>| expenses.iter().map(|e| e.id).collect::<Vec<_>>();
+        assert_eq!(ids(storage.load().unwrap()), vec![2, 4]);
+        assert_eq!(
+            ids(load_from_file(&result.path.to_string_lossy()).unwrap()),
+            vec![1, 3]
+        );
+        assert!(config.archived_years.contains("2024"));
+
+        assert_eq!(
+            archive_year(&mut storage, &mut config, "2026", "2026-03-01", false),
+            Err(ArchiveError::NotOver("2026".to_string()))
+        );
+        assert!(matches!(
+            archive_year(&mut storage, &mut config, "24", "2026-03-01", false),
+            Err(ArchiveError::BadYear(_))
+        ));
+        let forced = archive_year(&mut storage, &mut config, "2026", "2026-03-01", true).unwrap();
+        assert_eq!(forced.moved, 1);
+        assert_eq!(ids(storage.load().unwrap()), vec![2]);
+    }
+
+    #[test]
+    fn test_range_queries_reload_archived_years() {
+        let dir = TempDir::new("reload");
+        let mut storage = dir.storage();
+        storage.save(&sample()).unwrap();
+        let mut config = Config::default();
+        archive_year(&mut storage, &mut config, "2024", "2026-03-01", false).unwrap();
+
+        let ids = |from: &str, to: &str| {
+            let expenses = load_with_archives(&storage, &config, from, to).unwrap();
+            expenses.iter().map(|e| e.id).collect::<Vec<_>>()
+        };
+        assert_eq!(ids("2024-12-01", "2025-01-31"), vec![1, 3, 2, 4]);
+        assert_eq!(ids("2025-01-01", "2026-12-31"), vec![2, 4]);
+
+        std::fs::remove_file(archive_path(&storage.path, "2024")).unwrap();
+        let err = load_with_archives(&storage, &config, "2024-01-01", "2024-12-31").unwrap_err();
+        assert!(err.to_string().contains("expenses-2024.csv"), "{}", err);
+    }
+
+    #[test]
+    fn test_archiving_again_only_moves_new_expenses() {
+        let dir = TempDir::new("again");
+        let mut storage = dir.storage();
+        storage.save(&sample()).unwrap();
+        let mut config = Config::default();
+        let first = archive_year(&mut storage, &mut config, "2024", "2026-03-01", false).unwrap();
+        let archived = std::fs::read_to_string(&first.path).unwrap();
+
+        let again = archive_year(&mut storage, &mut config, "2024", "2026-03-01", false).unwrap();
+        assert_eq!(again.moved, 0);
+        assert_eq!(std::fs::read_to_string(&first.path).unwrap(), archived);
+
+        // A late entry for the year joins the ones already archived
+        let mut live = storage.load().unwrap();
+        live.push(Expense::with_id(5, 8.0, "food", "2024-06-01"));
+        storage.save(&live).unwrap();
+        let late = archive_year(&mut storage, &mut config, "2024", "2026-03-01", false).unwrap();
+        assert_eq!(late.moved, 1);
+        let ids: Vec<u64> = load_from_file(&late.path.to_string_lossy())
+            .unwrap()
+            .iter()
+            .map(|e| e.id)
+            .collect();
+        assert_eq!(ids, vec![1, 3, 5]);
+
+        // Nothing to move and nothing archived before records nothing
+        let none = archive_year(&mut storage, &mut config, "2023", "2026-03-01", false).unwrap();
+        assert_eq!(none.moved, 0);
+        assert!(!none.path.exists());
+        assert!(!config.archived_years.contains("2023"));
+    }
+}
@@ -0,0 +1,336 @@
+// This module stores expenses in a compact binary format, with an optional
+// run-length encoded (RLE) variant for long logs
+
+use crate::error::ExpenseError;
+use crate::expense::{Expense, TransactionKind};
+
+/// Marks a file written by `to_binary`
+const BINARY_MAGIC: &[u8; 4] = b"EXPB";
+/// Marks a file written by `compress`
+const RLE_MAGIC: &[u8; 4] = b"EXPR";
+
+/// Encode expenses in the plain binary format
+///
+/// Layout: magic, record count, then for every expense its id, amount,
+/// category, date, note, payee, tags, currency, kind, subcategory and
+/// receipt number. Numbers are little-endian, strings are a `u32` length
+/// followed by UTF-8 bytes, the optional note, payee, currency,
+/// subcategory and receipt number are a 0/1 byte followed by the string
+/// when present, the tags are a `u32` count
+/// followed by that many strings, and the kind is one byte, 0 for an
+/// expense and 1 for income.
+pub fn to_binary(expenses: &[Expense]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(BINARY_MAGIC);
+    write_u32(&mut out, expenses.len() as u32);
+    for expense in expenses {
+        write_u64(&mut out, expense.id);
+        write_f64(&mut out, expense.amount);
+        write_str(&mut out, &expense.category);
+        write_str(&mut out, &expense.date);
+        write_opt_str(&mut out, expense.note.as_deref());
+        write_opt_str(&mut out, expense.payee.as_deref());
+        write_strs(&mut out, &expense.tags);
+        write_opt_str(&mut out, expense.currency.as_deref());
+        write_kind(&mut out, expense.kind);
+        write_opt_str(&mut out, expense.subcategory.as_deref());
+        write_opt_str(&mut out, expense.receipt_number.as_deref());
+    }
+    out
+}
+
+/// Decode expenses written by `to_binary`
+pub fn from_binary(bytes: &[u8]) -> Result<Vec<Expense>, ExpenseError> {
+    let mut reader = Reader::new(bytes);
+    reader.expect_magic(BINARY_MAGIC)?;
+    let count = reader.read_u32()?;
+    let mut expenses = Vec::new();
+    for _ in 0..count {
+        let id = reader.read_u64()?;
+        let amount = reader.read_f64()?;
+        let category = reader.read_str()?;
+        let date = reader.read_str()?;
+        let mut expense = Expense::with_id(id, amount, &category, &date);
+        expense.note = reader.read_opt_str()?;
+        expense.payee = reader.read_opt_str()?;
+        expense.tags = reader.read_strs()?;
+        expense.currency = reader.read_opt_str()?;
+        expense.kind = reader.read_kind()?;
+        expense.subcategory = reader.read_opt_str()?;
+        expense.receipt_number = reader.read_opt_str()?;
+        expenses.push(expense);
+    }
+    reader.expect_end()?;
+    Ok(expenses)
+}
+
+/// Encode expenses with run-length encoding on the category
+///
+/// Consecutive expenses sharing a category are stored as one run: the
+/// category once, the run length, then only the id, amount, date, note,
+/// payee, tags, currency, kind, subcategory and receipt number of each
+/// expense in the run, each as in `to_binary`.
+pub fn compress(expenses: &[Expense]) -> Vec<u8> {
+    let runs: Vec<&[Expense]> = expenses.chunk_by(|a, b| a.category == b.category).collect();
+
+    let mut out = Vec::new();
+    out.extend_from_slice(RLE_MAGIC);
+    write_u32(&mut out, runs.len() as u32);
+    for run in runs {
+        write_str(&mut out, &run[0].category);
+        write_u32(&mut out, run.len() as u32);
+        for expense in run {
+            write_u64(&mut out, expense.id);
+            write_f64(&mut out, expense.amount);
+            write_str(&mut out, &expense.date);
+            write_opt_str(&mut out, expense.note.as_deref());
+            write_opt_str(&mut out, expense.payee.as_deref());
+            write_strs(&mut out, &expense.tags);
+            write_opt_str(&mut out, expense.currency.as_deref());
+            write_kind(&mut out, expense.kind);
+            write_opt_str(&mut out, expense.subcategory.as_deref());
+            write_opt_str(&mut out, expense.receipt_number.as_deref());
+        }
+    }
+    out
+}
+
+/// Decode expenses written by `compress`
+pub fn decompress(bytes: &[u8]) -> Result<Vec<Expense>, ExpenseError> {
+    let mut reader = Reader::new(bytes);
+    reader.expect_magic(RLE_MAGIC)?;
+    let run_count = reader.read_u32()?;
+    let mut expenses = Vec::new();
+    for _ in 0..run_count {
+        let category = reader.read_str()?;
+        let run_len = reader.read_u32()?;
+        if run_len == 0 {
+            return Err(ExpenseError::ParseError("empty run".to_string()));
+        }
+        for _ in 0..run_len {
+            let id = reader.read_u64()?;
+            let amount = reader.read_f64()?;
+            let date = reader.read_str()?;
+            let mut expense = Expense::with_id(id, amount, &category, &date);
+            expense.note = reader.read_opt_str()?;
+            expense.payee = reader.read_opt_str()?;
+            expense.tags = reader.read_strs()?;
+            expense.currency = reader.read_opt_str()?;
+            expense.kind = reader.read_kind()?;
+            expense.subcategory = reader.read_opt_str()?;
+            expense.receipt_number = reader.read_opt_str()?;
+            expenses.push(expense);
+        }
+    }
+    reader.expect_end()?;
+    Ok(expenses)
+}
+
+/// Compress expenses and write them to a file
+#[cfg(feature = "fs")]
+pub fn compress_and_save(expenses: &[Expense], path: &str) -> Result<(), ExpenseError> {
+    std::fs::write(path, compress(expenses))?;
+    Ok(())
+}
+
+/// Read a file written by `compress_and_save`
+#[cfg(feature = "fs")]
+pub fn decompress_and_load(path: &str) -> Result<Vec<Expense>, ExpenseError> {
+    let bytes = std::fs::read(path)?;
+    decompress(&bytes)
+}
+
+fn write_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn write_u64(out: &mut Vec<u8>, value: u64) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn write_f64(out: &mut Vec<u8>, value: f64) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn write_str(out: &mut Vec<u8>, value: &str) {
+    write_u32(out, value.len() as u32);
+    out.extend_from_slice(value.as_bytes());
+}
+
+fn write_strs(out: &mut Vec<u8>, values: &[String]) {
+    write_u32(out, values.len() as u32);
+    for value in values {
+        write_str(out, value);
+    }
+}
+
+fn write_kind(out: &mut Vec<u8>, kind: TransactionKind) {
+    out.push(match kind {
+        TransactionKind::Expense => 0,
+        TransactionKind::Income => 1,
+    });
+}
+
+fn write_opt_str(out: &mut Vec<u8>, value: Option<&str>) {
+    match value {
+        Some(value) => {
+            out.push(1);
+            write_str(out, value);
+        }
+        None => out.push(0),
+    }
+}
+
+/// Cursor over an encoded buffer that turns truncation into errors
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Reader<'a> {
+        Reader { bytes, pos: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ExpenseError> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len());
+        match end {
+            Some(end) => {
+                let slice = &self.bytes[self.pos..end];
+                self.pos = end;
+                Ok(slice)
+            }
+            None => Err(ExpenseError::ParseError(format!(
+                "unexpected end of data at byte {}",
+                self.pos
+            ))),
+        }
+    }
+
+    fn expect_magic(&mut self, magic: &[u8; 4]) -> Result<(), ExpenseError> {
+        if self.take(4)? != magic {
+            return Err(ExpenseError::ParseError(
+                "unrecognized file header".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn expect_end(&self) -> Result<(), ExpenseError> {
+        if self.pos != self.bytes.len() {
+            return Err(ExpenseError::ParseError(format!(
+                "trailing data at byte {}",
+                self.pos
+            )));
+        }
+        Ok(())
+    }
+
+    fn read_u32(&mut self) -> Result<u32, ExpenseError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn read_u64(&mut self) -> Result<u64, ExpenseError> {
+        let bytes = self.take(8)?;
+        Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn read_f64(&mut self) -> Result<f64, ExpenseError> {
+        let bytes = self.take(8)?;
+        Ok(f64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn read_opt_str(&mut self) -> Result<Option<String>, ExpenseError> {
+        match self.take(1)?[0] {
+            0 => Ok(None),
+            1 => Ok(Some(self.read_str()?)),
+            flag => Err(ExpenseError::ParseError(format!(
+                "bad option flag {}",
+                flag
+            ))),
+        }
+    }
+
+    fn read_kind(&mut self) -> Result<TransactionKind, ExpenseError> {
+        match self.take(1)?[0] {
+            0 => Ok(TransactionKind::Expense),
+            1 => Ok(TransactionKind::Income),
+            kind => Err(ExpenseError::ParseError(format!("bad kind {}", kind))),
+        }
+    }
+
+    fn read_strs(&mut self) -> Result<Vec<String>, ExpenseError> {
+        let count = self.read_u32()?;
+        (0..count).map(|_| self.read_str()).collect()
+    }
+
+    fn read_str(&mut self) -> Result<String, ExpenseError> {
+        let len = self.read_u32()? as usize;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec())
+            .map_err(|_| ExpenseError::ParseError("string is not valid UTF-8".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Vec<Expense> {
+        let mut expenses = Vec::new();
+        for day in 1..=10 {
+            expenses.push(Expense::new(12.5, "food", &format!("2026-01-{:02}", day)));
+        }
+        expenses[3].note = Some("team lunch".to_string());
+        expenses[4].payee = Some("Deli".to_string());
+        expenses[5].tags = vec!["work".to_string(), "client".to_string()];
+        expenses[6].currency = Some("NGN".to_string());
+        for day in 1..=5 {
+            expenses.push(Expense::new(
+                3.0,
+                "transport",
+                &format!("2026-02-{:02}", day),
+            ));
+        }
+        expenses
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        let expenses = sample();
+        assert_eq!(from_binary(&to_binary(&expenses)).unwrap(), expenses);
+    }
+
+    #[test]
+    fn test_compress_is_smaller_than_binary() {
+        let expenses = sample();
+        let compressed = compress(&expenses);
+        assert!(compressed.len() < to_binary(&expenses).len());
+        assert_eq!(decompress(&compressed).unwrap(), expenses);
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn test_compress_and_save_round_trip() {
+        let path = std::env::temp_dir().join(format!("rle_test_{}.bin", std::process::id()));
+        let path = path.to_str().unwrap();
+        let expenses = sample();
+
+        compress_and_save(&expenses, path).unwrap();
+        let loaded = decompress_and_load(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(loaded, expenses);
+    }
+
+    #[test]
+    fn test_decompress_rejects_truncated_data() {
+        let compressed = compress(&sample());
+        let result = decompress(&compressed[..compressed.len() - 3]);
+        assert!(matches!(result, Err(ExpenseError::ParseError(_))));
+    }
+}
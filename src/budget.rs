@@ -0,0 +1,890 @@
+// This module defines weekly or monthly spending limits per category and
+// works out how much of them is left
+//
+// Income entries aren't spending: they never count against a limit.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::dates::{
+    days_between_dates, days_in_month, month_of, next_date, next_month, parse_date, week_end,
+    week_start,
+};
+use crate::expense::Expense;
+use crate::operations::{calculate_total, date_span, total_by_category};
+
+/// Share of a limit at which spending starts to draw a warning
+pub const WARN_FRACTION: f64 = 0.8;
+
+/// How often a limit starts over
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Period {
+    /// Every ISO week, Monday to Sunday
+    Weekly,
+    /// Every calendar month
+    #[default]
+    Monthly,
+}
+
+impl Period {
+    /// The window `date` falls in: its month (`YYYY-MM`), or for a weekly
+    /// period the Monday (`YYYY-MM-DD`) starting its week
+    ///
+    /// Windows of one period sort in time order as strings.
+    pub fn window_of(self, date: &str) -> Option<String> {
+        match self {
+            Period::Weekly => week_start(date),
+            Period::Monthly => month_of(date).map(str::to_string),
+        }
+    }
+
+    /// The window a budget starting in `start_month` (`YYYY-MM`) begins
+    /// with; for weeks, the one holding the month's first day
+    fn first_window(self, start_month: &str) -> Option<String> {
+        self.window_of(&format!("{}-01", start_month))
+    }
+
+    fn next_window(self, window: &str) -> Option<String> {
+        match self {
+            Period::Weekly => next_date(window, 7).ok(),
+            Period::Monthly => next_month(window),
+        }
+    }
+}
+
+/// The limit for one category
+#[derive(Debug, Clone, PartialEq)]
+pub struct CategoryLimit {
+    /// How much may be spent each period
+    pub limit: f64,
+    /// Whether unspent money (or overspend) moves on to the next period
+    pub carryover: bool,
+    /// How often the limit starts over
+    pub period: Period,
+}
+
+/// Spending limits, counted from `start_month`
+///
+/// Each limit is weekly or monthly on its own, so rent can be budgeted by
+/// the month and food by the week.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Budget {
+    /// First month (`YYYY-MM`) the budget applies to
+    pub start_month: String,
+    /// Limits by category name
+    pub limits: HashMap<String, CategoryLimit>,
+    /// Never let overspend carried forward push a period's budget below zero
+    pub floor_at_zero: bool,
+    /// Limit on the total across all categories, if any
+    pub overall_limit: Option<f64>,
+    /// How often `overall_limit` starts over
+    pub overall_period: Period,
+}
+
+/// A period's spending nearing or over a limit
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetWarning {
+    /// The category whose limit this is, or `None` for the overall limit
+    pub category: Option<String>,
+    /// The month or week the spending is in, as given by `Period::window_of`
+    pub window: String,
+    /// Spent so far in that window
+    pub spent: f64,
+    /// The window's limit, including any carryover
+    pub limit: f64,
+    /// Past the limit itself, not just `WARN_FRACTION` of it
+    pub over: bool,
+}
+
+/// How removing one expense would change its category's budget
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemovalImpact {
+    /// The expense's category
+    pub category: String,
+    /// The month or week of the expense, as given by `Period::window_of`
+    pub window: String,
+    /// The window's limit, including any carryover
+    pub limit: f64,
+    /// Spent in the window with the expense
+    pub spent: f64,
+    /// Spent in the window without it
+    pub spent_after: f64,
+    /// Over the limit now, but not once the expense is gone
+    pub back_under_budget: bool,
+}
+
+/// Projected figures for an expense that hasn't been added yet (see
+/// `simulate_add`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationResult {
+    /// Total of every expense, the new one included
+    pub new_total: f64,
+    /// Total of the new expense's category, the new one included
+    pub new_category_total: f64,
+    /// Whether the new expense would push its category or the overall
+    /// spending past a limit for its week or month
+    pub would_exceed_budget: bool,
+    /// How much the average spent per day goes up, counting the days from
+    /// the first expense to the last
+    pub daily_average_impact: f64,
+}
+
+/// One line of `category_budget_report`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CategoryBudgetStatus {
+    pub category: String,
+    /// The category's limit, without carryover
+    pub limit: f64,
+    pub spent: f64,
+    /// `limit - spent`; negative when over
+    pub remaining: f64,
+    /// Spent more than the limit; spending exactly the limit is not over
+    pub is_over: bool,
+}
+
+/// How much a category can still spend per day for the rest of its week
+/// or month (see `daily_allowance`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct DailyAllowance {
+    /// Spent in the window so far
+    pub spent: f64,
+    /// What is left of the window's budget, including carryover; 0.0 when
+    /// over
+    pub remaining: f64,
+    /// How far spending is past the budget; 0.0 when not over
+    pub overage: f64,
+    /// Days from the given date to the end of the window, both included
+    pub days_left: u32,
+    /// `remaining` spread over `days_left`
+    pub per_day: f64,
+}
+
+impl Budget {
+    /// Creates an empty budget starting in `start_month` (`YYYY-MM`)
+    pub fn new(start_month: &str) -> Budget {
+        Budget {
+            start_month: start_month.to_string(),
+            limits: HashMap::new(),
+            floor_at_zero: false,
+            overall_limit: None,
+            overall_period: Period::Monthly,
+        }
+    }
+
+    /// Set the monthly limit for a category, without carryover
+    pub fn set_limit(&mut self, category: &str, limit: f64) {
+        self.limits.insert(
+            category.to_string(),
+            CategoryLimit {
+                limit,
+                carryover: false,
+                period: Period::Monthly,
+            },
+        );
+    }
+
+    /// Change how often a category's limit starts over
+    pub fn set_period(&mut self, category: &str, period: Period) {
+        if let Some(limit) = self.limits.get_mut(category) {
+            limit.period = period;
+        }
+    }
+
+    /// Turn carryover on or off for a category that has a limit
+    pub fn set_carryover(&mut self, category: &str, carryover: bool) {
+        if let Some(limit) = self.limits.get_mut(category) {
+            limit.carryover = carryover;
+        }
+    }
+}
+
+/// Each category's budget for the week or month holding `date`
+/// (`YYYY-MM-DD`), including carryover
+///
+/// Without carryover this is just the limit. With carryover, every period
+/// from the budget's start up to (not including) the one holding `date`
+/// passes on what was left of its own budget: unspent money adds to the
+/// next period and overspend takes away from it. With `floor_at_zero`, a
+/// period's budget never goes below zero, so a large overspend is only
+/// paid back once.
+pub fn effective_budget(expenses: &[Expense], budget: &Budget, date: &str) -> HashMap<String, f64> {
+    let mut result = HashMap::new();
+    for (category, limit) in &budget.limits {
+        if !limit.carryover {
+            result.insert(category.clone(), limit.limit);
+            continue;
+        }
+        let (Some(window), Some(mut current)) = (
+            limit.period.window_of(date),
+            limit.period.first_window(&budget.start_month),
+        ) else {
+            continue;
+        };
+
+        let spent = spent_by_window(expenses, Some(category), limit.period);
+        let mut effective = limit.limit;
+        while current < window {
+            let leftover = effective - spent.get(&current).copied().unwrap_or(0.0);
+            effective = limit.limit + leftover;
+            if budget.floor_at_zero {
+                effective = effective.max(0.0);
+            }
+            match limit.period.next_window(&current) {
+                Some(next) => current = next,
+                None => break,
+            }
+        }
+        result.insert(category.clone(), effective);
+    }
+    result
+}
+
+/// What is left of each category's budget in the week or month holding
+/// `date`, after that window's spending; negative when overspent
+pub fn remaining_budget(expenses: &[Expense], budget: &Budget, date: &str) -> HashMap<String, f64> {
+    effective_budget(expenses, budget, date)
+        .into_iter()
+        .filter_map(|(category, effective)| {
+            let period = budget.limits[&category].period;
+            let spent = spent_in_window(expenses, Some(&category), period, date)?;
+            Some((category, effective - spent))
+        })
+        .collect()
+}
+
+/// Categories that have spent more than their budget in the week or month
+/// holding `date`, alphabetically
+pub fn over_budget_categories(expenses: &[Expense], budget: &Budget, date: &str) -> Vec<String> {
+    let mut over: Vec<String> = remaining_budget(expenses, budget, date)
+        .into_iter()
+        .filter(|&(_, remaining)| remaining < 0.0)
+        .map(|(category, _)| category)
+        .collect();
+    over.sort();
+    over
+}
+
+/// What removing the expense with `id` would do to its category's budget
+/// for the week or month it falls in
+///
+/// `None` if there is no such expense, its category has no limit or its
+/// date can't be read.
+pub fn impact_on_category_budget(
+    expenses: &[Expense],
+    id: u64,
+    budget: &Budget,
+) -> Option<RemovalImpact> {
+    let expense = expenses.iter().find(|e| e.id == id)?;
+    let category = &expense.category;
+    let period = budget.limits.get(category)?.period;
+    let window = period.window_of(&expense.date)?;
+    let limit = *effective_budget(expenses, budget, &expense.date).get(category)?;
+    let spent = spent_in_window(expenses, Some(category), period, &expense.date)?;
+    let spent_after = if expense.kind.is_expense() {
+        spent - expense.amount
+    } else {
+        spent
+    };
+    Some(RemovalImpact {
+        category: category.clone(),
+        window,
+        limit,
+        spent,
+        spent_after,
+        back_under_budget: spent > limit && spent_after <= limit,
+    })
+}
+
+/// What adding an expense would do, without adding it
+///
+/// `would_exceed_budget` is only ever true with a `budget`, and checks the
+/// same limits as `budget_warnings`.
+pub fn simulate_add(
+    expenses: &[Expense],
+    amount: f64,
+    category: &str,
+    date: &str,
+    budget: Option<&Budget>,
+) -> SimulationResult {
+    let mut with_new = expenses.to_vec();
+    // Id 0 is never handed out, so this reserves nothing
+    with_new.push(Expense::with_id(0, amount, category, date));
+    let would_exceed_budget = budget.is_some_and(|budget| {
+        budget_warnings(&with_new, budget, category, date)
+            .iter()
+            .any(|w| w.over)
+    });
+    SimulationResult {
+        new_total: calculate_total(&with_new),
+        new_category_total: total_by_category(&with_new, category),
+        would_exceed_budget,
+        daily_average_impact: daily_average(&with_new) - daily_average(expenses),
+    }
+}
+
+/// Total divided by the days from the earliest to the latest date, both
+/// included; 0.0 with no expenses
+fn daily_average(expenses: &[Expense]) -> f64 {
+    match date_span(expenses) {
+        Some((first, last)) => {
+            let days = days_between_dates(&first, &last).map_or(1, |d| d.abs() + 1);
+            calculate_total(expenses) / days as f64
+        }
+        None => 0.0,
+    }
+}
+
+/// Limit, spending and what is left for every category with a limit,
+/// alphabetically
+///
+/// Like `rebalance_toward_budget`, this totals all of `expenses`, so pass
+/// one period's expenses to compare against that period's limits.
+pub fn category_budget_report(expenses: &[Expense], budget: &Budget) -> Vec<CategoryBudgetStatus> {
+    let mut report: Vec<CategoryBudgetStatus> = budget
+        .limits
+        .iter()
+        .map(|(category, limit)| {
+            let spent = spent_in_category(expenses, category);
+            CategoryBudgetStatus {
+                category: category.clone(),
+                limit: limit.limit,
+                spent,
+                remaining: limit.limit - spent,
+                is_over: spent > limit.limit,
+            }
+        })
+        .collect();
+    report.sort_by(|a, b| a.category.cmp(&b.category));
+    report
+}
+
+/// How far each category's spending is from its limit, alphabetically
+///
+/// For a category with a limit this is `limit - total`: positive is room
+/// left to spend, negative is overspend. A category without a limit is
+/// unconstrained and gets `f64::INFINITY`. Every category in the budget or
+/// the expenses is listed. Totals cover all of `expenses`, so pass one
+/// period's expenses to compare against that period's limits.
+pub fn rebalance_toward_budget(expenses: &[Expense], budget: &Budget) -> Vec<(String, f64)> {
+    let categories: BTreeSet<&str> = budget
+        .limits
+        .keys()
+        .map(String::as_str)
+        .chain(expenses.iter().map(|e| e.category.as_str()))
+        .collect();
+    categories
+        .into_iter()
+        .map(|category| {
+            let room = match budget.limits.get(category) {
+                Some(limit) => limit.limit - spent_in_category(expenses, category),
+                None => f64::INFINITY,
+            };
+            (category.to_string(), room)
+        })
+        .collect()
+}
+
+/// Spending in `category` over all of `expenses`
+fn spent_in_category(expenses: &[Expense], category: &str) -> f64 {
+    expenses
+        .iter()
+        .filter(|e| e.kind.is_expense() && e.category == category)
+        .map(|e| e.amount)
+        .sum()
+}
+
+/// Spending per window of `period`, in `category` or across all of them
+fn spent_by_window(
+    expenses: &[Expense],
+    category: Option<&str>,
+    period: Period,
+) -> BTreeMap<String, f64> {
+    let mut totals = BTreeMap::new();
+    for expense in expenses {
+        if !expense.kind.is_expense() || category.is_some_and(|c| expense.category != c) {
+            continue;
+        }
+        if let Some(window) = period.window_of(&expense.date) {
+            *totals.entry(window).or_insert(0.0) += expense.amount;
+        }
+    }
+    totals
+}
+
+/// Spending in the window of `period` holding `date`, or `None` if `date`
+/// can't be read
+fn spent_in_window(
+    expenses: &[Expense],
+    category: Option<&str>,
+    period: Period,
+    date: &str,
+) -> Option<f64> {
+    let window = period.window_of(date)?;
+    Some(
+        spent_by_window(expenses, category, period)
+            .get(&window)
+            .copied()
+            .unwrap_or(0.0),
+    )
+}
+
+/// What `category` can spend per day from `as_of` to the end of its week
+/// or month (the 31st of January, say), counting `as_of` itself
+///
+/// An overspent category gets a zero allowance with the overspend in
+/// `overage`, never a negative one. `None` if the category has no limit
+/// or `as_of` can't be read.
+pub fn daily_allowance(
+    expenses: &[Expense],
+    budget: &Budget,
+    category: &str,
+    as_of: &str,
+) -> Option<DailyAllowance> {
+    let period = budget.limits.get(category)?.period;
+    let (year, month, day) = parse_date(as_of).ok()?;
+    let days_left = match period {
+        Period::Monthly => days_in_month(year as i64, month as u32) - day as u32 + 1,
+        Period::Weekly => days_between_dates(as_of, &week_end(as_of)?).ok()? as u32 + 1,
+    };
+    let limit = *effective_budget(expenses, budget, as_of).get(category)?;
+    let spent = spent_in_window(expenses, Some(category), period, as_of)?;
+    let remaining = (limit - spent).max(0.0);
+    Some(DailyAllowance {
+        spent,
+        remaining,
+        overage: (spent - limit).max(0.0),
+        days_left,
+        per_day: remaining / days_left as f64,
+    })
+}
+
+/// Warnings for the week or month of `date`, checking `category`'s limit
+/// and the overall limit
+///
+/// Each limit is checked over its own period. A limit draws a warning once
+/// the spending in that window reaches `WARN_FRACTION` of it, and is
+/// marked `over` once spending passes it. Other categories aren't looked
+/// at.
+pub fn budget_warnings(
+    expenses: &[Expense],
+    budget: &Budget,
+    category: &str,
+    date: &str,
+) -> Vec<BudgetWarning> {
+    let mut checks = Vec::new();
+    if let Some(category_limit) = budget.limits.get(category)
+        && let Some(window) = category_limit.period.window_of(date)
+        && let Some(&limit) = effective_budget(expenses, budget, date).get(category)
+    {
+        let spent = spent_in_window(expenses, Some(category), category_limit.period, date);
+        checks.push((
+            Some(category.to_string()),
+            window,
+            spent.unwrap_or(0.0),
+            limit,
+        ));
+    }
+    if let Some(limit) = budget.overall_limit
+        && let Some(window) = budget.overall_period.window_of(date)
+    {
+        let spent = spent_in_window(expenses, None, budget.overall_period, date);
+        checks.push((None, window, spent.unwrap_or(0.0), limit));
+    }
+    checks
+        .into_iter()
+        .filter(|&(_, _, spent, limit)| spent >= limit * WARN_FRACTION)
+        .map(|(category, window, spent, limit)| BudgetWarning {
+            category,
+            window,
+            spent,
+            limit,
+            over: spent > limit,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn food_budget() -> Budget {
+        let mut budget = Budget::new("2026-01");
+        budget.set_limit("food", 100.0);
+        budget.set_carryover("food", true);
+        budget.set_limit("rent", 500.0);
+        budget
+    }
+
+    #[test]
+    fn test_carryover_over_three_months() {
+        let expenses = vec![
+            Expense::new(80.0, "food", "2026-01-10"),
+            Expense::new(150.0, "food", "2026-02-10"),
+            Expense::new(600.0, "rent", "2026-01-01"),
+        ];
+        let budget = food_budget();
+
+        assert_eq!(
+            effective_budget(&expenses, &budget, "2026-01-01")["food"],
+            100.0
+        );
+        // 20 left over from January
+        assert_eq!(
+            effective_budget(&expenses, &budget, "2026-02-01")["food"],
+            120.0
+        );
+        // February overspent its 120 by 30
+        assert_eq!(
+            effective_budget(&expenses, &budget, "2026-03-01")["food"],
+            70.0
+        );
+        // No carryover for rent, even though January was over
+        assert_eq!(
+            effective_budget(&expenses, &budget, "2026-03-01")["rent"],
+            500.0
+        );
+    }
+
+    #[test]
+    fn test_budget_warnings_for_category_and_overall() {
+        let mut budget = Budget::new("2026-01");
+        budget.set_limit("food", 100.0);
+        budget.overall_limit = Some(1000.0);
+        let expenses = vec![
+            Expense::new(85.0, "food", "2026-01-10"),
+            Expense::new(900.0, "rent", "2026-01-01"),
+            Expense::new(500.0, "food", "2026-02-10"),
+        ];
+
+        let warnings = budget_warnings(&expenses, &budget, "food", "2026-01-12");
+        assert_eq!(
+            warnings,
+            vec![
+                BudgetWarning {
+                    category: Some("food".to_string()),
+                    window: "2026-01".to_string(),
+                    spent: 85.0,
+                    limit: 100.0,
+                    over: false,
+                },
+                BudgetWarning {
+                    category: None,
+                    window: "2026-01".to_string(),
+                    spent: 985.0,
+                    limit: 1000.0,
+                    over: false,
+                },
+            ]
+        );
+        // Rent has no limit of its own, so only the overall one is checked
+        assert_eq!(
+            budget_warnings(&expenses, &budget, "rent", "2026-01-01").len(),
+            1
+        );
+        assert!(budget_warnings(&expenses, &budget, "food", "2026-03-01").is_empty());
+    }
+
+    #[test]
+    fn test_weekly_and_monthly_limits_in_one_budget() {
+        let mut budget = Budget::new("2026-01");
+        budget.set_limit("food", 50.0);
+        budget.set_period("food", Period::Weekly);
+        budget.set_limit("rent", 500.0);
+        // The week of Monday 2026-01-26 runs to Sunday 2026-02-01
+        let expenses = vec![
+            Expense::new(30.0, "food", "2026-01-30"),
+            Expense::new(25.0, "food", "2026-02-01"),
+            Expense::new(500.0, "rent", "2026-02-01"),
+            Expense::new(10.0, "food", "2026-02-03"),
+        ];
+
+        let remaining = remaining_budget(&expenses, &budget, "2026-02-01");
+        assert_eq!(remaining["food"], -5.0);
+        assert_eq!(remaining["rent"], 0.0);
+        assert_eq!(
+            over_budget_categories(&expenses, &budget, "2026-02-01"),
+            vec!["food".to_string()]
+        );
+
+        // A new week on the 2nd, but still February's rent
+        let remaining = remaining_budget(&expenses, &budget, "2026-02-03");
+        assert_eq!(remaining["food"], 40.0);
+        assert_eq!(remaining["rent"], 0.0);
+        assert!(over_budget_categories(&expenses, &budget, "2026-02-03").is_empty());
+
+        let warnings = budget_warnings(&expenses, &budget, "food", "2026-01-30");
+        assert_eq!(warnings[0].window, "2026-01-26");
+        assert_eq!(warnings[0].spent, 55.0);
+        assert!(warnings[0].over);
+    }
+
+    #[test]
+    fn test_weekly_carryover_and_overall_limit() {
+        let mut budget = Budget::new("2026-02");
+        budget.set_limit("food", 50.0);
+        budget.set_period("food", Period::Weekly);
+        budget.set_carryover("food", true);
+        budget.overall_limit = Some(60.0);
+        budget.overall_period = Period::Weekly;
+        let expenses = vec![
+            // The budget's first week starts on Monday 2026-01-26
+            Expense::new(30.0, "food", "2026-01-27"),
+            Expense::new(60.0, "food", "2026-02-05"),
+        ];
+
+        assert_eq!(
+            effective_budget(&expenses, &budget, "2026-02-01")["food"],
+            50.0
+        );
+        assert_eq!(
+            effective_budget(&expenses, &budget, "2026-02-02")["food"],
+            70.0
+        );
+        assert_eq!(
+            effective_budget(&expenses, &budget, "2026-02-09")["food"],
+            60.0
+        );
+
+        let warnings = budget_warnings(&expenses, &budget, "food", "2026-02-05");
+        let limits: Vec<(Option<&str>, &str, f64, bool)> = warnings
+            .iter()
+            .map(|w| (w.category.as_deref(), w.window.as_str(), w.limit, w.over))
+            .collect();
+        assert_eq!(
+            limits,
+            vec![
+                (Some("food"), "2026-02-02", 70.0, false),
+                (None, "2026-02-02", 60.0, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rebalance_toward_budget() {
+        let mut budget = food_budget();
+        budget.set_limit("coffee", 30.0);
+        let expenses = vec![
+            Expense::new(80.0, "food", "2026-01-10"),
+            Expense::new(45.5, "food", "2026-01-12"),
+            Expense::new(450.0, "rent", "2026-01-01"),
+            Expense::new(20.0, "transport", "2026-01-08"),
+        ];
+        assert_eq!(
+            rebalance_toward_budget(&expenses, &budget),
+            vec![
+                // Budgeted but nothing spent yet
+                ("coffee".to_string(), 30.0),
+                ("food".to_string(), -25.5),
+                ("rent".to_string(), 50.0),
+                ("transport".to_string(), f64::INFINITY),
+            ]
+        );
+        assert_eq!(
+            rebalance_toward_budget(&[], &Budget::new("2026-01")),
+            Vec::new()
+        );
+    }
+
+    #[test]
+    fn test_impact_on_category_budget() {
+        let budget = food_budget();
+        let lunch = Expense::new(30.0, "food", "2026-01-10");
+        let groceries = Expense::new(85.0, "food", "2026-01-12");
+        let february = Expense::new(20.0, "food", "2026-02-01");
+        let taxi = Expense::new(15.0, "transport", "2026-01-12");
+        let expenses = vec![
+            lunch.clone(),
+            groceries.clone(),
+            february.clone(),
+            taxi.clone(),
+        ];
+
+        assert_eq!(
+            impact_on_category_budget(&expenses, lunch.id, &budget),
+            Some(RemovalImpact {
+                category: "food".to_string(),
+                window: "2026-01".to_string(),
+                limit: 100.0,
+                spent: 115.0,
+                spent_after: 85.0,
+                back_under_budget: true,
+            })
+        );
+        // January's overspend of 15 carries into February, which is under
+        // its limit either way
+        let impact = impact_on_category_budget(&expenses, february.id, &budget).unwrap();
+        assert_eq!((impact.limit, impact.spent), (85.0, 20.0));
+        assert!(!impact.back_under_budget);
+
+        assert_eq!(impact_on_category_budget(&expenses, taxi.id, &budget), None);
+        assert_eq!(impact_on_category_budget(&expenses, 0, &budget), None);
+    }
+
+    #[test]
+    fn test_simulate_add() {
+        let expenses = vec![
+            Expense::new(60.0, "food", "2026-01-01"),
+            Expense::new(500.0, "rent", "2026-01-01"),
+            Expense::new(30.0, "food", "2026-01-10"),
+        ];
+        let before = expenses.clone();
+        let budget = food_budget();
+
+        let small = simulate_add(&expenses, 5.0, "food", "2026-01-10", Some(&budget));
+        assert_eq!(small.new_total, 595.0);
+        assert_eq!(small.new_category_total, 95.0);
+        assert!(!small.would_exceed_budget);
+        // Still ten days, 5 more spent
+        assert!((small.daily_average_impact - 0.5).abs() < 1e-9);
+
+        let big = simulate_add(&expenses, 20.0, "food", "2026-01-10", Some(&budget));
+        assert!(big.would_exceed_budget);
+        assert!(!simulate_add(&expenses, 20.0, "food", "2026-01-10", None).would_exceed_budget);
+        // February has its own limit
+        assert!(
+            !simulate_add(&expenses, 20.0, "food", "2026-02-01", Some(&budget)).would_exceed_budget
+        );
+
+        // Twenty days instead of ten: 610 / 20 - 590 / 10
+        let later = simulate_add(&expenses, 20.0, "coffee", "2026-01-20", None);
+        assert!((later.daily_average_impact - (30.5 - 59.0)).abs() < 1e-9);
+
+        assert_eq!(expenses, before);
+    }
+
+    #[test]
+    fn test_category_budget_report() {
+        let mut budget = food_budget();
+        budget.set_limit("coffee", 30.0);
+        let expenses = vec![
+            Expense::new(80.0, "food", "2026-01-10"),
+            Expense::new(20.0, "food", "2026-01-12"),
+            Expense::new(550.0, "rent", "2026-01-01"),
+            Expense::new(12.5, "coffee", "2026-01-08"),
+            Expense::new(20.0, "transport", "2026-01-08"),
+        ];
+        let status = |category: &str, limit: f64, spent: f64, is_over: bool| CategoryBudgetStatus {
+            category: category.to_string(),
+            limit,
+            spent,
+            remaining: limit - spent,
+            is_over,
+        };
+        assert_eq!(
+            category_budget_report(&expenses, &budget),
+            vec![
+                status("coffee", 30.0, 12.5, false),
+                // Exactly at the limit
+                status("food", 100.0, 100.0, false),
+                status("rent", 500.0, 550.0, true),
+            ]
+        );
+        assert!(category_budget_report(&expenses, &Budget::new("2026-01")).is_empty());
+    }
+
+    #[test]
+    fn test_income_never_counts_against_a_limit() {
+        use crate::expense::TransactionKind;
+
+        let mut budget = food_budget();
+        budget.overall_limit = Some(200.0);
+        // A refund booked as income in the food category
+        let expenses = vec![
+            Expense::new(90.0, "food", "2026-01-10"),
+            Expense::new(500.0, "food", "2026-01-11").with_kind(TransactionKind::Income),
+        ];
+        let warnings = budget_warnings(&expenses, &budget, "food", "2026-01-12");
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].spent, 90.0);
+        assert_eq!(
+            remaining_budget(&expenses, &budget, "2026-01-12")["food"],
+            10.0
+        );
+        assert_eq!(category_budget_report(&expenses, &budget)[0].spent, 90.0);
+        assert_eq!(
+            rebalance_toward_budget(&expenses, &budget),
+            vec![("food".to_string(), 10.0), ("rent".to_string(), 500.0)]
+        );
+    }
+
+    #[test]
+    fn test_overspend_floored_at_zero() {
+        let expenses = vec![Expense::new(250.0, "food", "2026-01-10")];
+        let mut budget = food_budget();
+
+        assert_eq!(
+            effective_budget(&expenses, &budget, "2026-02-01")["food"],
+            -50.0
+        );
+        assert_eq!(
+            effective_budget(&expenses, &budget, "2026-03-01")["food"],
+            50.0
+        );
+
+        budget.floor_at_zero = true;
+        assert_eq!(
+            effective_budget(&expenses, &budget, "2026-02-01")["food"],
+            0.0
+        );
+        assert_eq!(
+            effective_budget(&expenses, &budget, "2026-03-01")["food"],
+            100.0
+        );
+    }
+
+    #[test]
+    fn test_daily_allowance_to_the_end_of_the_month() {
+        let mut budget = Budget::new("2026-01");
+        budget.set_limit("food", 400.0);
+        let expenses = vec![
+            Expense::new(95.0, "food", "2026-01-03"),
+            Expense::new(60.0, "food", "2026-01-12"),
+            Expense::new(500.0, "rent", "2026-01-01"),
+        ];
+
+        let mid = daily_allowance(&expenses, &budget, "food", "2026-01-12").unwrap();
+        assert_eq!(mid.spent, 155.0);
+        assert_eq!(mid.remaining, 245.0);
+        assert_eq!(mid.days_left, 20);
+        assert_eq!(mid.per_day, 12.25);
+
+        // The last day counts itself
+        let last = daily_allowance(&expenses, &budget, "food", "2026-01-31").unwrap();
+        assert_eq!(last.days_left, 1);
+        assert_eq!(last.per_day, 245.0);
+        assert_eq!(
+            daily_allowance(&expenses, &budget, "food", "2026-02-28")
+                .unwrap()
+                .days_left,
+            1
+        );
+
+        assert_eq!(
+            daily_allowance(&expenses, &budget, "rent", "2026-01-12"),
+            None
+        );
+        assert_eq!(daily_allowance(&expenses, &budget, "food", "someday"), None);
+    }
+
+    #[test]
+    fn test_daily_allowance_when_over_budget() {
+        let mut budget = Budget::new("2026-01");
+        budget.set_limit("food", 100.0);
+        budget.set_limit("coffee", 20.0);
+        budget.set_period("coffee", Period::Weekly);
+        let expenses = vec![
+            Expense::new(130.0, "food", "2026-01-05"),
+            Expense::new(5.0, "coffee", "2026-01-06"),
+        ];
+
+        let over = daily_allowance(&expenses, &budget, "food", "2026-01-20").unwrap();
+        assert_eq!(over.remaining, 0.0);
+        assert_eq!(over.overage, 30.0);
+        assert_eq!(over.per_day, 0.0);
+
+        // A weekly limit runs to Sunday: Tuesday the 6th to the 11th
+        let week = daily_allowance(&expenses, &budget, "coffee", "2026-01-06").unwrap();
+        assert_eq!(week.days_left, 6);
+        assert_eq!(week.per_day, 2.5);
+        assert_eq!(week.overage, 0.0);
+    }
+}
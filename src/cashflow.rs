@@ -0,0 +1,92 @@
+// This module puts money received next to money spent, month by month, as
+// a cash-flow statement (`expense cashflow`)
+
+use std::collections::BTreeMap;
+
+use crate::dates::month_of;
+use crate::expense::{Expense, TransactionKind};
+
+/// One month of a cash-flow statement
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CashFlow {
+    /// Received in the month
+    pub income: f64,
+    /// Spent in the month
+    pub expenses: f64,
+    /// `income - expenses`
+    pub net: f64,
+    /// The opening balance plus the net of this and every earlier month
+    pub running_balance: f64,
+}
+
+/// Income, spending and the running balance for each month (`YYYY-MM`)
+/// that has any entries, in time order
+///
+/// The balance starts from `opening_balance` (0.0 if `None`). Entries whose
+/// date can't be read are left out.
+pub fn cash_flow_by_month(
+    expenses: &[Expense],
+    opening_balance: Option<f64>,
+) -> BTreeMap<String, CashFlow> {
+    let mut months: BTreeMap<String, CashFlow> = BTreeMap::new();
+    for expense in expenses {
+        let Some(month) = month_of(&expense.date) else {
+            continue;
+        };
+        let flow = months.entry(month.to_string()).or_insert(CashFlow {
+            income: 0.0,
+            expenses: 0.0,
+            net: 0.0,
+            running_balance: 0.0,
+        });
+        match expense.kind {
+            TransactionKind::Income => flow.income += expense.amount,
+            TransactionKind::Expense => flow.expenses += expense.amount,
+        }
+    }
+    let mut balance = opening_balance.unwrap_or(0.0);
+    for flow in months.values_mut() {
+        flow.net = flow.income - flow.expenses;
+        balance += flow.net;
+        flow.running_balance = balance;
+    }
+    months
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cash_flow_by_month() {
+        let expenses = vec![
+            Expense::new(2000.0, "salary", "2026-01-31").with_kind(TransactionKind::Income),
+            Expense::new(500.0, "rent", "2026-01-01"),
+            Expense::new(120.5, "food", "2026-01-12"),
+            Expense::new(80.0, "food", "2026-02-03"),
+            Expense::new(300.0, "freelance", "2026-03-15").with_kind(TransactionKind::Income),
+            Expense::new(10.0, "food", "someday"),
+        ];
+        let flows = cash_flow_by_month(&expenses, Some(100.0));
+        let months: Vec<&str> = flows.keys().map(String::as_str).collect();
+        assert_eq!(months, vec!["2026-01", "2026-02", "2026-03"]);
+        assert_eq!(
+            flows["2026-01"],
+            CashFlow {
+                income: 2000.0,
+                expenses: 620.5,
+                net: 1379.5,
+                running_balance: 1479.5,
+            }
+        );
+        // A month with only spending, then one with only income
+        assert_eq!(flows["2026-02"].net, -80.0);
+        assert_eq!(flows["2026-02"].running_balance, 1399.5);
+        assert_eq!(flows["2026-03"].expenses, 0.0);
+        assert_eq!(flows["2026-03"].running_balance, 1699.5);
+
+        let flows = cash_flow_by_month(&expenses, None);
+        assert_eq!(flows["2026-03"].running_balance, 1599.5);
+        assert!(cash_flow_by_month(&[], Some(100.0)).is_empty());
+    }
+}
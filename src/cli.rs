@@ -0,0 +1,2181 @@
+// This module implements the `expense` command line interface
+//
+// Commands that change the data file are split into a plan step, which
+// works out exactly which expenses are affected, and an apply step, which
+// carries that plan out. `--dry-run` prints the plan and stops, so the
+// preview is always what the real command would have done.
+
+use std::fmt;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::Path;
+
+use crate::cashflow::cash_flow_by_month;
+use crate::clock::Clock;
+use crate::config::{Config, DEFAULT_CONFIG_FILE};
+use crate::currency::format_amount;
+use crate::dates::{validate_date_format, validate_month_format, week_end, week_start};
+use crate::diff::{diff_expense_sets, format_diff, split_recorded};
+use crate::doctor::{
+    FindingKind, Severity, apply_safe_fixes, category_registry, diagnose, has_errors, read_rows,
+};
+use crate::error::ExpenseError;
+use crate::expense::{Expense, TransactionKind, parse_amount, validate_amount};
+use crate::format::{
+    DisplayOptions, GroupKey, format_cash_flow_table, render_grouped, render_report, render_table,
+};
+use crate::import::{
+    format_import_preview, format_import_summary, import_with_mapping, import_with_summary,
+    preview_imported, suggest_category, uncategorized,
+};
+use crate::journal::{
+    JournalAction, JournalEntry, changes_between, journal_path, load_journal, record_change,
+    save_journal, split_last_batch, undo_batch,
+};
+use crate::lock::{FileLock, LOCK_TIMEOUT};
+use crate::operations::*;
+use crate::storage::{
+    CsvOptions, load_from_file, read_next_id, save_to_file, to_csv, to_csv_with_next_id,
+    to_csv_with_options,
+};
+use crate::templates::add_from_template;
+use crate::tracker::check_period_open;
+
+/// Data file used when neither `--file` nor `EXPENSE_FILE` is given
+pub const DEFAULT_FILE: &str = "expenses.csv";
+
+const USAGE: &str = "usage: expense [--file PATH] [--config PATH] [--dry-run] [--yes] [--force]
+               [--verbose] [--color | --no-color] <command>
+
+commands:
+  add <amount> <category> <date> [--income]
+                                        record an expense, or money received
+  quick <template> [amount]             add today's expense from a template
+  list [--category C]... [--group-by category|date|month]
+                                        show all expenses, or those in any of
+                                        the given categories, optionally in
+                                        sections with subtotals
+  report                                summarize spending by category
+  cashflow [--opening AMOUNT]           show income, spending and the running
+                                        balance month by month
+  total [--today | --this-week | --this-month | --since DATE] [--raw]
+                                        print the total spent
+  count [--category C] [--raw]          print how many expenses there are
+  max [--category C] [--nonzero] [--raw]
+                                        show the most expensive expense
+  min [--category C] [--nonzero] [--raw]
+                                        show the cheapest expense, with
+                                        --nonzero skipping 0.00 entries
+  delete <id>...                        delete expenses by id
+  purge [--category C] [--before DATE]  delete every matching expense
+  merge <data-file>                     add expenses from another data file
+  diff <old-file> <new-file>            show what changed between two data files
+  import <csv-file> [--preset NAME] [--format json] [--preview]
+         [--delimiter C] [--decimal-comma]
+                                        add expenses from an exported CSV,
+                                        laid out as a preset in the config,
+                                        skipping ones already recorded;
+                                        --preview lists new rows, duplicates
+                                        and failed rows without importing;
+                                        the delimiter is detected unless given
+  export [--delimiter C] [--decimal-comma] [--no-header]
+                                        print the expenses as CSV, e.g. for a
+                                        spreadsheet expecting `;` and `12,50`
+  categorize                            pick a category for each uncategorized
+                                        expense: Enter takes the suggestion,
+                                        `s` skips, anything else is used as is
+  undo [--show]                         revert the last add, quick, delete,
+                                        purge, merge, import or categorize
+  doctor [--fix]                        check the data file for problems;
+                                        --fix tidies whitespace and case
+  close <YYYY-MM>                       refuse changes to the month's expenses
+                                        unless --force is given
+  reopen <YYYY-MM>                      allow changes to a closed month again
+  serve [--port N]                      run the HTTP JSON API (server builds)
+
+--verbose also logs what the command reads and writes to stderr
+--raw prints only the number (amounts as e.g. 23.50), for scripts";
+
+/// Everything that can stop a command
+#[derive(Debug)]
+pub enum CliError {
+    /// The command line could not be understood
+    Usage(String),
+    /// Loading, validating or saving expenses failed
+    Expense(ExpenseError),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Usage(msg) => write!(f, "{}\n\n{}", msg, USAGE),
+            CliError::Expense(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+impl From<ExpenseError> for CliError {
+    fn from(err: ExpenseError) -> Self {
+        CliError::Expense(err)
+    }
+}
+
+impl From<std::io::Error> for CliError {
+    fn from(err: std::io::Error) -> Self {
+        CliError::Expense(err.into())
+    }
+}
+
+/// Flags that apply to every command
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    /// Path of the CSV data file
+    pub file: String,
+    /// Path of the TOML config file
+    pub config: String,
+    /// Color categories in listings and reports
+    pub color: bool,
+    /// Print what a destructive command would do without writing anything
+    pub dry_run: bool,
+    /// Skip the confirmation prompt for changes touching several expenses
+    pub yes: bool,
+    /// Change expenses even in months closed with `expense close`
+    pub force: bool,
+    /// Print explanations around results instead of bare values
+    pub verbose: bool,
+}
+
+/// The stretch of time a command like `total` looks at
+#[derive(Debug, Clone, PartialEq)]
+pub enum Period {
+    /// Every expense
+    All,
+    /// Only today
+    Today,
+    /// The ISO week (Monday to Sunday) containing today
+    ThisWeek,
+    /// The calendar month containing today
+    ThisMonth,
+    /// From the given date onwards
+    Since(String),
+}
+
+impl Period {
+    /// The first and last date (both inclusive) of the period, given today
+    ///
+    /// `None` means the period is unbounded on that side.
+    pub fn resolve(&self, today: &str) -> Result<(Option<String>, Option<String>), CliError> {
+        let bad_today = || CliError::Usage(format!("clock returned a bad date `{}`", today));
+        match self {
+            Period::All => Ok((None, None)),
+            Period::Today => Ok((Some(today.to_string()), Some(today.to_string()))),
+            Period::ThisWeek => Ok((
+                Some(week_start(today).ok_or_else(bad_today)?),
+                Some(week_end(today).ok_or_else(bad_today)?),
+            )),
+            Period::ThisMonth => {
+                let month = today.get(..7).ok_or_else(bad_today)?;
+                Ok((Some(format!("{}-01", month)), Some(format!("{}-31", month))))
+            }
+            Period::Since(date) => Ok((Some(date.clone()), None)),
+        }
+    }
+
+    fn label(&self) -> String {
+        match self {
+            Period::All => "all time".to_string(),
+            Period::Today => "today".to_string(),
+            Period::ThisWeek => "this week".to_string(),
+            Period::ThisMonth => "this month".to_string(),
+            Period::Since(date) => format!("since {}", date),
+        }
+    }
+}
+
+/// A change to the expense list, worked out before anything is written
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Plan {
+    /// Expenses that will be deleted
+    pub remove: Vec<Expense>,
+    /// Expenses that will be added
+    pub add: Vec<Expense>,
+}
+
+impl Plan {
+    /// Number of expenses the plan deletes or adds
+    pub fn touched(&self) -> usize {
+        self.remove.len() + self.add.len()
+    }
+
+    /// Describe the plan with totals in `currency`, e.g. "delete 14
+    /// expenses totaling $230.10"
+    pub fn describe(&self, currency: &str) -> String {
+        self.describe_with("delete", "add", currency)
+    }
+
+    /// Describe the plan after it has been applied
+    pub fn describe_done(&self, currency: &str) -> String {
+        self.describe_with("deleted", "added", currency)
+    }
+
+    fn describe_with(&self, delete: &str, add: &str, currency: &str) -> String {
+        let mut parts = Vec::new();
+        if !self.remove.is_empty() {
+            parts.push(format!(
+                "{} {}",
+                delete,
+                count_and_total(&self.remove, currency)
+            ));
+        }
+        if !self.add.is_empty() {
+            parts.push(format!("{} {}", add, count_and_total(&self.add, currency)));
+        }
+        if parts.is_empty() {
+            return "nothing to do".to_string();
+        }
+        parts.join(" and ")
+    }
+
+    /// Carry out the plan on the expense list
+    pub fn apply(self, expenses: &mut Vec<Expense>) {
+        expenses.retain(|e| !self.remove.iter().any(|r| r.id == e.id));
+        expenses.extend(self.add);
+    }
+}
+
+fn count_and_total(expenses: &[Expense], currency: &str) -> String {
+    let noun = if expenses.len() == 1 {
+        "expense"
+    } else {
+        "expenses"
+    };
+    format!(
+        "{} {} totaling {}",
+        expenses.len(),
+        noun,
+        format_amount(calculate_total(expenses), currency)
+    )
+}
+
+/// Plan deleting the expenses with the given ids
+pub fn plan_delete(expenses: &[Expense], ids: &[u64]) -> Result<Plan, CliError> {
+    let mut remove = Vec::new();
+    for id in ids {
+        match expenses.iter().find(|e| e.id == *id) {
+            Some(expense) => {
+                if !remove.contains(expense) {
+                    remove.push(expense.clone());
+                }
+            }
+            None => return Err(CliError::Usage(format!("no expense with id {}", id))),
+        }
+    }
+    Ok(Plan {
+        remove,
+        add: Vec::new(),
+    })
+}
+
+/// Plan deleting every expense in `category` (if given) dated before
+/// `before` (if given)
+pub fn plan_purge(expenses: &[Expense], category: Option<&str>, before: Option<&str>) -> Plan {
+    let remove = expenses
+        .iter()
+        .filter(|e| category.is_none_or(|c| e.category == c))
+        .filter(|e| before.is_none_or(|d| e.date.as_str() < d))
+        .cloned()
+        .collect();
+    Plan {
+        remove,
+        add: Vec::new(),
+    }
+}
+
+/// Plan adding the expenses from `other` that are not already recorded
+///
+/// An expense counts as already recorded when one with the same amount,
+/// category and date exists (see `split_recorded`). Merged expenses get
+/// fresh ids and keep everything else.
+pub fn plan_merge(expenses: &[Expense], other: &[Expense]) -> Plan {
+    let (new, _) = split_recorded(expenses, other);
+    let add = new
+        .into_iter()
+        .map(|expense| expense.clone().with_fresh_id())
+        .collect();
+    Plan {
+        remove: Vec::new(),
+        add,
+    }
+}
+
+/// Plan adding the imported expenses that are not already recorded, the
+/// same ones `preview_import` lists as new
+pub fn plan_import(expenses: &[Expense], imported: &[Expense]) -> Plan {
+    let (new, _) = split_recorded(expenses, imported);
+    Plan {
+        remove: Vec::new(),
+        add: new.into_iter().cloned().collect(),
+    }
+}
+
+/// Run the command line `args` (without the program name)
+///
+/// `clock` supplies today's date to commands that need it.
+pub fn run(
+    args: &[String],
+    clock: &dyn Clock,
+    input: &mut dyn BufRead,
+    out: &mut dyn Write,
+) -> Result<(), CliError> {
+    let (options, mut rest) = parse_options(args)?;
+    if rest.is_empty() {
+        return Err(CliError::Usage("missing command".to_string()));
+    }
+    let command = rest.remove(0);
+    // Held until the command is done, so a concurrent `expense add` can't
+    // load the file before this one saves it. `serve` keeps it for as long
+    // as it runs, since every change made over HTTP rewrites the file.
+    let _lock = match command.as_str() {
+        "add" | "quick" | "delete" | "purge" | "merge" | "import" | "categorize" | "undo"
+        | "doctor" | "serve" => Some(FileLock::acquire(&options.file, LOCK_TIMEOUT)?),
+        _ => None,
+    };
+
+    match command.as_str() {
+        "add" => cmd_add(&options, &rest, out),
+        "quick" => cmd_quick(&options, &rest, clock, out),
+        "list" => cmd_list(&options, &rest, out),
+        "report" => {
+            expect_no_more(&rest)?;
+            let expenses = load(&options)?;
+            write!(
+                out,
+                "{}",
+                render_report(&expenses, &display_options(&options)?)
+            )?;
+            Ok(())
+        }
+        "cashflow" => {
+            // Negative for an account that starts overdrawn
+            let opening = take_value(&mut rest, "--opening")?
+                .map(|amount| {
+                    parse_amount(&amount)
+                        .map_err(|_| CliError::Usage(format!("bad amount `{}`", amount)))
+                })
+                .transpose()?;
+            expect_no_more(&rest)?;
+            let flows = cash_flow_by_month(&load(&options)?, opening);
+            write!(
+                out,
+                "{}",
+                format_cash_flow_table(&flows, &display_options(&options)?)
+            )?;
+            Ok(())
+        }
+        "total" => cmd_total(&options, &rest, clock, out),
+        "count" => cmd_count(&options, &rest, out),
+        "max" => cmd_extreme(&options, &rest, Extreme::Max, out),
+        "min" => cmd_extreme(&options, &rest, Extreme::Min, out),
+        "delete" => {
+            let expenses = load(&options)?;
+            let ids = rest
+                .iter()
+                .map(|id| {
+                    id.parse::<u64>()
+                        .map_err(|_| CliError::Usage(format!("bad id `{}`", id)))
+                })
+                .collect::<Result<Vec<u64>, CliError>>()?;
+            if ids.is_empty() {
+                return Err(CliError::Usage("delete needs at least one id".to_string()));
+            }
+            let plan = plan_delete(&expenses, &ids)?;
+            execute(&options, "delete", expenses, plan, input, out)?;
+            Ok(())
+        }
+        "purge" => {
+            let category = take_value(&mut rest, "--category")?;
+            let before = take_value(&mut rest, "--before")?;
+            expect_no_more(&rest)?;
+            if category.is_none() && before.is_none() {
+                return Err(CliError::Usage(
+                    "purge needs --category and/or --before".to_string(),
+                ));
+            }
+            let expenses = load(&options)?;
+            let plan = plan_purge(&expenses, category.as_deref(), before.as_deref());
+            execute(&options, "purge", expenses, plan, input, out)?;
+            Ok(())
+        }
+        "merge" => {
+            let path = single_arg(&rest, "merge needs a data file")?;
+            let expenses = load(&options)?;
+            let other = load_from_file(path)?;
+            let plan = plan_merge(&expenses, &other);
+            execute(&options, "merge", expenses, plan, input, out)?;
+            Ok(())
+        }
+        "export" => {
+            let csv = CsvOptions {
+                delimiter: take_delimiter(&mut rest)?.unwrap_or(','),
+                decimal_separator: if take_flag(&mut rest, "--decimal-comma") {
+                    ','
+                } else {
+                    '.'
+                },
+                include_header: !take_flag(&mut rest, "--no-header"),
+            };
+            expect_no_more(&rest)?;
+            write!(out, "{}", to_csv_with_options(&load(&options)?, &csv))?;
+            Ok(())
+        }
+        "diff" => {
+            let [old, new] = rest.as_slice() else {
+                return Err(CliError::Usage(
+                    "diff needs <old-file> <new-file>".to_string(),
+                ));
+            };
+            let (old, new) = (load_from_file(old)?, load_from_file(new)?);
+            write!(out, "{}", format_diff(&diff_expense_sets(&old, &new)))?;
+            Ok(())
+        }
+        "import" => {
+            let json = match take_value(&mut rest, "--format")?.as_deref() {
+                None | Some("text") => false,
+                Some("json") => true,
+                Some(other) => return Err(CliError::Usage(format!("unknown format `{}`", other))),
+            };
+            let preset = take_value(&mut rest, "--preset")?;
+            let preview = take_flag(&mut rest, "--preview");
+            let delimiter = take_delimiter(&mut rest)?;
+            let decimal_comma = take_flag(&mut rest, "--decimal-comma");
+            let path = single_arg(&rest, "import needs a CSV file")?;
+            let config = Config::load(&options.config)?;
+            let rules = &config.auto_categories;
+            let mapping = preset.map(|name| config.import_preset(&name)).transpose()?;
+            let expenses = load(&options)?;
+            let text = std::fs::read_to_string(path)?;
+            // What isn't given is still detected
+            let csv = (delimiter.is_some() || decimal_comma).then(|| {
+                let mut csv = CsvOptions::detect(&text);
+                if let Some(delimiter) = delimiter {
+                    csv.delimiter = delimiter;
+                    csv.decimal_separator = '.';
+                }
+                if decimal_comma {
+                    csv.decimal_separator = ',';
+                }
+                csv
+            });
+            let (imported, mut summary) = match mapping {
+                Some(mapping) => import_with_mapping(&text, mapping, csv, rules)?,
+                None => import_with_summary(&text, csv, rules)?,
+            };
+            if preview {
+                let preview = preview_imported(&expenses, &imported, &summary);
+                if json {
+                    let json = serde_json::to_string_pretty(&preview)
+                        .map_err(|e| ExpenseError::ParseError(e.to_string()))?;
+                    writeln!(out, "{}", json)?;
+                } else {
+                    write!(out, "{}", format_import_preview(&preview))?;
+                }
+                return Ok(());
+            }
+            let plan = plan_import(&expenses, &imported);
+            summary.recount(&plan.add);
+            if json {
+                // A prompt would be lost in the JSON, so there is nobody to ask
+                if needs_confirmation(&options, &plan) {
+                    return Err(CliError::Usage(
+                        "--format json can't ask for confirmation; add --yes or --dry-run"
+                            .to_string(),
+                    ));
+                }
+                // Keep stdout machine-readable: the plan outcome goes nowhere
+                let applied = execute(
+                    &options,
+                    "import",
+                    expenses,
+                    plan,
+                    input,
+                    &mut std::io::sink(),
+                )?;
+                let mut json = serde_json::to_value(&summary)
+                    .map_err(|e| ExpenseError::ParseError(e.to_string()))?;
+                json["applied"] = applied.into();
+                let json = serde_json::to_string_pretty(&json)
+                    .map_err(|e| ExpenseError::ParseError(e.to_string()))?;
+                writeln!(out, "{}", json)?;
+            } else if execute(&options, "import", expenses, plan, input, out)? {
+                let currency = config.default_currency();
+                write!(out, "{}", format_import_summary(&summary, currency))?;
+            }
+            Ok(())
+        }
+        "categorize" => {
+            expect_no_more(&rest)?;
+            cmd_categorize(&options, input, out)
+        }
+        "undo" => cmd_undo(&options, &rest, out),
+        "close" | "reopen" => cmd_close(&options, &command, &rest, out),
+        "doctor" => cmd_doctor(&options, &rest, clock, out),
+        "serve" => cmd_serve(&options, &mut rest, out),
+        "help" | "--help" | "-h" => {
+            writeln!(out, "{}", USAGE)?;
+            Ok(())
+        }
+        other => Err(CliError::Usage(format!("unknown command `{}`", other))),
+    }
+}
+
+/// Split the global flags off the front of the command line
+fn parse_options(args: &[String]) -> Result<(Options, Vec<String>), CliError> {
+    let mut options = Options {
+        file: std::env::var("EXPENSE_FILE").unwrap_or_else(|_| DEFAULT_FILE.to_string()),
+        config: std::env::var("EXPENSE_CONFIG").unwrap_or_else(|_| DEFAULT_CONFIG_FILE.to_string()),
+        color: std::env::var_os("NO_COLOR").is_none() && std::io::stdout().is_terminal(),
+        dry_run: false,
+        yes: false,
+        force: false,
+        verbose: false,
+    };
+    let mut rest = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--file" => match args.next() {
+                Some(path) => options.file = path.clone(),
+                None => return Err(CliError::Usage("--file needs a path".to_string())),
+            },
+            "--config" => match args.next() {
+                Some(path) => options.config = path.clone(),
+                None => return Err(CliError::Usage("--config needs a path".to_string())),
+            },
+            "--color" => options.color = true,
+            "--no-color" => options.color = false,
+            "--dry-run" => options.dry_run = true,
+            "--yes" | "-y" => options.yes = true,
+            "--force" => options.force = true,
+            "--verbose" | "-v" => options.verbose = true,
+            _ => rest.push(arg.clone()),
+        }
+    }
+    Ok((options, rest))
+}
+
+/// Writes log records to stderr as `level: message` lines
+struct StderrLogger;
+
+impl log::Log for StderrLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            let level = record.level().as_str().to_ascii_lowercase();
+            eprintln!("{}: {}", level, record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static STDERR_LOGGER: StderrLogger = StderrLogger;
+
+/// Log to stderr at debug level if `--verbose` is on the command line
+///
+/// Call once before `run`. Without `--verbose` no logger is installed, so
+/// nothing is logged.
+pub fn init_logging(args: &[String]) {
+    let verbose = parse_options(args).is_ok_and(|(options, _)| options.verbose);
+    if verbose && log::set_logger(&STDERR_LOGGER).is_ok() {
+        log::set_max_level(log::LevelFilter::Debug);
+    }
+}
+
+/// Remove `flag VALUE` from the arguments, returning the value
+fn take_value(args: &mut Vec<String>, flag: &str) -> Result<Option<String>, CliError> {
+    match args.iter().position(|a| a == flag) {
+        Some(i) if i + 1 < args.len() => {
+            let value = args.remove(i + 1);
+            args.remove(i);
+            Ok(Some(value))
+        }
+        Some(_) => Err(CliError::Usage(format!("{} needs a value", flag))),
+        None => Ok(None),
+    }
+}
+
+/// Remove every use of a flag that takes a value, returning the values in
+/// order
+fn take_values(args: &mut Vec<String>, flag: &str) -> Result<Vec<String>, CliError> {
+    let mut values = Vec::new();
+    while let Some(value) = take_value(args, flag)? {
+        values.push(value);
+    }
+    Ok(values)
+}
+
+/// Remove a flag without a value, saying whether it was there
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    let before = args.len();
+    args.retain(|a| a != flag);
+    args.len() != before
+}
+
+/// Take `--delimiter C`: one character, or `tab`
+fn take_delimiter(args: &mut Vec<String>) -> Result<Option<char>, CliError> {
+    let Some(value) = take_value(args, "--delimiter")? else {
+        return Ok(None);
+    };
+    let mut chars = value.chars();
+    match (value.as_str(), chars.next(), chars.next()) {
+        ("tab" | "\\t", _, _) => Ok(Some('\t')),
+        (_, Some(c), None) if !matches!(c, '"' | '\n' | '\r') => Ok(Some(c)),
+        _ => Err(CliError::Usage(format!("bad delimiter `{}`", value))),
+    }
+}
+
+fn expect_no_more(args: &[String]) -> Result<(), CliError> {
+    match args.first() {
+        Some(arg) => Err(CliError::Usage(format!("unexpected argument `{}`", arg))),
+        None => Ok(()),
+    }
+}
+
+fn single_arg<'a>(args: &'a [String], missing: &str) -> Result<&'a str, CliError> {
+    match args {
+        [arg] => Ok(arg),
+        [] => Err(CliError::Usage(missing.to_string())),
+        [_, extra, ..] => Err(CliError::Usage(format!("unexpected argument `{}`", extra))),
+    }
+}
+
+/// Load the data file, treating a missing file as an empty list
+fn load(options: &Options) -> Result<Vec<Expense>, CliError> {
+    if !Path::new(&options.file).exists() {
+        return Ok(Vec::new());
+    }
+    Ok(load_from_file(&options.file)?)
+}
+
+/// Save the data file after `command` changed `before` into `after`,
+/// journaling the change so `undo` can revert it
+fn save(
+    options: &Options,
+    command: &str,
+    before: &[Expense],
+    after: &[Expense],
+) -> Result<(), CliError> {
+    check_open(options, &changes_between(0, command, None, before, after))?;
+    let next_id = match std::fs::read_to_string(&options.file) {
+        Ok(text) => read_next_id(&text)?,
+        Err(_) => None,
+    };
+    save_to_file(after, &options.file)?;
+    record_change(
+        &journal_path(&options.file),
+        command,
+        next_id,
+        before,
+        after,
+    )?;
+    Ok(())
+}
+
+/// Refuse, unless `--force` is given, changes that touch an expense dated
+/// in a closed month
+fn check_open(options: &Options, changes: &[JournalEntry]) -> Result<(), CliError> {
+    if options.force || changes.is_empty() {
+        return Ok(());
+    }
+    let closed = Config::load(&options.config)?.closed_periods;
+    for entry in changes {
+        check_period_open(&closed, &entry.expense.date)?;
+    }
+    Ok(())
+}
+
+/// Rendering settings from the config file and the color flags
+fn display_options(options: &Options) -> Result<DisplayOptions, CliError> {
+    Ok(DisplayOptions {
+        color: options.color,
+        config: Config::load(&options.config)?,
+    })
+}
+
+/// Print, confirm and apply a plan according to the global flags, saying
+/// whether it was applied
+///
+/// It isn't with nothing to do, with `--dry-run`, or when the answer to
+/// the confirmation is no.
+fn execute(
+    options: &Options,
+    command: &str,
+    mut expenses: Vec<Expense>,
+    plan: Plan,
+    input: &mut dyn BufRead,
+    out: &mut dyn Write,
+) -> Result<bool, CliError> {
+    let config = Config::load(&options.config)?;
+    let currency = config.default_currency();
+    if plan.touched() == 0 {
+        writeln!(out, "{}", plan.describe(currency))?;
+        return Ok(false);
+    }
+    if options.dry_run {
+        writeln!(out, "would {}", plan.describe(currency))?;
+        return Ok(false);
+    }
+    if needs_confirmation(options, &plan) && !confirm(&plan, currency, input, out)? {
+        writeln!(out, "aborted")?;
+        return Ok(false);
+    }
+    let done = plan.describe_done(currency);
+    let before = expenses.clone();
+    plan.apply(&mut expenses);
+    save(options, command, &before, &expenses)?;
+    writeln!(out, "{}", done)?;
+    Ok(true)
+}
+
+/// Whether `execute` would ask before applying `plan`
+fn needs_confirmation(options: &Options, plan: &Plan) -> bool {
+    plan.touched() > 1 && !options.yes && !options.dry_run
+}
+
+fn confirm(
+    plan: &Plan,
+    currency: &str,
+    input: &mut dyn BufRead,
+    out: &mut dyn Write,
+) -> Result<bool, CliError> {
+    write!(out, "{}? [y/N] ", plan.describe(currency))?;
+    out.flush()?;
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+/// The amount of an expense being added: a number, and not a negative one
+/// (unlike a refund read from a statement)
+fn parse_new_amount(text: &str) -> Result<f64, CliError> {
+    let amount =
+        parse_amount(text).map_err(|_| CliError::Usage(format!("bad amount `{}`", text)))?;
+    validate_amount(amount)?;
+    Ok(amount)
+}
+
+fn cmd_add(options: &Options, args: &[String], out: &mut dyn Write) -> Result<(), CliError> {
+    let mut args = args.to_vec();
+    let income = take_flag(&mut args, "--income");
+    let [amount, category, date] = &args[..] else {
+        return Err(CliError::Usage(
+            "add needs <amount> <category> <date>".to_string(),
+        ));
+    };
+    let amount = parse_new_amount(amount)?;
+    validate_date_format(date)?;
+    let mut expenses = load(options)?;
+    let before = expenses.clone();
+    add_expense(&mut expenses, amount, category, date);
+    if income && let Some(added) = expenses.last_mut() {
+        added.kind = TransactionKind::Income;
+    }
+    save(options, "add", &before, &expenses)?;
+    writeln!(out, "added #{}", expenses[expenses.len() - 1].id)?;
+    Ok(())
+}
+
+fn cmd_quick(
+    options: &Options,
+    args: &[String],
+    clock: &dyn Clock,
+    out: &mut dyn Write,
+) -> Result<(), CliError> {
+    let (name, amount) = match args {
+        [name] => (name, None),
+        [name, amount] => (name, Some(parse_new_amount(amount)?)),
+        _ => {
+            return Err(CliError::Usage(
+                "quick needs <template> [amount]".to_string(),
+            ));
+        }
+    };
+    let config = Config::load(&options.config)?;
+    let mut expenses = load(options)?;
+    let before = expenses.clone();
+    let added = add_from_template(
+        &mut expenses,
+        &config.templates,
+        name,
+        &clock.today(),
+        amount,
+    )?;
+    // Said only once the save went through
+    let done = format!("added #{} {}", added.id, added);
+    save(options, "quick", &before, &expenses)?;
+    writeln!(out, "{}", done)?;
+    Ok(())
+}
+
+fn cmd_list(options: &Options, args: &[String], out: &mut dyn Write) -> Result<(), CliError> {
+    let mut args = args.to_vec();
+    let categories = take_values(&mut args, "--category")?;
+    let group_by = match take_value(&mut args, "--group-by")?.as_deref() {
+        None => None,
+        Some("category") => Some(GroupKey::Category),
+        Some("date") => Some(GroupKey::Date),
+        Some("month") => Some(GroupKey::Month),
+        Some(other) => return Err(CliError::Usage(format!("can't group by `{}`", other))),
+    };
+    expect_no_more(&args)?;
+    let expenses = load(options)?;
+    let refs: Vec<&Expense> = if categories.is_empty() {
+        expenses.iter().collect()
+    } else {
+        let categories: Vec<&str> = categories.iter().map(String::as_str).collect();
+        get_by_categories(&expenses, &categories)
+    };
+    let display = display_options(options)?;
+    match group_by {
+        Some(key) => write!(out, "{}", render_grouped(&refs, key, &display))?,
+        None => write!(out, "{}", render_table(&refs, &display))?,
+    }
+    Ok(())
+}
+
+/// Revert the latest journaled change, or with `--show` (or `--dry-run`)
+/// list what reverting it would do
+///
+/// The data file goes back to exactly what it was before the change,
+/// including its `# next_id=` line.
+fn cmd_undo(options: &Options, args: &[String], out: &mut dyn Write) -> Result<(), CliError> {
+    let mut args = args.to_vec();
+    let show = take_flag(&mut args, "--show");
+    expect_no_more(&args)?;
+    let path = journal_path(&options.file);
+    let mut entries = load_journal(&path)?;
+    let batch = split_last_batch(&mut entries);
+    let Some(first) = batch.first() else {
+        return Err(ExpenseError::NotFound("nothing to undo".to_string()).into());
+    };
+    let expenses = load(options)?;
+    let restored = undo_batch(&expenses, &batch)?;
+
+    // Undoing deletes what the change added and adds back what it removed
+    let mut plan = Plan::default();
+    for entry in &batch {
+        match entry.action {
+            JournalAction::Added => plan.remove.push(entry.expense.clone()),
+            JournalAction::Removed => plan.add.push(entry.expense.clone()),
+        }
+    }
+    let config = Config::load(&options.config)?;
+    let currency = config.default_currency();
+    if show || options.dry_run {
+        writeln!(
+            out,
+            "would undo {}: {}",
+            first.command,
+            plan.describe(currency)
+        )?;
+        for expense in &plan.remove {
+            writeln!(out, "- #{} {}", expense.id, expense)?;
+        }
+        for expense in &plan.add {
+            writeln!(out, "+ #{} {}", expense.id, expense)?;
+        }
+        return Ok(());
+    }
+    check_open(options, &batch)?;
+    let text = match first.next_id {
+        Some(next_id) => to_csv_with_next_id(&restored, next_id),
+        None => to_csv(&restored),
+    };
+    std::fs::write(&options.file, text)?;
+    save_journal(&path, &entries)?;
+    writeln!(
+        out,
+        "undid {}: {}",
+        first.command,
+        plan.describe_done(currency)
+    )?;
+    Ok(())
+}
+
+/// Ask for a category for each uncategorized expense in turn, offering the
+/// one its note (or payee) suggests
+///
+/// An empty answer takes the suggestion, or skips if there is none; `s`
+/// skips; anything else becomes the category. Each answer is saved right
+/// away, so stopping halfway (or at the end of input) keeps what was done.
+fn cmd_categorize(
+    options: &Options,
+    input: &mut dyn BufRead,
+    out: &mut dyn Write,
+) -> Result<(), CliError> {
+    let rules = Config::load(&options.config)?.auto_categories;
+    let mut expenses = load(options)?;
+    let ids: Vec<u64> = uncategorized(&expenses).iter().map(|e| e.id).collect();
+    let (mut categorized, mut skipped) = (0, 0);
+    for id in ids {
+        let Some(index) = expenses.iter().position(|e| e.id == id) else {
+            continue;
+        };
+        let expense = &expenses[index];
+        let description = expense.note.as_deref().or(expense.payee.as_deref());
+        let suggestion = description.and_then(|d| suggest_category(d, &rules));
+        match description {
+            Some(description) => writeln!(out, "#{} {} {}", id, expense, description)?,
+            None => writeln!(out, "#{} {}", id, expense)?,
+        }
+        match &suggestion {
+            Some(suggestion) => write!(out, "category [{}]: ", suggestion)?,
+            None => write!(out, "category (Enter skips): ")?,
+        }
+        out.flush()?;
+
+        let mut answer = String::new();
+        if input.read_line(&mut answer)? == 0 {
+            writeln!(out)?;
+            break;
+        }
+        let category = match answer.trim() {
+            "" => suggestion,
+            "s" => None,
+            typed => Some(typed.to_string()),
+        };
+        let Some(category) = category else {
+            skipped += 1;
+            continue;
+        };
+        if !options.dry_run {
+            let before = expenses.clone();
+            expenses[index].category = category;
+            save(options, "categorize", &before, &expenses)?;
+        }
+        categorized += 1;
+    }
+    let verb = if options.dry_run {
+        "would categorize"
+    } else {
+        "categorized"
+    };
+    writeln!(out, "{} {}, skipped {}", verb, categorized, skipped)?;
+    Ok(())
+}
+
+/// Check the data file, print what was found and fail if any finding is an
+/// error; with `--fix`, tidy whitespace and category case first
+fn cmd_doctor(
+    options: &Options,
+    args: &[String],
+    clock: &dyn Clock,
+    out: &mut dyn Write,
+) -> Result<(), CliError> {
+    let mut args = args.to_vec();
+    let fix = take_flag(&mut args, "--fix");
+    expect_no_more(&args)?;
+    let config = Config::load(&options.config)?;
+    let text = if Path::new(&options.file).exists() {
+        std::fs::read_to_string(&options.file)?
+    } else {
+        String::new()
+    };
+    let (mut expenses, mut findings) = read_rows(&text);
+    let diagnose = |expenses: &[Expense]| {
+        diagnose(
+            expenses,
+            &category_registry(&config),
+            &config,
+            &clock.today(),
+        )
+    };
+    findings.extend(diagnose(&expenses));
+
+    if fix && findings.iter().any(|f| f.kind == FindingKind::Untidy) {
+        // Saving would drop the rows that couldn't be read
+        if findings
+            .iter()
+            .any(|f| f.kind == FindingKind::UnparseableRow)
+        {
+            return Err(CliError::Usage(
+                "fix the unreadable rows by hand before using --fix".to_string(),
+            ));
+        }
+        let before = expenses.clone();
+        let fixed = apply_safe_fixes(&mut expenses);
+        if !options.dry_run {
+            save(options, "doctor", &before, &expenses)?;
+        }
+        let verb = if options.dry_run {
+            "would fix"
+        } else {
+            "fixed"
+        };
+        let noun = if fixed == 1 { "expense" } else { "expenses" };
+        writeln!(out, "{} {} {}", verb, fixed, noun)?;
+        findings.retain(|f| f.kind == FindingKind::UnparseableRow);
+        findings.extend(diagnose(&expenses));
+    }
+
+    for finding in &findings {
+        writeln!(out, "{}", finding)?;
+    }
+    let count = |severity| findings.iter().filter(|f| f.severity == severity).count();
+    let errors = count(Severity::Error);
+    if findings.is_empty() {
+        writeln!(out, "no problems found")?;
+    } else {
+        writeln!(
+            out,
+            "{} errors, {} warnings, {} notes",
+            errors,
+            count(Severity::Warning),
+            count(Severity::Info)
+        )?;
+    }
+    if has_errors(&findings) {
+        return Err(
+            ExpenseError::ParseError(format!("{} has {} errors", options.file, errors)).into(),
+        );
+    }
+    Ok(())
+}
+
+/// Close or reopen a month, recording it in the config file
+fn cmd_close(
+    options: &Options,
+    command: &str,
+    args: &[String],
+    out: &mut dyn Write,
+) -> Result<(), CliError> {
+    let month = single_arg(args, &format!("{} needs a YYYY-MM month", command))?;
+    validate_month_format(month)?;
+    let mut config = Config::load(&options.config)?;
+    let (changed, done, state) = if command == "close" {
+        let changed = config.closed_periods.insert(month.to_string());
+        (changed, "closed", "closed")
+    } else {
+        (config.closed_periods.remove(month), "reopened", "open")
+    };
+    if !changed {
+        writeln!(out, "{} is already {}", month, state)?;
+        return Ok(());
+    }
+    config.save(&options.config)?;
+    writeln!(out, "{} {}", done, month)?;
+    Ok(())
+}
+
+/// Serve the data file over HTTP until the process is stopped
+#[cfg(feature = "server")]
+fn cmd_serve(
+    options: &Options,
+    rest: &mut Vec<String>,
+    out: &mut dyn Write,
+) -> Result<(), CliError> {
+    use crate::server::{ServerState, serve};
+
+    let port = match take_value(rest, "--port")? {
+        Some(port) => port
+            .parse::<u16>()
+            .map_err(|_| CliError::Usage(format!("bad port `{}`", port)))?,
+        None => 8080,
+    };
+    expect_no_more(rest)?;
+    let tracker = served_tracker(options)?;
+    let listener = std::net::TcpListener::bind(("127.0.0.1", port))?;
+    writeln!(out, "Listening on http://{}", listener.local_addr()?)?;
+    out.flush()?;
+    tracker.autosave(&options.file);
+    let state = ServerState { tracker };
+    Ok(serve(listener, state)?)
+}
+
+/// The data file as `serve` works on it, with the months closed in the
+/// config kept closed over HTTP too
+#[cfg(feature = "server")]
+fn served_tracker(options: &Options) -> Result<crate::tracker::SharedTracker, CliError> {
+    use crate::tracker::SharedTracker;
+
+    let tracker = if Path::new(&options.file).exists() {
+        SharedTracker::load(&options.file)?
+    } else {
+        SharedTracker::new()
+    };
+    tracker.set_closed_periods(Config::load(&options.config)?.closed_periods);
+    Ok(tracker)
+}
+
+#[cfg(not(feature = "server"))]
+fn cmd_serve(_: &Options, _: &mut Vec<String>, _: &mut dyn Write) -> Result<(), CliError> {
+    Err(CliError::Usage(
+        "this build has no HTTP server (rebuild with `--features server`)".to_string(),
+    ))
+}
+
+fn cmd_total(
+    options: &Options,
+    args: &[String],
+    clock: &dyn Clock,
+    out: &mut dyn Write,
+) -> Result<(), CliError> {
+    let mut args = args.to_vec();
+    let raw = take_flag(&mut args, "--raw");
+    let period = parse_period(&args)?;
+    let expenses = load(options)?;
+    let (from, to) = period.resolve(&clock.today())?;
+    // Income isn't spending
+    let total: f64 = expenses
+        .iter()
+        .filter(|e| e.kind.is_expense())
+        .filter(|e| from.as_deref().is_none_or(|from| e.date.as_str() >= from))
+        .filter(|e| to.as_deref().is_none_or(|to| e.date.as_str() <= to))
+        .map(|e| e.amount)
+        .sum();
+
+    if options.verbose && !raw {
+        let range = match (&from, &to) {
+            (Some(from), Some(to)) if from == to => format!(" ({})", from),
+            (Some(from), Some(to)) => format!(" ({} to {})", from, to),
+            _ => String::new(),
+        };
+        let config = Config::load(&options.config)?;
+        let total = format_amount(total, config.default_currency());
+        writeln!(out, "Total {}{}: {}", period.label(), range, total)?;
+    } else {
+        writeln!(out, "{}", raw_amount(total))?;
+    }
+    Ok(())
+}
+
+/// An amount as `--raw` prints it: two decimals after a dot, no grouping
+/// and no currency, whatever the locale
+fn raw_amount(amount: f64) -> String {
+    format!("{:.2}", amount)
+}
+
+fn cmd_count(options: &Options, args: &[String], out: &mut dyn Write) -> Result<(), CliError> {
+    let mut args = args.to_vec();
+    let raw = take_flag(&mut args, "--raw");
+    let category = take_value(&mut args, "--category")?;
+    expect_no_more(&args)?;
+    let expenses = load(options)?;
+    let count = match &category {
+        Some(category) => count_by_category(&expenses, category),
+        None => expenses.len(),
+    };
+
+    if options.verbose && !raw {
+        let noun = if count == 1 { "expense" } else { "expenses" };
+        match &category {
+            Some(category) => writeln!(out, "{} {} {}", count, category, noun)?,
+            None => writeln!(out, "{} {}", count, noun)?,
+        }
+    } else {
+        writeln!(out, "{}", count)?;
+    }
+    Ok(())
+}
+
+/// `max` or `min`: show the most or least expensive expense, or with
+/// `--raw` just its amount
+fn cmd_extreme(
+    options: &Options,
+    args: &[String],
+    which: Extreme,
+    out: &mut dyn Write,
+) -> Result<(), CliError> {
+    let mut args = args.to_vec();
+    let raw = take_flag(&mut args, "--raw");
+    let ignore_zero = take_flag(&mut args, "--nonzero");
+    let category = take_value(&mut args, "--category")?;
+    expect_no_more(&args)?;
+    let expenses = load(options)?;
+    let opts = MinMaxOptions {
+        ignore_zero,
+        category: category.as_deref(),
+    };
+    let expense = extreme_expense(&expenses, which, opts).ok_or_else(|| match &category {
+        Some(category) => ExpenseError::NotFound(format!("no {} expenses", category)),
+        None => ExpenseError::NotFound("no expenses".to_string()),
+    })?;
+
+    if raw {
+        writeln!(out, "{}", raw_amount(expense.amount))?;
+    } else {
+        writeln!(out, "#{} {}", expense.id, expense)?;
+    }
+    Ok(())
+}
+
+fn parse_period(args: &[String]) -> Result<Period, CliError> {
+    let mut periods = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        periods.push(match arg.as_str() {
+            "--today" => Period::Today,
+            "--this-week" => Period::ThisWeek,
+            "--this-month" => Period::ThisMonth,
+            "--since" => match args.next() {
+                Some(date) => Period::Since(date.clone()),
+                None => return Err(CliError::Usage("--since needs a date".to_string())),
+            },
+            other => return Err(CliError::Usage(format!("unexpected argument `{}`", other))),
+        });
+    }
+    match periods.len() {
+        0 => Ok(Period::All),
+        1 => Ok(periods.remove(0)),
+        _ => Err(CliError::Usage(
+            "choose only one period for total".to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FixedClock;
+    use std::time::Duration;
+
+    struct TempFile(String);
+
+    impl TempFile {
+        fn new(name: &str) -> TempFile {
+            let path =
+                std::env::temp_dir().join(format!("cli_{}_{}.csv", name, std::process::id()));
+            TempFile(path.to_str().unwrap().to_string())
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+            let _ = std::fs::remove_file(journal_path(&self.0));
+        }
+    }
+
+    fn run_with(args: &[&str], input: &str) -> Result<String, CliError> {
+        run_on(args, input, "2026-01-08")
+    }
+
+    fn run_on(args: &[&str], input: &str, today: &str) -> Result<String, CliError> {
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        let mut out = Vec::new();
+        run(
+            &args,
+            &FixedClock::new(today),
+            &mut input.as_bytes(),
+            &mut out,
+        )?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    fn sample() -> Vec<Expense> {
+        vec![
+            Expense::new(45.5, "food", "2026-01-08"),
+            Expense::new(20.0, "transport", "2026-01-08"),
+            Expense::new(30.0, "food", "2026-01-07"),
+            Expense::new(12.25, "food", "2026-02-01"),
+        ]
+    }
+
+    #[test]
+    fn test_purge_plan_matches_what_apply_removes() {
+        let before = sample();
+        let plan = plan_purge(&before, Some("food"), Some("2026-02-01"));
+        let planned = plan.remove.clone();
+
+        let mut after = before.clone();
+        plan.apply(&mut after);
+        let removed: Vec<Expense> = before.into_iter().filter(|e| !after.contains(e)).collect();
+
+        assert_eq!(removed, planned);
+        assert_eq!(removed.len(), 2);
+    }
+
+    #[test]
+    fn test_dry_run_describes_without_writing() {
+        let file = TempFile::new("dry_run");
+        save_to_file(&sample(), &file.0).unwrap();
+
+        let out = run_with(
+            &[
+                "--file",
+                &file.0,
+                "--dry-run",
+                "purge",
+                "--category",
+                "food",
+            ],
+            "",
+        )
+        .unwrap();
+
+        assert_eq!(out, "would delete 3 expenses totaling $87.75\n");
+        assert_eq!(load_from_file(&file.0).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_multi_expense_change_needs_confirmation() {
+        let file = TempFile::new("confirm");
+        save_to_file(&sample(), &file.0).unwrap();
+
+        let out = run_with(&["--file", &file.0, "purge", "--category", "food"], "n\n").unwrap();
+        assert!(out.ends_with("aborted\n"));
+        assert_eq!(load_from_file(&file.0).unwrap().len(), 4);
+
+        let out = run_with(&["--file", &file.0, "purge", "--category", "food"], "y\n").unwrap();
+        assert!(out.ends_with("deleted 3 expenses totaling $87.75\n"));
+        assert_eq!(load_from_file(&file.0).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_export_and_import_with_semicolons_and_decimal_commas() {
+        let file = TempFile::new("export");
+        let sheet = TempFile::new("export_sheet");
+        let copy = TempFile::new("export_copy");
+        save_to_file(&sample(), &file.0).unwrap();
+
+        let args = [
+            "--file",
+            &file.0,
+            "export",
+            "--delimiter",
+            ";",
+            "--decimal-comma",
+        ];
+        let text = run_with(&args, "").unwrap();
+        assert!(text.starts_with("id;amount;category;date;"), "{}", text);
+        assert!(text.contains(";45,5;food;2026-01-08;"), "{}", text);
+        std::fs::write(&sheet.0, &text).unwrap();
+
+        // Detected, then given explicitly
+        let import = |flags: &[&str]| {
+            let _ = std::fs::remove_file(&copy.0);
+            let mut args = vec!["--file", &copy.0, "--yes", "import", &sheet.0];
+            args.extend_from_slice(flags);
+            run_with(&args, "").unwrap();
+            let amounts: Vec<f64> = load_from_file(&copy.0)
+                .unwrap()
+                .iter()
+                .map(|e| e.amount)
+                .collect();
+            amounts
+        };
+        let amounts: Vec<f64> = sample().iter().map(|e| e.amount).collect();
+        assert_eq!(import(&[]), amounts);
+        assert_eq!(import(&["--delimiter", ";", "--decimal-comma"]), amounts);
+
+        let args = ["--file", &file.0, "export", "--delimiter", "ab"];
+        assert!(run_with(&args, "").is_err());
+        let plain = run_with(&["--file", &file.0, "export", "--no-header"], "").unwrap();
+        assert_eq!(plain.lines().count(), 4);
+        assert!(plain.contains(",45.5,food,"), "{}", plain);
+    }
+
+    #[test]
+    fn test_totals_use_the_configured_currency() {
+        let file = TempFile::new("currency");
+        let config = TempFile::new("currency_config");
+        std::fs::write(&config.0, "currency = \"EUR\"\n").unwrap();
+        save_to_file(&sample(), &file.0).unwrap();
+        let run = |args: &[&str], input: &str| {
+            let mut full = vec!["--file", &file.0, "--config", &config.0];
+            full.extend_from_slice(args);
+            run_with(&full, input).unwrap()
+        };
+
+        let out = run(&["purge", "--category", "food"], "n\n");
+        assert_eq!(out, "delete 3 expenses totaling 87,75 €? [y/N] aborted\n");
+        let out = run(&["total", "--verbose"], "");
+        assert!(out.ends_with(": 107,75 €\n"), "{}", out);
+    }
+
+    #[test]
+    fn test_yes_skips_confirmation() {
+        let file = TempFile::new("yes");
+        let expenses = sample();
+        save_to_file(&expenses, &file.0).unwrap();
+        let first = expenses[0].id.to_string();
+        let second = expenses[1].id.to_string();
+
+        let out = run_with(&["--file", &file.0, "--yes", "delete", &first, &second], "").unwrap();
+
+        assert_eq!(out, "deleted 2 expenses totaling $65.50\n");
+        assert_eq!(load_from_file(&file.0).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_merge_skips_already_recorded_expenses() {
+        let existing = sample();
+        let other = vec![
+            Expense::new(45.5, "food", "2026-01-08"),
+            Expense::new(9.0, "coffee", "2026-01-09"),
+        ];
+        let plan = plan_merge(&existing, &other);
+        assert_eq!(plan.add.len(), 1);
+        assert_eq!(plan.add[0].category, "coffee");
+    }
+
+    #[test]
+    fn test_merge_keeps_every_field() {
+        let file = TempFile::new("merge_fields");
+        let other = TempFile::new("merge_fields_other");
+        save_to_file(&sample(), &file.0).unwrap();
+        let salary = Expense::new(2000.0, "salary", "2026-01-31")
+            .with_kind(TransactionKind::Income)
+            .with_currency("EUR")
+            .with_note("jan")
+            .with_payee("Acme")
+            .with_tag("work")
+            .with_subcategory("monthly");
+        save_to_file(std::slice::from_ref(&salary), &other.0).unwrap();
+
+        run_with(&["--file", &file.0, "merge", &other.0], "").unwrap();
+        let merged = load_from_file(&file.0).unwrap().pop().unwrap();
+        assert_ne!(merged.id, salary.id);
+        assert_eq!(
+            Expense {
+                id: salary.id,
+                ..merged
+            },
+            salary
+        );
+    }
+
+    #[test]
+    fn test_diff_compares_two_data_files() {
+        let (old, new) = (TempFile::new("diff_old"), TempFile::new("diff_new"));
+        let mut expenses = vec![
+            Expense::with_id(1, 45.5, "food", "2026-01-08"),
+            Expense::with_id(2, 20.0, "transport", "2026-01-08"),
+        ];
+        save_to_file(&expenses, &old.0).unwrap();
+        expenses.reverse();
+        save_to_file(&expenses, &new.0).unwrap();
+        let out = run_with(&["diff", &old.0, &new.0], "").unwrap();
+        assert_eq!(out, "no differences\n");
+
+        expenses.push(Expense::with_id(3, 9.0, "coffee", "2026-01-09"));
+        save_to_file(&expenses, &new.0).unwrap();
+        let out = run_with(&["diff", &old.0, &new.0], "").unwrap();
+        assert_eq!(
+            out,
+            "+ #3 $9.00 - coffee (2026-01-09)\n0 removed, 1 added, 0 changed\n"
+        );
+        assert!(matches!(
+            run_with(&["diff", &old.0], ""),
+            Err(CliError::Usage(_))
+        ));
+    }
+
+    #[test]
+    fn test_list_with_several_categories() {
+        let file = TempFile::new("list_categories");
+        let mut expenses = sample();
+        expenses.push(Expense::new(500.0, "rent", "2026-01-01"));
+        save_to_file(&expenses, &file.0).unwrap();
+        let list = |flags: &[&str]| {
+            let mut args = vec!["--file", file.0.as_str(), "--no-color", "list"];
+            args.extend_from_slice(flags);
+            run_with(&args, "").unwrap()
+        };
+
+        let both = list(&["--category", "food", "--category", "transport"]);
+        assert!(both.contains("45.50") && both.contains("20.00") && both.contains("12.25"));
+        assert!(!both.contains("500.00"));
+        assert_eq!(
+            both,
+            list(&["--category", "transport", "--category", "food"])
+        );
+        assert!(list(&[]).contains("500.00"));
+        assert!(!list(&["--category", "rent"]).contains("45.50"));
+    }
+
+    #[test]
+    fn test_list_grouped_by_month() {
+        let file = TempFile::new("list_grouped");
+        save_to_file(&sample(), &file.0).unwrap();
+        let list = |flags: &[&str]| {
+            let mut args = vec!["--file", file.0.as_str(), "--no-color", "list"];
+            args.extend_from_slice(flags);
+            run_with(&args, "")
+        };
+
+        let out = list(&["--group-by", "month", "--category", "food"]).unwrap();
+        assert!(out.starts_with("2026-01\n"), "{}", out);
+        assert!(out.contains("\n\n2026-02\n"), "{}", out);
+        assert!(out.ends_with("Grand total: $87.75\n"), "{}", out);
+        assert!(matches!(
+            list(&["--group-by", "payee"]),
+            Err(CliError::Usage(_))
+        ));
+    }
+
+    #[test]
+    fn test_raw_output_is_the_bare_value() {
+        let file = TempFile::new("raw");
+        let mut expenses = sample();
+        expenses.push(Expense::new(1234567.5, "rent", "2026-01-08").with_currency("EUR"));
+        save_to_file(&expenses, &file.0).unwrap();
+        let run = |args: &[&str]| {
+            let mut all = vec!["--file", file.0.as_str(), "--color", "--verbose"];
+            all.extend_from_slice(args);
+            run_on(&all, "", "2026-01-08").unwrap().into_bytes()
+        };
+
+        assert_eq!(run(&["total", "--today", "--raw"]), b"1234633.00\n");
+        assert_eq!(run(&["count", "--category", "food", "--raw"]), b"3\n");
+        assert_eq!(run(&["count", "--raw"]), b"5\n");
+        assert_eq!(run(&["max", "--raw"]), b"1234567.50\n");
+        assert_eq!(run(&["min", "--category", "food", "--raw"]), b"12.25\n");
+
+        assert_eq!(run(&["count", "--category", "food"]), b"3 food expenses\n");
+        assert_eq!(
+            run(&["max", "--category", "food"]),
+            format!("#{} $45.50 - food (2026-01-08)\n", expenses[0].id).into_bytes()
+        );
+        let args = [
+            "--file",
+            file.0.as_str(),
+            "max",
+            "--category",
+            "coffee",
+            "--raw",
+        ];
+        assert_eq!(
+            run_with(&args, "").unwrap_err().to_string(),
+            "not found: no coffee expenses"
+        );
+    }
+
+    #[test]
+    fn test_total_periods_across_month_boundary() {
+        let file = TempFile::new("total");
+        let expenses = vec![
+            Expense::new(4.0, "food", "2026-01-25"),
+            Expense::new(10.0, "food", "2026-01-26"),
+            Expense::new(2.5, "transport", "2026-01-31"),
+            Expense::new(7.0, "food", "2026-02-01"),
+            Expense::new(100.0, "rent", "2026-02-02"),
+        ];
+        save_to_file(&expenses, &file.0).unwrap();
+        let total = |flags: &[&str]| {
+            let mut args = vec!["--file", file.0.as_str(), "total"];
+            args.extend_from_slice(flags);
+            run_on(&args, "", "2026-02-01").unwrap()
+        };
+
+        assert_eq!(total(&["--today"]), "7.00\n");
+        assert_eq!(total(&["--this-week"]), "19.50\n");
+        assert_eq!(total(&["--this-month"]), "107.00\n");
+        assert_eq!(total(&["--since", "2026-01-26"]), "119.50\n");
+        assert_eq!(total(&[]), "123.50\n");
+        assert_eq!(
+            total(&["--this-week", "--verbose"]),
+            "Total this week (2026-01-26 to 2026-02-01): $19.50\n"
+        );
+
+        let weekly = weekly_totals(&expenses);
+        assert_eq!(weekly["2026-01-26"], 19.5);
+    }
+
+    #[test]
+    fn test_quick_dates_expense_today() {
+        let file = TempFile::new("quick");
+        let config = TempFile::new("quick_config");
+        let text = "[templates]\nbus = { amount = 2.50, category = \"transport\" }\n";
+        std::fs::write(&config.0, text).unwrap();
+
+        let args = ["--file", &file.0, "--config", &config.0, "quick", "bus"];
+        run_on(&args, "", "2026-03-14").unwrap();
+
+        let expenses = load_from_file(&file.0).unwrap();
+        assert_eq!(expenses.len(), 1);
+        assert_eq!(expenses[0].date, "2026-03-14");
+        assert_eq!(expenses[0].amount, 2.5);
+    }
+
+    #[test]
+    fn test_add_and_quick_refuse_negative_amounts() {
+        let file = TempFile::new("negative");
+        let config = TempFile::new("negative_config");
+        let text = "[templates]\nbus = { amount = 2.50, category = \"transport\" }\n";
+        std::fs::write(&config.0, text).unwrap();
+
+        let add = run_with(&["--file", &file.0, "add", "-5", "food", "2026-01-08"], "");
+        assert!(add.unwrap_err().to_string().contains("negative"));
+        let args = [
+            "--file", &file.0, "--config", &config.0, "quick", "bus", "-2",
+        ];
+        assert!(
+            run_with(&args, "")
+                .unwrap_err()
+                .to_string()
+                .contains("negative")
+        );
+        assert!(!Path::new(&file.0).exists());
+
+        let add = run_with(&["--file", &file.0, "add", "x", "food", "2026-01-08"], "");
+        assert!(add.unwrap_err().to_string().starts_with("bad amount `x`"));
+        run_with(&["--file", &file.0, "add", "0", "food", "2026-01-08"], "").unwrap();
+        assert_eq!(load_from_file(&file.0).unwrap()[0].amount, 0.0);
+    }
+
+    #[test]
+    fn test_quick_into_closed_month_says_nothing_was_added() {
+        let file = TempFile::new("quick_closed");
+        let config = TempFile::new("quick_closed_config");
+        let text = "closed_periods = [\"2026-03\"]\n[templates]\nbus = { amount = 2.50, category = \"transport\" }\n";
+        std::fs::write(&config.0, text).unwrap();
+
+        let args: Vec<String> = ["--file", &file.0, "--config", &config.0, "quick", "bus"]
+            .iter()
+            .map(|a| a.to_string())
+            .collect();
+        let mut out = Vec::new();
+        let result = run(
+            &args,
+            &FixedClock::new("2026-03-14"),
+            &mut "".as_bytes(),
+            &mut out,
+        );
+        assert!(matches!(
+            result,
+            Err(CliError::Expense(ExpenseError::PeriodClosed(_)))
+        ));
+        assert!(out.is_empty(), "{}", String::from_utf8_lossy(&out));
+        assert!(!Path::new(&file.0).exists());
+    }
+
+    #[test]
+    fn test_categorize_accepts_overrides_and_skips() {
+        let file = TempFile::new("categorize");
+        let config = TempFile::new("categorize_config");
+        std::fs::write(&config.0, "[auto_categories]\ntransport = [\"uber\"]\n").unwrap();
+        let expenses = vec![
+            Expense::new(12.5, "food", "2026-01-05"),
+            Expense::new(7.0, "uncategorized", "2026-01-05").with_note("UBER trip"),
+            Expense::new(30.0, "uncategorized", "2026-01-06").with_note("Shoprite"),
+            Expense::new(2.0, "uncategorized", "2026-01-06"),
+            Expense::new(9.0, "uncategorized", "2026-01-07").with_note("uber eats"),
+        ];
+        save_to_file(&expenses, &file.0).unwrap();
+        let categorize = |input: &str| {
+            run_with(
+                &["--file", &file.0, "--config", &config.0, "categorize"],
+                input,
+            )
+        };
+        let categories = || -> Vec<String> {
+            let loaded = load_from_file(&file.0).unwrap();
+            loaded.into_iter().map(|e| e.category).collect()
+        };
+
+        // Input runs out after three answers; those are already saved
+        let out = categorize("\nfood\ns\n").unwrap();
+        assert!(
+            out.starts_with(&format!(
+                "#{} $7.00 - uncategorized (2026-01-05) UBER trip\ncategory [transport]: ",
+                expenses[1].id
+            )),
+            "{}",
+            out
+        );
+        assert!(out.contains("category (Enter skips): "), "{}", out);
+        assert!(out.ends_with("\ncategorized 2, skipped 1\n"), "{}", out);
+        assert_eq!(
+            categories(),
+            vec![
+                "food",
+                "transport",
+                "food",
+                "uncategorized",
+                "uncategorized"
+            ]
+        );
+
+        // The skipped one comes up again; Enter with no suggestion skips it
+        let out = categorize("\n\n").unwrap();
+        assert!(out.ends_with("categorized 1, skipped 1\n"), "{}", out);
+        assert_eq!(categories()[3..], ["uncategorized", "transport"]);
+    }
+
+    #[test]
+    fn test_import_preview_agrees_with_the_import() {
+        let file = TempFile::new("preview");
+        let source = TempFile::new("preview_source");
+        save_to_file(&sample(), &file.0).unwrap();
+        let csv = "date,amount,category\n2026-01-07,30.00,food\n2026-01-09,5.00,food\n2026-01-09,5.00,food\n2026-01-10,x,food\n";
+        std::fs::write(&source.0, csv).unwrap();
+        let before = std::fs::read_to_string(&file.0).unwrap();
+
+        let out = run_with(&["--file", &file.0, "import", &source.0, "--preview"], "").unwrap();
+        assert_eq!(
+            out,
+            "\
+New (1):
+  $5.00 - food (2026-01-09)
+Duplicates (2):
+  $30.00 - food (2026-01-07)
+  $5.00 - food (2026-01-09)
+Failed (1):
+  line 5: bad amount `x`
+"
+        );
+        assert_eq!(std::fs::read_to_string(&file.0).unwrap(), before);
+        let args = [
+            "--file",
+            &file.0,
+            "import",
+            &source.0,
+            "--preview",
+            "--format",
+            "json",
+        ];
+        let preview: serde_json::Value =
+            serde_json::from_str(&run_with(&args, "").unwrap()).unwrap();
+        assert_eq!(preview["new"].as_array().unwrap().len(), 1);
+
+        // The same rows a merge would add, and what the import then adds
+        let (imported, _) = import_with_summary(csv, None, &Default::default()).unwrap();
+        assert_eq!(plan_merge(&sample(), &imported).add.len(), 1);
+        run_with(&["--file", &file.0, "import", &source.0], "").unwrap();
+        assert_eq!(load_from_file(&file.0).unwrap().len(), sample().len() + 1);
+    }
+
+    #[test]
+    fn test_import_summary_only_after_importing() {
+        let file = TempFile::new("import_declined");
+        let source = TempFile::new("import_declined_source");
+        std::fs::write(
+            &source.0,
+            "date,amount,category\n2026-01-05,3.50,food\n2026-01-06,4.00,food\n",
+        )
+        .unwrap();
+        let import = |flags: &[&str], input: &str| {
+            let mut args = vec!["--file", file.0.as_str(), "import", source.0.as_str()];
+            args.extend_from_slice(flags);
+            run_with(&args, input)
+        };
+
+        let out = import(&[], "n\n").unwrap();
+        assert!(out.ends_with("aborted\n"), "{}", out);
+        let out = import(&["--dry-run"], "").unwrap();
+        assert_eq!(out, "would add 2 expenses totaling $7.50\n");
+        assert!(!Path::new(&file.0).exists());
+
+        // JSON output can't show a prompt, so it needs --yes or --dry-run
+        assert!(matches!(
+            import(&["--format", "json"], ""),
+            Err(CliError::Usage(_))
+        ));
+        let out = import(&["--dry-run", "--format", "json"], "").unwrap();
+        let summary: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(summary["applied"], false);
+        assert!(!Path::new(&file.0).exists());
+
+        let out = import(&["--yes"], "").unwrap();
+        assert!(out.contains("Imported 2 expenses"), "{}", out);
+
+        // Importing it again adds nothing, and the summary says so
+        std::fs::write(
+            &source.0,
+            "date,amount,category\n2026-01-05,3.50,food\n2026-01-06,4.00,food\n2026-01-07,1.25,food\n",
+        )
+        .unwrap();
+        let out = import(&["--yes"], "").unwrap();
+        assert!(
+            out.contains("Imported 1 expense totaling $1.25\n"),
+            "{}",
+            out
+        );
+        assert!(out.contains("Already recorded, left out: 2\n"), "{}", out);
+        assert_eq!(load_from_file(&file.0).unwrap().len(), 3);
+        let out = import(&["--yes", "--format", "json"], "").unwrap();
+        let summary: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(summary["imported"], 0);
+        assert_eq!(summary["duplicates"], 3);
+        assert_eq!(summary["applied"], false);
+    }
+
+    #[test]
+    fn test_categorize_respects_closed_months_and_undo() {
+        let file = TempFile::new("categorize_closed");
+        let config = TempFile::new("categorize_closed_config");
+        std::fs::write(&config.0, "closed_periods = [\"2026-01\"]\n").unwrap();
+        let expenses = vec![
+            Expense::new(7.0, "uncategorized", "2026-01-05"),
+            Expense::new(3.0, "uncategorized", "2026-02-01"),
+        ];
+        save_to_file(&expenses, &file.0).unwrap();
+        let original = std::fs::read_to_string(&file.0).unwrap();
+        let run = |flags: &[&str], input: &str| {
+            let mut args = vec!["--file", file.0.as_str(), "--config", config.0.as_str()];
+            args.extend_from_slice(flags);
+            run_with(&args, input)
+        };
+
+        let err = run(&["categorize"], "food\n").unwrap_err();
+        assert!(err.to_string().contains("2026-01"), "{}", err);
+        assert_eq!(std::fs::read_to_string(&file.0).unwrap(), original);
+
+        run(&["--force", "categorize"], "food\ntransport\n").unwrap();
+        let categories = || -> Vec<String> {
+            let loaded = load_from_file(&file.0).unwrap();
+            loaded.into_iter().map(|e| e.category).collect()
+        };
+        assert_eq!(categories(), vec!["food", "transport"]);
+
+        // Each answer was its own change, undone newest first
+        run(&["undo"], "").unwrap();
+        assert_eq!(categories(), vec!["food", "uncategorized"]);
+        run(&["--force", "undo"], "").unwrap();
+        assert_eq!(std::fs::read_to_string(&file.0).unwrap(), original);
+    }
+
+    #[test]
+    fn test_import_prints_json_summary() {
+        let file = TempFile::new("import");
+        let source = TempFile::new("import_source");
+        let config = TempFile::new("import_config");
+        std::fs::write(
+            &source.0,
+            "date,amount,category\n2026-01-05,3.50,\n2026-01-06,x,food\n",
+        )
+        .unwrap();
+        std::fs::write(&config.0, "").unwrap();
+
+        let args = [
+            "--file", &file.0, "--config", &config.0, "import", &source.0, "--format", "json",
+        ];
+        let out = run_with(&args, "").unwrap();
+
+        let summary: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(summary["applied"], true);
+        assert_eq!(summary["imported"], 1);
+        assert_eq!(summary["per_category"]["uncategorized"], 1);
+        assert_eq!(summary["skipped"][0]["line"], 3);
+        assert_eq!(load_from_file(&file.0).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_undo_restores_the_file_before_the_last_change() {
+        let file = TempFile::new("undo");
+        let source = TempFile::new("undo_source");
+        save_to_file(&sample(), &file.0).unwrap();
+        let original = std::fs::read_to_string(&file.0).unwrap();
+
+        run_with(&["--file", &file.0, "add", "9", "coffee", "2026-01-09"], "").unwrap();
+        let added = std::fs::read_to_string(&file.0).unwrap();
+        std::fs::write(
+            &source.0,
+            "date,amount,category\n2026-01-10,5.00,food\n2026-01-11,7.50,food\n",
+        )
+        .unwrap();
+        run_with(&["--file", &file.0, "--yes", "import", &source.0], "").unwrap();
+        assert_eq!(load_from_file(&file.0).unwrap().len(), 7);
+
+        let out = run_with(&["--file", &file.0, "undo", "--show"], "").unwrap();
+        assert!(out.starts_with("would undo import: delete 2 expenses totaling $12.50\n"));
+        assert_eq!(load_from_file(&file.0).unwrap().len(), 7);
+
+        // The import comes out as a whole, then the add
+        let out = run_with(&["--file", &file.0, "undo"], "").unwrap();
+        assert_eq!(out, "undid import: deleted 2 expenses totaling $12.50\n");
+        assert_eq!(std::fs::read_to_string(&file.0).unwrap(), added);
+        let out = run_with(&["--file", &file.0, "undo"], "").unwrap();
+        assert_eq!(out, "undid add: deleted 1 expense totaling $9.00\n");
+        assert_eq!(std::fs::read_to_string(&file.0).unwrap(), original);
+
+        let err = run_with(&["--file", &file.0, "undo"], "").unwrap_err();
+        assert_eq!(err.to_string(), "not found: nothing to undo");
+    }
+
+    #[test]
+    fn test_undo_puts_deleted_expenses_back_in_place() {
+        let file = TempFile::new("undo_delete");
+        save_to_file(&sample(), &file.0).unwrap();
+        let original = std::fs::read_to_string(&file.0).unwrap();
+        let expenses = load_from_file(&file.0).unwrap();
+
+        let args = ["--file", &file.0, "--yes", "purge", "--category", "food"];
+        run_with(&args, "").unwrap();
+        let out = run_with(&["--file", &file.0, "undo", "--show"], "").unwrap();
+        assert_eq!(
+            out.lines().nth(1).unwrap(),
+            format!("+ #{} {}", expenses[0].id, expenses[0])
+        );
+        run_with(&["--file", &file.0, "undo"], "").unwrap();
+        assert_eq!(std::fs::read_to_string(&file.0).unwrap(), original);
+    }
+
+    #[test]
+    fn test_closed_month_refuses_changes_unless_forced() {
+        let file = TempFile::new("close");
+        let config = TempFile::new("close_config");
+        let run = |args: &[&str]| {
+            let mut all = vec!["--file", &file.0, "--config", &config.0];
+            all.extend(args);
+            run_with(&all, "")
+        };
+
+        run(&["add", "10", "food", "2026-01-15"]).unwrap();
+        assert_eq!(run(&["close", "2026-01"]).unwrap(), "closed 2026-01\n");
+        assert_eq!(
+            run(&["close", "2026-01"]).unwrap(),
+            "2026-01 is already closed\n"
+        );
+        assert!(run(&["close", "2026-1"]).is_err());
+
+        let err = run(&["add", "5", "food", "2026-01-31"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "period closed: 2026-01 (expense dated 2026-01-31)"
+        );
+        let id = load_from_file(&file.0).unwrap()[0].id.to_string();
+        assert!(run(&["delete", &id]).is_err());
+        assert_eq!(load_from_file(&file.0).unwrap().len(), 1);
+
+        // Open months still take changes, and --force gets past the check
+        run(&["add", "7", "food", "2026-02-01"]).unwrap();
+        run(&["--force", "add", "5", "food", "2026-01-31"]).unwrap();
+        assert_eq!(load_from_file(&file.0).unwrap().len(), 3);
+        assert!(run(&["undo"]).is_err());
+
+        assert_eq!(run(&["reopen", "2026-01"]).unwrap(), "reopened 2026-01\n");
+        run(&["delete", &id]).unwrap();
+        assert_eq!(load_from_file(&file.0).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_changes_wait_for_the_data_file_lock() {
+        let file = TempFile::new("locked");
+        let held = FileLock::acquire(&file.0, LOCK_TIMEOUT).unwrap();
+        let (done, finished) = std::sync::mpsc::channel();
+        let path = file.0.clone();
+        let adder = std::thread::spawn(move || {
+            let out = run_with(&["--file", &path, "add", "9", "coffee", "2026-01-09"], "");
+            done.send(()).unwrap();
+            out
+        });
+        // Still waiting while the lock is held, done soon after it goes
+        assert!(finished.recv_timeout(Duration::from_millis(200)).is_err());
+        std::fs::write(&file.0, to_csv(&sample())).unwrap();
+        drop(held);
+        assert!(adder.join().unwrap().is_ok());
+        assert_eq!(load_from_file(&file.0).unwrap().len(), 5);
+
+        // Reading needs no lock
+        let _held = FileLock::acquire(&file.0, LOCK_TIMEOUT).unwrap();
+        let out = run_with(&["--file", &file.0, "count", "--raw"], "").unwrap();
+        assert_eq!(out, "5\n");
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn test_serve_holds_the_data_file_lock() {
+        let file = TempFile::new("serve_locked");
+        save_to_file(&sample(), &file.0).unwrap();
+        let path = file.0.clone();
+        // Runs until the process ends, keeping the lock all along
+        std::thread::spawn(move || run_with(&["--file", &path, "serve", "--port", "0"], ""));
+        let lock = crate::lock::lock_path(&file.0);
+        let start = std::time::Instant::now();
+        while !Path::new(&lock).exists() {
+            assert!(start.elapsed() < LOCK_TIMEOUT, "serve never took the lock");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(FileLock::acquire(&file.0, Duration::from_millis(100)).is_err());
+        std::fs::remove_file(&lock).unwrap();
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn test_serve_keeps_closed_months_closed() {
+        use crate::server::{Request, ServerState, handle};
+
+        let file = TempFile::new("serve_closed");
+        let config = TempFile::new("serve_closed_config");
+        save_to_file(&sample(), &file.0).unwrap();
+        run_with(&["--config", &config.0, "close", "2026-01"], "").unwrap();
+
+        let args: Vec<String> = ["--file", &file.0, "--config", &config.0]
+            .iter()
+            .map(|a| a.to_string())
+            .collect();
+        let (options, _) = parse_options(&args).unwrap();
+        let state = ServerState {
+            tracker: served_tracker(&options).unwrap(),
+        };
+        let body = r#"{"amount": 9.5, "category": "coffee", "date": "2026-01-20"}"#;
+        let response = handle(&Request::new("POST", "/expenses", body), &state);
+        assert_eq!(response.status, 409);
+        let path = format!("/expenses/{}", load_from_file(&file.0).unwrap()[0].id);
+        let response = handle(&Request::new("DELETE", &path, ""), &state);
+        assert_eq!(response.status, 409);
+        assert_eq!(state.tracker.len(), 4);
+    }
+
+    #[test]
+    fn test_cashflow_shows_income_and_balance() {
+        let file = TempFile::new("cashflow");
+        let config = TempFile::new("cashflow_config");
+        std::fs::write(&config.0, "").unwrap();
+        let add = |args: &[&str]| {
+            let mut full = vec!["--file", &file.0, "add"];
+            full.extend_from_slice(args);
+            run_with(&full, "").unwrap();
+        };
+        add(&["2000", "salary", "2026-01-31", "--income"]);
+        add(&["500", "rent", "2026-01-01"]);
+        add(&["80", "food", "2026-02-03"]);
+        assert_eq!(
+            load_from_file(&file.0).unwrap()[0].kind,
+            TransactionKind::Income
+        );
+
+        let args = [
+            "--file",
+            &file.0,
+            "--config",
+            &config.0,
+            "cashflow",
+            "--opening",
+            "100",
+        ];
+        assert_eq!(
+            run_with(&args, "").unwrap(),
+            "\
+Month      Income  Expenses       Net   Balance
+2026-01  $2000.00   $500.00  $1500.00  $1600.00
+2026-02     $0.00    $80.00   -$80.00  $1520.00
+"
+        );
+
+        // Income isn't spending in the total, the list or the report
+        let total = run_with(&["--file", &file.0, "total"], "").unwrap();
+        assert_eq!(total, "580.00\n");
+        let args = ["--file", &file.0, "--config", &config.0, "list"];
+        let list = run_with(&args, "").unwrap();
+        let totals: Vec<&str> = list.lines().rev().take(2).collect();
+        assert!(
+            totals[1].starts_with("Total") && totals[1].ends_with("$580.00"),
+            "{}",
+            list
+        );
+        assert!(
+            totals[0].starts_with("Income") && totals[0].ends_with("$2000.00"),
+            "{}",
+            list
+        );
+        let args = ["--file", &file.0, "--config", &config.0, "report"];
+        let report = run_with(&args, "").unwrap();
+        assert!(
+            report.starts_with("Total: $580.00 (2 expenses)\nIncome: $2000.00\n"),
+            "{}",
+            report
+        );
+    }
+
+    #[test]
+    fn test_doctor_reports_and_fixes() {
+        let file = TempFile::new("doctor");
+        let config = TempFile::new("doctor_config");
+        let args = ["--file", &file.0, "--config", &config.0, "doctor"];
+        assert_eq!(run_with(&args, "").unwrap(), "no problems found\n");
+
+        std::fs::write(
+            &file.0,
+            "id,amount,category,date\n1,45.5, Food,2026-01-08\n2,-3,food,2026-01-08\n",
+        )
+        .unwrap();
+        let out = run_with(&args, "").unwrap();
+        assert!(out.contains("warning: #1 has stray whitespace"), "{}", out);
+        assert!(out.ends_with("0 errors, 2 warnings, 0 notes\n"), "{}", out);
+
+        let fix = ["--file", &file.0, "--config", &config.0, "doctor", "--fix"];
+        let out = run_with(&fix, "").unwrap();
+        assert!(out.starts_with("fixed 1 expense\n"), "{}", out);
+        assert_eq!(load_from_file(&file.0).unwrap()[0].category, "food");
+        assert!(
+            run_with(&args, "")
+                .unwrap()
+                .ends_with("0 errors, 1 warnings, 0 notes\n")
+        );
+
+        let mut text = std::fs::read_to_string(&file.0).unwrap();
+        text.push_str("1,9,food,2026-01-09\n3,x,food,2026-01-09\n");
+        std::fs::write(&file.0, text).unwrap();
+        let err = run_with(&args, "").unwrap_err();
+        assert!(err.to_string().ends_with("has 2 errors"), "{}", err);
+    }
+
+    #[test]
+    fn test_doctor_fix_respects_closed_months_and_undo() {
+        let file = TempFile::new("doctor_closed");
+        let config = TempFile::new("doctor_closed_config");
+        std::fs::write(&config.0, "closed_periods = [\"2026-01\"]\n").unwrap();
+        let original = "id,amount,category,date\n1,45.5, Food,2026-01-08\n";
+        std::fs::write(&file.0, original).unwrap();
+        let run = |flags: &[&str]| {
+            let mut args = vec!["--file", file.0.as_str(), "--config", config.0.as_str()];
+            args.extend_from_slice(flags);
+            run_with(&args, "")
+        };
+
+        let err = run(&["doctor", "--fix"]).unwrap_err();
+        assert!(err.to_string().contains("2026-01"), "{}", err);
+        assert_eq!(std::fs::read_to_string(&file.0).unwrap(), original);
+
+        let out = run(&["--force", "doctor", "--fix"]).unwrap();
+        assert!(out.starts_with("fixed 1 expense\n"), "{}", out);
+        assert_eq!(load_from_file(&file.0).unwrap()[0].category, "food");
+        run(&["--force", "undo"]).unwrap();
+        assert_eq!(load_from_file(&file.0).unwrap()[0].category, " Food");
+    }
+
+    #[test]
+    fn test_import_with_preset() {
+        let file = TempFile::new("import_preset");
+        let source = TempFile::new("import_preset_source");
+        let config = TempFile::new("import_preset_config");
+        std::fs::write(
+            &source.0,
+            "Date,Ref,Debit\n05/01/2026,FT001,-12500.00\n06/01/2026,FT002,-3200.50\n",
+        )
+        .unwrap();
+        std::fs::write(
+            &config.0,
+            "[import.gtbank]\namount_col = 2\ndate_col = 0\ndate_format = \"DD/MM/YYYY\"\nnegate = true\n",
+        )
+        .unwrap();
+
+        let args = [
+            "--file", &file.0, "--config", &config.0, "--yes", "import", "--preset", "gtbank",
+            &source.0,
+        ];
+        run_with(&args, "").unwrap();
+        let expenses = load_from_file(&file.0).unwrap();
+        let rows: Vec<(f64, &str)> = expenses
+            .iter()
+            .map(|e| (e.amount, e.date.as_str()))
+            .collect();
+        assert_eq!(rows, vec![(12500.0, "2026-01-05"), (3200.5, "2026-01-06")]);
+
+        let args = [
+            "--file", &file.0, "--config", &config.0, "import", "--preset", "zenith", &source.0,
+        ];
+        let err = run_with(&args, "").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "not found: import preset `zenith` (available: gtbank)"
+        );
+    }
+}
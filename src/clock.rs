@@ -0,0 +1,44 @@
+// This module provides "today" as a value that can be swapped out, so
+// anything depending on the current date can be tested
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::dates::{civil_from_days, format_ymd};
+
+/// Where the current date comes from
+pub trait Clock {
+    /// Today's date as `YYYY-MM-DD`
+    fn today(&self) -> String;
+}
+
+/// The real clock, reading the system time (in UTC)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn today(&self) -> String {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let (y, m, d) = civil_from_days((secs / 86_400) as i64);
+        format_ymd(y, m, d)
+    }
+}
+
+/// A clock stuck on one date, for tests
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixedClock(pub String);
+
+impl FixedClock {
+    /// Creates a clock that always returns `date`
+    pub fn new(date: &str) -> FixedClock {
+        FixedClock(date.to_string())
+    }
+}
+
+impl Clock for FixedClock {
+    fn today(&self) -> String {
+        self.0.clone()
+    }
+}
@@ -0,0 +1,241 @@
+// This module holds user settings, stored as a TOML file
+//
+// Example:
+//
+//     currency = "NGN"
+//     monthly_income = 250000
+//     closed_periods = ["2026-01"]
+//
+//     [categories.food]
+//     emoji = "🍔"
+//     color = "yellow"
+//
+//     [templates]
+//     bus = { amount = 2.50, category = "transport" }
+//
+//     [auto_categories]
+//     transport = ["uber", "bolt"]
+//
+//     [import.gtbank]
+//     amount_col = 3
+//     date_col = 0
+//     date_format = "DD/MM/YYYY"
+//     negate = true
+
+use std::collections::{BTreeMap, BTreeSet};
+#[cfg(feature = "fs")]
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::currency::DEFAULT_CURRENCY;
+use crate::error::ExpenseError;
+use crate::expense::Expense;
+use crate::import::ColumnMapping;
+use crate::templates::Template;
+
+/// Config file used when neither `--config` nor `EXPENSE_CONFIG` is given
+pub const DEFAULT_CONFIG_FILE: &str = "expense.toml";
+
+/// Everything the user can configure
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Display metadata per category name
+    pub categories: BTreeMap<String, CategoryStyle>,
+    /// Named templates for `expense quick`
+    pub templates: BTreeMap<String, Template>,
+    /// Keywords per category, used to categorize imported rows that have
+    /// no category of their own
+    pub auto_categories: BTreeMap<String, Vec<String>>,
+    /// Named column mappings for `expense import --preset`, e.g. one per
+    /// bank
+    pub import: BTreeMap<String, ColumnMapping>,
+    /// ISO 4217 code for expenses that don't name their own currency
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+    /// Take-home pay per month; when set, `expense report` shows spending
+    /// as a share of it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monthly_income: Option<f64>,
+    /// `YYYY-MM` months closed with `expense close`, whose expenses the
+    /// command line won't change without `--force`
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    pub closed_periods: BTreeSet<String>,
+    /// `YYYY` years moved out of the data file by `archive_year`
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    pub archived_years: BTreeSet<String>,
+}
+
+/// How a category is shown in listings and reports
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CategoryStyle {
+    /// Shown before the category, e.g. "🍔"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub emoji: Option<String>,
+    /// Shown instead of the category name, e.g. "eats"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Terminal color name: red, green, yellow, blue, magenta, cyan or white
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+}
+
+impl Config {
+    /// Read settings from TOML text
+    pub fn from_toml_str(text: &str) -> Result<Config, ExpenseError> {
+        toml::from_str(text).map_err(|e| ExpenseError::ParseError(e.to_string()))
+    }
+
+    /// Write settings as TOML text
+    pub fn to_toml_string(&self) -> String {
+        toml::to_string(self).expect("config is always representable as TOML")
+    }
+
+    /// Load settings from a file, using the defaults if it doesn't exist
+    #[cfg(feature = "fs")]
+    pub fn load(path: &str) -> Result<Config, ExpenseError> {
+        if !Path::new(path).exists() {
+            return Ok(Config::default());
+        }
+        Config::from_toml_str(&std::fs::read_to_string(path)?)
+    }
+
+    /// Save settings to a file
+    #[cfg(feature = "fs")]
+    pub fn save(&self, path: &str) -> Result<(), ExpenseError> {
+        std::fs::write(path, self.to_toml_string())?;
+        Ok(())
+    }
+
+    /// The currency `expense` is in: its own, else the configured default,
+    /// else `DEFAULT_CURRENCY`
+    pub fn currency_of<'a>(&'a self, expense: &'a Expense) -> &'a str {
+        expense
+            .currency
+            .as_deref()
+            .or(self.currency.as_deref())
+            .unwrap_or(DEFAULT_CURRENCY)
+    }
+
+    /// The currency totals are shown in: the configured one, or
+    /// `DEFAULT_CURRENCY`
+    pub fn default_currency(&self) -> &str {
+        self.currency.as_deref().unwrap_or(DEFAULT_CURRENCY)
+    }
+
+    /// The import preset called `name`
+    ///
+    /// The error for an unknown name lists the presets that do exist.
+    pub fn import_preset(&self, name: &str) -> Result<&ColumnMapping, ExpenseError> {
+        self.import.get(name).ok_or_else(|| {
+            let available = if self.import.is_empty() {
+                "none are configured".to_string()
+            } else {
+                let names: Vec<&str> = self.import.keys().map(String::as_str).collect();
+                format!("available: {}", names.join(", "))
+            };
+            ExpenseError::NotFound(format!("import preset `{}` ({})", name, available))
+        })
+    }
+
+    /// Display metadata for a category, if any is configured
+    pub fn style(&self, category: &str) -> Option<&CategoryStyle> {
+        self.categories.get(category)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_category_styles_round_trip_through_toml() {
+        let text = "[categories.food]\nemoji = \"🍔\"\ncolor = \"yellow\"\n\n[categories.rent]\nlabel = \"home\"\n";
+        let config = Config::from_toml_str(text).unwrap();
+        assert_eq!(config.style("food").unwrap().emoji.as_deref(), Some("🍔"));
+        assert_eq!(config.style("rent").unwrap().label.as_deref(), Some("home"));
+        assert!(config.style("transport").is_none());
+
+        assert_eq!(
+            Config::from_toml_str(&config.to_toml_string()).unwrap(),
+            config
+        );
+    }
+
+    #[test]
+    fn test_currency_falls_back_to_config_default() {
+        let config =
+            Config::from_toml_str("currency = \"NGN\"\n\n[categories.food]\nemoji = \"🍔\"\n")
+                .unwrap();
+        let naira = Expense::with_id(1, 4500.0, "food", "2026-01-08");
+        let euro = naira.clone().with_currency("EUR");
+        assert_eq!(config.currency_of(&naira), "NGN");
+        assert_eq!(config.currency_of(&euro), "EUR");
+        assert_eq!(Config::default().currency_of(&naira), DEFAULT_CURRENCY);
+        assert_eq!(
+            Config::from_toml_str(&config.to_toml_string()).unwrap(),
+            config
+        );
+    }
+
+    #[test]
+    fn test_empty_config_is_default() {
+        assert_eq!(Config::from_toml_str("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn test_import_presets_load_from_toml() {
+        let text = "\
+[import.gtbank]
+amount_col = 3
+date_col = 0
+date_format = \"DD/MM/YYYY\"
+negate = true
+
+[import.plain]
+amount_col = 1
+date_col = 0
+";
+        let config = Config::from_toml_str(text).unwrap();
+        let gtbank = config.import_preset("gtbank").unwrap();
+        assert_eq!(
+            *gtbank,
+            ColumnMapping {
+                date_format: "DD/MM/YYYY".to_string(),
+                negate: true,
+                ..ColumnMapping::new(3, 0)
+            }
+        );
+        assert_eq!(
+            *config.import_preset("plain").unwrap(),
+            ColumnMapping::new(1, 0)
+        );
+        assert_eq!(
+            config.import_preset("zenith"),
+            Err(ExpenseError::NotFound(
+                "import preset `zenith` (available: gtbank, plain)".to_string()
+            ))
+        );
+        assert_eq!(
+            Config::default()
+                .import_preset("gtbank")
+                .unwrap_err()
+                .to_string(),
+            "not found: import preset `gtbank` (none are configured)"
+        );
+        assert_eq!(
+            Config::from_toml_str(&config.to_toml_string()).unwrap(),
+            config
+        );
+    }
+
+    #[test]
+    fn test_templates_load_from_toml() {
+        let text = "[templates]\nbus = { amount = 2.50, category = \"transport\" }\n";
+        let config = Config::from_toml_str(text).unwrap();
+        assert_eq!(config.templates["bus"].amount, 2.5);
+        assert_eq!(config.templates["bus"].category, "transport");
+    }
+}
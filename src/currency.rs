@@ -0,0 +1,70 @@
+// This module writes amounts in their currency, named by ISO 4217 code
+// (`USD`, `NGN`, `EUR`, ...)
+
+/// Currency assumed when neither the expense nor the config names one
+pub const DEFAULT_CURRENCY: &str = "USD";
+
+/// How one currency writes its amounts
+struct CurrencyStyle {
+    symbol: &'static str,
+    /// `45,50 €` rather than `€45.50`
+    symbol_after: bool,
+    decimal_separator: char,
+}
+
+fn style_of(code: &str) -> Option<CurrencyStyle> {
+    let before = |symbol| CurrencyStyle {
+        symbol,
+        symbol_after: false,
+        decimal_separator: '.',
+    };
+    let after = |symbol| CurrencyStyle {
+        symbol,
+        symbol_after: true,
+        decimal_separator: ',',
+    };
+    match code.to_ascii_uppercase().as_str() {
+        "USD" => Some(before("$")),
+        "NGN" => Some(before("₦")),
+        "GBP" => Some(before("£")),
+        "INR" => Some(before("₹")),
+        "GHS" => Some(before("GH₵")),
+        "EUR" => Some(after("€")),
+        _ => None,
+    }
+}
+
+/// An amount with two decimals in `currency`, e.g. `$45.50`, `₦45.50` or
+/// `45,50 €`
+///
+/// Currencies without a known symbol are written with their code after
+/// the amount, e.g. `45.50 KES`.
+pub fn format_amount(amount: f64, currency: &str) -> String {
+    let number = format!("{:.2}", amount);
+    match style_of(currency) {
+        Some(style) if style.symbol_after => format!(
+            "{} {}",
+            number.replace('.', &style.decimal_separator.to_string()),
+            style.symbol
+        ),
+        Some(style) => match number.strip_prefix('-') {
+            Some(digits) => format!("-{}{}", style.symbol, digits),
+            None => format!("{}{}", style.symbol, number),
+        },
+        None => format!("{} {}", number, currency),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_amount() {
+        assert_eq!(format_amount(45.5, "USD"), "$45.50");
+        assert_eq!(format_amount(45.5, "ngn"), "₦45.50");
+        assert_eq!(format_amount(45.5, "EUR"), "45,50 €");
+        assert_eq!(format_amount(-3.0, "GBP"), "-£3.00");
+        assert_eq!(format_amount(1200.0, "KES"), "1200.00 KES");
+    }
+}
@@ -0,0 +1,402 @@
+// This module does calendar arithmetic on `YYYY-MM-DD` date strings,
+// without any external date library
+
+use crate::error::ExpenseError;
+
+/// Split a `YYYY-MM-DD` string into numbers, without range checks
+pub(crate) fn split_ymd(date: &str) -> Option<(i64, u32, u32)> {
+    let mut parts = date.split('-');
+    let year = parts.next()?;
+    let month = parts.next()?;
+    let day = parts.next()?;
+    if parts.next().is_some() || year.len() != 4 || month.len() != 2 || day.len() != 2 {
+        return None;
+    }
+    let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if !all_digits(year) || !all_digits(month) || !all_digits(day) {
+        return None;
+    }
+    Some((year.parse().ok()?, month.parse().ok()?, day.parse().ok()?))
+}
+
+/// Whether `year` has a February 29th
+pub(crate) fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+/// Number of days in a month (1-12)
+pub(crate) fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Check that `date` is a real calendar date written as `YYYY-MM-DD`
+///
+/// The month must be 1-12 and the day must exist in that month, so
+/// `2024-02-29` passes but `2023-02-29` and `2026-04-31` don't.
+pub fn validate_date_format(date: &str) -> Result<(), ExpenseError> {
+    let invalid =
+        |reason: String| Err(ExpenseError::InvalidDate(format!("`{}`: {}", date, reason)));
+    let Some((year, month, day)) = split_ymd(date) else {
+        return invalid("expected YYYY-MM-DD".to_string());
+    };
+    if !(1..=12).contains(&month) {
+        return invalid(format!("month {} is not between 1 and 12", month));
+    }
+    if !(1..=31).contains(&day) {
+        return invalid(format!("day {} is not between 1 and 31", day));
+    }
+    let last = days_in_month(year, month);
+    if day > last {
+        return invalid(format!("{:04}-{:02} only has {} days", year, month, last));
+    }
+    Ok(())
+}
+
+/// Check that `month` is a `YYYY-MM` month with the month 1-12
+pub fn validate_month_format(month: &str) -> Result<(), ExpenseError> {
+    match split_ym(month) {
+        Some(_) => Ok(()),
+        None => Err(ExpenseError::InvalidDate(format!(
+            "`{}`: expected YYYY-MM",
+            month
+        ))),
+    }
+}
+
+/// Parse a `YYYY-MM-DD` date into `(year, month, day)`
+///
+/// Fails with the same errors as `validate_date_format`, so the result is
+/// always a real calendar date.
+pub fn parse_date(date: &str) -> Result<(u32, u8, u8), ExpenseError> {
+    validate_date_format(date)?;
+    let (year, month, day) = split_ymd(date).expect("validated above");
+    // Four digits, 1-12 and 1-31 always fit
+    Ok((year as u32, month as u8, day as u8))
+}
+
+/// Split a `YYYY-MM-DD` string into numbers, checking it is a real date
+pub(crate) fn checked_ymd(date: &str) -> Option<(i64, u32, u32)> {
+    validate_date_format(date).ok()?;
+    split_ymd(date)
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date
+pub(crate) fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    // Shift the year to start in March so the leap day comes last
+    let (y, m) = if month <= 2 {
+        (year - 1, month as i64 + 9)
+    } else {
+        (year, month as i64 - 3)
+    };
+    let era = y.div_euclid(400);
+    let year_of_era = y - era * 400;
+    let day_of_year = (153 * m + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The `(year, month, day)` that is `days` after 1970-01-01
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Number of days from `a` to `b`, negative when `b` is earlier
+///
+/// Both dates are validated first; the count uses proleptic Gregorian
+/// days since the epoch (see `days_from_civil`), so leap days are counted.
+pub fn days_between_dates(a: &str, b: &str) -> Result<i64, ExpenseError> {
+    let days = |date: &str| {
+        parse_date(date).map(|(y, m, d)| days_from_civil(y as i64, m as u32, d as u32))
+    };
+    Ok(days(b)? - days(a)?)
+}
+
+/// The date `days` days after `date`, as `YYYY-MM-DD`
+pub fn next_date(date: &str, days: u32) -> Result<String, ExpenseError> {
+    let (y, m, d) = parse_date(date)?;
+    let (y, m, d) = civil_from_days(days_from_civil(y as i64, m as u32, d as u32) + days as i64);
+    if y > 9999 {
+        return Err(ExpenseError::InvalidDate(format!(
+            "`{}` plus {} days is past year 9999",
+            date, days
+        )));
+    }
+    Ok(format_ymd(y, m, d))
+}
+
+/// The same day `months` months after `date`, as `YYYY-MM-DD`
+///
+/// A day that doesn't exist in the new month becomes that month's last
+/// day, so `2026-01-31` plus one month is `2026-02-28`.
+pub fn add_months(date: &str, months: u32) -> Result<String, ExpenseError> {
+    let (y, m, d) = parse_date(date)?;
+    let index = y as i64 * 12 + (m as i64 - 1) + months as i64;
+    let (year, month) = (index / 12, (index % 12) as u32 + 1);
+    if year > 9999 {
+        return Err(ExpenseError::InvalidDate(format!(
+            "`{}` plus {} months is past year 9999",
+            date, months
+        )));
+    }
+    let day = (d as u32).min(days_in_month(year, month));
+    Ok(format_ymd(year, month, day))
+}
+
+/// The ISO 8601 week number (1 to 53) of `date`
+///
+/// Weeks start on Monday and week 1 is the one holding the year's first
+/// Thursday, so early January can fall in the previous year's last week
+/// and late December in the next year's first.
+pub fn week_number(date: &str) -> Result<u8, ExpenseError> {
+    iso_year_week(date).map(|(_, week)| week)
+}
+
+/// The ISO 8601 year and week number of `date`
+///
+/// The year is the one the week belongs to, which differs from the
+/// calendar year for a few days around New Year.
+pub fn iso_year_week(date: &str) -> Result<(u32, u8), ExpenseError> {
+    let (y, m, d) = parse_date(date)?;
+    let days = days_from_civil(y as i64, m as u32, d as u32);
+    // The week belongs to whichever year its Thursday is in
+    let thursday = days - weekday_from_days(days) as i64 + 3;
+    let (year, _, _) = civil_from_days(thursday);
+    let week = (thursday - days_from_civil(year, 1, 1)) / 7 + 1;
+    Ok((year as u32, week as u8))
+}
+
+/// The quarter (1 to 4) `date` falls in; Q1 is January to March
+pub fn quarter_of(date: &str) -> Result<u8, ExpenseError> {
+    let (_, month, _) = parse_date(date)?;
+    Ok((month - 1) / 3 + 1)
+}
+
+/// Format a date as `YYYY-MM-DD`
+pub(crate) fn format_ymd(year: i64, month: u32, day: u32) -> String {
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Day of the week, 0 = Monday through 6 = Sunday
+pub(crate) fn weekday_from_days(days: i64) -> u32 {
+    // 1970-01-01 was a Thursday
+    (days + 3).rem_euclid(7) as u32
+}
+
+/// The Monday starting the ISO week that contains `date`, or `None` if it
+/// isn't a real date
+pub fn week_start(date: &str) -> Option<String> {
+    let (y, m, d) = checked_ymd(date)?;
+    let days = days_from_civil(y, m, d);
+    let (y, m, d) = civil_from_days(days - weekday_from_days(days) as i64);
+    Some(format_ymd(y, m, d))
+}
+
+/// The Sunday ending the ISO week that contains `date`, or `None` if it
+/// isn't a real date
+pub fn week_end(date: &str) -> Option<String> {
+    let (y, m, d) = checked_ymd(date)?;
+    let days = days_from_civil(y, m, d);
+    let (y, m, d) = civil_from_days(days + 6 - weekday_from_days(days) as i64);
+    Some(format_ymd(y, m, d))
+}
+
+/// The `YYYY-MM` month a `YYYY-MM-DD` date falls in, or `None` if it
+/// isn't a real date
+pub(crate) fn month_of(date: &str) -> Option<&str> {
+    checked_ymd(date)?;
+    Some(&date[..7])
+}
+
+/// The month after a `YYYY-MM` month
+pub(crate) fn next_month(month: &str) -> Option<String> {
+    let (year, month) = split_ym(month)?;
+    Some(if month == 12 {
+        format!("{:04}-01", year + 1)
+    } else {
+        format!("{:04}-{:02}", year, month + 1)
+    })
+}
+
+/// Split a `YYYY-MM` string into numbers
+pub(crate) fn split_ym(month: &str) -> Option<(i64, u32)> {
+    let (y, m, _) = split_ymd(&format!("{}-01", month))?;
+    (1..=12).contains(&m).then_some((y, m))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_civil_days_round_trip() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+        for days in [-1, 0, 59, 11_016, 20_454, 20_513] {
+            let (y, m, d) = civil_from_days(days);
+            assert_eq!(days_from_civil(y, m, d), days);
+        }
+    }
+
+    #[test]
+    fn test_week_spanning_month_boundary() {
+        assert_eq!(week_start("2026-02-01").unwrap(), "2026-01-26");
+        assert_eq!(week_end("2026-01-28").unwrap(), "2026-02-01");
+        assert_eq!(week_start("2026-01-26").unwrap(), "2026-01-26");
+    }
+
+    #[test]
+    fn test_validate_date_format_leap_years() {
+        assert_eq!(validate_date_format("2024-02-29"), Ok(()));
+        assert_eq!(validate_date_format("2000-02-29"), Ok(()));
+        assert_eq!(
+            validate_date_format("2023-02-29"),
+            Err(ExpenseError::InvalidDate(
+                "`2023-02-29`: 2023-02 only has 28 days".to_string()
+            ))
+        );
+        assert!(validate_date_format("1900-02-29").is_err());
+        for year in ["2023", "2024"] {
+            assert!(validate_date_format(&format!("{}-02-30", year)).is_err());
+        }
+    }
+
+    #[test]
+    fn test_validate_date_format_ranges() {
+        assert_eq!(validate_date_format("2026-04-30"), Ok(()));
+        assert!(validate_date_format("2026-04-31").is_err());
+        assert!(validate_date_format("2026-13-01").is_err());
+        assert!(validate_date_format("2026-00-10").is_err());
+        assert!(validate_date_format("2026-01-00").is_err());
+        assert!(validate_date_format("2026-01-32").is_err());
+        assert!(validate_date_format("2026-1-05").is_err());
+        assert!(validate_date_format("26-01-05").is_err());
+        assert!(validate_date_format("2026/01/05").is_err());
+        assert!(validate_date_format("2026-01-05T10:00").is_err());
+    }
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(parse_date("2026-01-05"), Ok((2026, 1, 5)));
+        assert_eq!(parse_date("2024-02-29"), Ok((2024, 2, 29)));
+        assert_eq!(parse_date("0099-12-31"), Ok((99, 12, 31)));
+        for bad in [
+            "2023-02-29",
+            "2024-02-30",
+            "2026-04-31",
+            "2026-13-01",
+            "2026-00-10",
+            "2026-01-00",
+            "2026-01-32",
+            "2026-1-05",
+            "26-01-05",
+            "2026/01/05",
+            "2026-01-05T10:00",
+            "",
+        ] {
+            assert_eq!(parse_date(bad), Err(validate_date_format(bad).unwrap_err()));
+        }
+    }
+
+    #[test]
+    fn test_days_between_dates() {
+        assert_eq!(days_between_dates("2026-01-01", "2026-02-01"), Ok(31));
+        assert_eq!(days_between_dates("2024-02-28", "2024-03-01"), Ok(2));
+        assert_eq!(days_between_dates("2023-02-28", "2023-03-01"), Ok(1));
+        assert_eq!(days_between_dates("2026-02-01", "2026-01-01"), Ok(-31));
+        assert_eq!(days_between_dates("2025-01-01", "2026-01-01"), Ok(365));
+        assert_eq!(days_between_dates("2026-01-08", "2026-01-08"), Ok(0));
+        assert!(days_between_dates("2026-01-08", "2026-02-30").is_err());
+    }
+
+    #[test]
+    fn test_next_date() {
+        assert_eq!(next_date("2024-02-28", 1).unwrap(), "2024-02-29");
+        assert_eq!(next_date("2024-02-29", 1).unwrap(), "2024-03-01");
+        assert_eq!(next_date("2025-01-01", 365).unwrap(), "2026-01-01");
+        assert_eq!(next_date("2025-12-31", 1).unwrap(), "2026-01-01");
+        assert_eq!(next_date("2026-01-08", 0).unwrap(), "2026-01-08");
+        assert_eq!(
+            days_between_dates("2026-01-08", &next_date("2026-01-08", 1000).unwrap()),
+            Ok(1000)
+        );
+        assert!(next_date("2026-02-30", 1).is_err());
+        assert_eq!(next_date("9999-12-30", 1).unwrap(), "9999-12-31");
+        assert!(matches!(
+            next_date("9999-12-31", 1),
+            Err(ExpenseError::InvalidDate(_))
+        ));
+    }
+
+    #[test]
+    fn test_add_months_clamps_to_month_end() {
+        assert_eq!(add_months("2026-01-31", 1).unwrap(), "2026-02-28");
+        assert_eq!(add_months("2024-01-31", 1).unwrap(), "2024-02-29");
+        assert_eq!(add_months("2025-02-28", 1).unwrap(), "2025-03-28");
+        assert_eq!(add_months("2025-12-31", 1).unwrap(), "2026-01-31");
+        assert_eq!(add_months("2026-01-15", 0).unwrap(), "2026-01-15");
+        assert_eq!(add_months("2026-03-31", 13).unwrap(), "2027-04-30");
+        assert!(add_months("9999-12-01", 1).is_err());
+    }
+
+    #[test]
+    fn test_week_number() {
+        assert_eq!(week_number("2026-01-01"), Ok(1));
+        assert_eq!(week_number("2025-12-29"), Ok(1));
+        assert_eq!(week_number("2025-12-28"), Ok(52));
+        assert_eq!(week_number("2026-07-01"), Ok(27));
+        assert_eq!(week_number("2021-01-03"), Ok(53));
+        assert_eq!(week_number("2020-12-31"), Ok(53));
+        assert!(week_number("2026-13-01").is_err());
+        assert_eq!(iso_year_week("2025-12-29"), Ok((2026, 1)));
+        assert_eq!(iso_year_week("2021-01-03"), Ok((2020, 53)));
+    }
+
+    #[test]
+    fn test_quarter_of() {
+        assert_eq!(quarter_of("2026-01-01"), Ok(1));
+        assert_eq!(quarter_of("2026-03-31"), Ok(1));
+        assert_eq!(quarter_of("2026-04-01"), Ok(2));
+        assert_eq!(quarter_of("2026-09-30"), Ok(3));
+        assert_eq!(quarter_of("2026-12-25"), Ok(4));
+        assert!(quarter_of("2026-00-10").is_err());
+    }
+
+    #[test]
+    fn test_next_month_wraps_year() {
+        assert_eq!(next_month("2025-12").unwrap(), "2026-01");
+        assert_eq!(next_month("2026-01").unwrap(), "2026-02");
+        assert_eq!(next_month("2026-13"), None);
+    }
+
+    #[test]
+    fn test_week_bounds_need_a_real_date() {
+        assert_eq!(week_start("2026-03-04").unwrap(), "2026-03-02");
+        assert_eq!(week_end("2026-03-04").unwrap(), "2026-03-08");
+        assert_eq!(week_start("2026-02-30"), None);
+        assert_eq!(week_end("2026-02-30"), None);
+        assert_eq!(week_start("2026-13-01"), None);
+    }
+
+    #[test]
+    fn test_month_of_needs_a_real_date() {
+        assert_eq!(month_of("2026-02-28"), Some("2026-02"));
+        assert_eq!(month_of("2026-00-05"), None);
+        assert_eq!(month_of("2026-13-01"), None);
+        assert_eq!(month_of("2026-02-30"), None);
+    }
+}
@@ -0,0 +1,290 @@
+// This module compares two sets of expenses, such as two exports of the
+// same data file taken at different times
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::expense::Expense;
+
+/// What makes two expenses "the same" when comparing sets: amount,
+/// category and date, ignoring the id
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ExpenseKey {
+    amount_bits: u64,
+    category: String,
+    date: String,
+}
+
+impl ExpenseKey {
+    /// The key of one expense
+    pub fn of(expense: &Expense) -> ExpenseKey {
+        ExpenseKey {
+            // Adding 0.0 turns -0.0 into 0.0, so the two compare equal
+            amount_bits: (expense.amount + 0.0).to_bits(),
+            category: expense.category.clone(),
+            date: expense.date.clone(),
+        }
+    }
+}
+
+/// Split `incoming` into the expenses not yet in `existing` and the ones
+/// already recorded there, each in list order, matching by `ExpenseKey`
+///
+/// An incoming expense that repeats an earlier incoming one counts as
+/// recorded too, so nothing gets added twice. Merges, imports and import
+/// previews all decide what is new this way.
+pub fn split_recorded<'a>(
+    existing: &[Expense],
+    incoming: &'a [Expense],
+) -> (Vec<&'a Expense>, Vec<&'a Expense>) {
+    let mut recorded: HashSet<ExpenseKey> = existing.iter().map(ExpenseKey::of).collect();
+    incoming
+        .iter()
+        .partition(|expense| recorded.insert(ExpenseKey::of(expense)))
+}
+
+/// Groups of expenses on the same date in the same category whose amounts
+/// are at most `cents_tolerance` cents apart, e.g. the same purchase read
+/// back as 10.00 and 10.004999 after an import and export
+///
+/// Unlike `ExpenseKey`, which stays exact, this links amounts in a chain:
+/// with a tolerance of 1, 10.00, 10.01 and 10.02 form one group. Each
+/// group is sorted by amount, groups come out by date, category and lowest
+/// amount, and expenses with no near duplicate are left out.
+pub fn find_near_duplicates(expenses: &[Expense], cents_tolerance: i64) -> Vec<Vec<&Expense>> {
+    if cents_tolerance < 0 {
+        return Vec::new();
+    }
+    // Slack for amounts such as 10.02 - 10.01, which come out a hair over
+    // one cent in floating point
+    let tolerance = cents_tolerance as f64 + 1e-6;
+
+    let mut buckets: HashMap<(&str, &str), Vec<&Expense>> = HashMap::new();
+    for expense in expenses {
+        buckets
+            .entry((expense.category.as_str(), expense.date.as_str()))
+            .or_default()
+            .push(expense);
+    }
+
+    let mut groups = Vec::new();
+    for mut bucket in buckets.into_values() {
+        // Sorted, only neighbours need comparing
+        bucket.sort_by(|a, b| a.amount.total_cmp(&b.amount));
+        let mut group = vec![bucket[0]];
+        for pair in bucket.windows(2) {
+            if (pair[1].amount - pair[0].amount) * 100.0 > tolerance {
+                if group.len() > 1 {
+                    groups.push(group);
+                }
+                group = Vec::new();
+            }
+            group.push(pair[1]);
+        }
+        if group.len() > 1 {
+            groups.push(group);
+        }
+    }
+    groups.sort_by(|a: &Vec<&Expense>, b: &Vec<&Expense>| {
+        (&a[0].date, &a[0].category)
+            .cmp(&(&b[0].date, &b[0].category))
+            .then(a[0].amount.total_cmp(&b[0].amount))
+    });
+    groups
+}
+
+/// How two sets of expenses differ
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ExpenseDiff<'a> {
+    /// Expenses in the first set with no match in the second
+    pub only_in_a: Vec<&'a Expense>,
+    /// Expenses in the second set with no match in the first
+    pub only_in_b: Vec<&'a Expense>,
+    /// Unmatched expenses that share an id, as `(before, after)`
+    pub changed: Vec<(&'a Expense, &'a Expense)>,
+}
+
+impl ExpenseDiff<'_> {
+    /// Whether the two sets hold the same expenses
+    pub fn is_empty(&self) -> bool {
+        self.only_in_a.is_empty() && self.only_in_b.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compare two sets of expenses, ignoring their order
+///
+/// Expenses are matched by `ExpenseKey` as a multiset: two identical
+/// expenses in `a` and one in `b` leave one in `only_in_a`. Of what is left
+/// unmatched, an expense in `a` and one in `b` with the same id count as
+/// one changed expense rather than a removal plus an addition.
+pub fn diff_expense_sets<'a>(a: &'a [Expense], b: &'a [Expense]) -> ExpenseDiff<'a> {
+    let mut unmatched_b: HashMap<ExpenseKey, VecDeque<usize>> = HashMap::new();
+    for (i, expense) in b.iter().enumerate() {
+        unmatched_b
+            .entry(ExpenseKey::of(expense))
+            .or_default()
+            .push_back(i);
+    }
+
+    let mut matched_b = vec![false; b.len()];
+    let mut only_in_a = Vec::new();
+    for expense in a {
+        match unmatched_b
+            .get_mut(&ExpenseKey::of(expense))
+            .and_then(|indices| indices.pop_front())
+        {
+            Some(i) => matched_b[i] = true,
+            None => only_in_a.push(expense),
+        }
+    }
+    let mut only_in_b: Vec<&Expense> = b
+        .iter()
+        .zip(&matched_b)
+        .filter(|(_, matched)| !**matched)
+        .map(|(expense, _)| expense)
+        .collect();
+
+    let mut changed = Vec::new();
+    only_in_a.retain(
+        |before| match only_in_b.iter().position(|e| e.id == before.id) {
+            Some(i) => {
+                changed.push((*before, only_in_b.remove(i)));
+                false
+            }
+            None => true,
+        },
+    );
+
+    ExpenseDiff {
+        only_in_a,
+        only_in_b,
+        changed,
+    }
+}
+
+/// Render a diff one expense per line: `-` only in the first set, `+` only
+/// in the second, `~` changed, followed by a count of each
+pub fn format_diff(diff: &ExpenseDiff) -> String {
+    if diff.is_empty() {
+        return "no differences\n".to_string();
+    }
+    let mut out = String::new();
+    for expense in &diff.only_in_a {
+        out.push_str(&format!("- #{} {}\n", expense.id, expense));
+    }
+    for expense in &diff.only_in_b {
+        out.push_str(&format!("+ #{} {}\n", expense.id, expense));
+    }
+    for (before, after) in &diff.changed {
+        out.push_str(&format!("~ #{} {} -> {}\n", before.id, before, after));
+    }
+    out.push_str(&format!(
+        "{} removed, {} added, {} changed\n",
+        diff.only_in_a.len(),
+        diff.only_in_b.len(),
+        diff.changed.len()
+    ));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_counts_duplicates_as_a_multiset() {
+        let a = vec![
+            Expense::with_id(1, 4.5, "coffee", "2026-01-08"),
+            Expense::with_id(2, 4.5, "coffee", "2026-01-08"),
+            Expense::with_id(3, 20.0, "transport", "2026-01-08"),
+        ];
+        let b = vec![
+            Expense::with_id(13, 20.0, "transport", "2026-01-08"),
+            Expense::with_id(11, 4.5, "coffee", "2026-01-08"),
+        ];
+        let diff = diff_expense_sets(&a, &b);
+        assert_eq!(diff.only_in_a, vec![&a[1]]);
+        assert!(diff.only_in_b.is_empty());
+        assert!(diff.changed.is_empty());
+
+        let diff = diff_expense_sets(&b, &a);
+        assert!(diff.only_in_a.is_empty());
+        assert_eq!(diff.only_in_b, vec![&a[1]]);
+    }
+
+    #[test]
+    fn test_diff_ignores_order_and_pairs_changes_by_id() {
+        let a = vec![
+            Expense::with_id(1, 45.5, "food", "2026-01-08"),
+            Expense::with_id(2, 20.0, "transport", "2026-01-08"),
+            Expense::with_id(3, 500.0, "rent", "2026-01-01"),
+        ];
+        let b = vec![
+            Expense::with_id(3, 500.0, "rent", "2026-01-01"),
+            Expense::with_id(2, 25.0, "transport", "2026-01-08"),
+            Expense::with_id(1, 45.5, "food", "2026-01-08"),
+            Expense::with_id(4, 9.0, "coffee", "2026-01-09"),
+        ];
+        let diff = diff_expense_sets(&a, &b);
+        assert!(diff.only_in_a.is_empty());
+        assert_eq!(diff.only_in_b, vec![&b[3]]);
+        assert_eq!(diff.changed, vec![(&a[1], &b[1])]);
+
+        assert!(diff_expense_sets(&a, &a).is_empty());
+    }
+
+    #[test]
+    fn test_find_near_duplicates() {
+        let expenses = vec![
+            Expense::with_id(1, 10.0, "food", "2026-01-08"),
+            Expense::with_id(2, 10.004999, "food", "2026-01-08"),
+            Expense::with_id(3, 10.004999, "food", "2026-01-09"),
+            Expense::with_id(4, 10.0, "transport", "2026-01-08"),
+            Expense::with_id(5, 10.03, "food", "2026-01-08"),
+        ];
+        let ids = |groups: Vec<Vec<&Expense>>| -> Vec<Vec<u64>> {
+            groups
+                .iter()
+                .map(|group| group.iter().map(|e| e.id).collect())
+                .collect()
+        };
+        assert_eq!(ids(find_near_duplicates(&expenses, 1)), vec![vec![1, 2]]);
+        assert!(find_near_duplicates(&expenses, 0).is_empty());
+        assert_eq!(ids(find_near_duplicates(&expenses, 3)), vec![vec![1, 2, 5]]);
+        assert!(find_near_duplicates(&expenses, -1).is_empty());
+
+        // Chained a cent apart, despite float error in the differences
+        let expenses = vec![
+            Expense::with_id(1, 10.02, "food", "2026-01-08"),
+            Expense::with_id(2, 10.0, "food", "2026-01-08"),
+            Expense::with_id(3, 10.01, "food", "2026-01-08"),
+            Expense::with_id(4, 5.0, "food", "2026-01-08"),
+            Expense::with_id(5, 5.0, "food", "2026-01-08"),
+        ];
+        assert_eq!(
+            ids(find_near_duplicates(&expenses, 1)),
+            vec![vec![4, 5], vec![2, 3, 1]]
+        );
+        assert_eq!(ids(find_near_duplicates(&expenses, 0)), vec![vec![4, 5]]);
+    }
+
+    #[test]
+    fn test_format_diff() {
+        let a = vec![
+            Expense::with_id(1, 45.5, "food", "2026-01-08"),
+            Expense::with_id(2, 20.0, "transport", "2026-01-08"),
+        ];
+        let b = vec![
+            Expense::with_id(2, 25.0, "transport", "2026-01-08"),
+            Expense::with_id(4, 9.0, "coffee", "2026-01-09"),
+        ];
+        assert_eq!(
+            format_diff(&diff_expense_sets(&a, &b)),
+            "- #1 $45.50 - food (2026-01-08)
++ #4 $9.00 - coffee (2026-01-09)
+~ #2 $20.00 - transport (2026-01-08) -> $25.00 - transport (2026-01-08)
+1 removed, 1 added, 1 changed
+"
+        );
+        assert_eq!(format_diff(&diff_expense_sets(&a, &a)), "no differences\n");
+    }
+}
@@ -0,0 +1,366 @@
+// This module checks a data file for problems worth a look, e.g. after
+// editing it by hand (`expense doctor`)
+//
+// `read_rows` reads the file one row at a time, so a bad row becomes a
+// finding instead of stopping the load, and `diagnose` checks the expenses
+// that did load.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::config::Config;
+use crate::dates::{add_months, month_of};
+use crate::expense::Expense;
+use crate::storage::{from_csv, join_csv_fields, parse_csv, read_next_id};
+
+/// Dates before this are reported as out of range
+pub const EARLIEST_SANE_DATE: &str = "1970-01-01";
+
+/// How much a finding matters; any `Error` makes `expense doctor` fail
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Info => write!(f, "info"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// What kind of problem a finding is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FindingKind {
+    /// A row that couldn't be read as an expense
+    UnparseableRow,
+    /// Several expenses share one id
+    DuplicateId,
+    /// A date before `EARLIEST_SANE_DATE` or over a year from today
+    DateOutOfRange,
+    NegativeAmount,
+    /// A category that isn't in the registry
+    UnknownCategory,
+    /// An expense dated in a closed month
+    ClosedPeriod,
+    /// Stray whitespace or an uppercase category, which `apply_safe_fixes`
+    /// can tidy up
+    Untidy,
+}
+
+/// One problem found in a data file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub severity: Severity,
+    pub kind: FindingKind,
+    /// The expense concerned, if the finding is about one
+    pub id: Option<u64>,
+    pub message: String,
+}
+
+impl Finding {
+    fn about(expense: &Expense, severity: Severity, kind: FindingKind, message: String) -> Finding {
+        Finding {
+            severity,
+            kind,
+            id: Some(expense.id),
+            message: format!("#{} {}", expense.id, message),
+        }
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.message)
+    }
+}
+
+/// The categories the config knows about: those with a style, a template
+/// or auto-categorization keywords
+pub fn category_registry(config: &Config) -> BTreeSet<String> {
+    let mut registry: BTreeSet<String> = config.categories.keys().cloned().collect();
+    registry.extend(config.auto_categories.keys().cloned());
+    registry.extend(config.templates.values().map(|t| t.category.clone()));
+    registry
+}
+
+/// Read data file text row by row, returning the expenses that could be
+/// read and an `UnparseableRow` error for each row that couldn't
+pub fn read_rows(text: &str) -> (Vec<Expense>, Vec<Finding>) {
+    let unparseable = |message: String| Finding {
+        severity: Severity::Error,
+        kind: FindingKind::UnparseableRow,
+        id: None,
+        message,
+    };
+    let mut text = std::borrow::Cow::Borrowed(text);
+    match read_next_id(&text) {
+        // Blank the line out so the rows keep their line numbers
+        Ok(Some(_)) => {
+            let body = text.split_once('\n').map(|(_, body)| body).unwrap_or("");
+            text = format!("\n{}", body).into();
+        }
+        Ok(None) => {}
+        Err(err) => return (Vec::new(), vec![unparseable(err.to_string())]),
+    }
+    let rows = match parse_csv(&text) {
+        Ok(rows) => rows,
+        Err(err) => return (Vec::new(), vec![unparseable(err.to_string())]),
+    };
+    let mut rows = rows.into_iter();
+    let Some((_, header)) = rows.next() else {
+        return (Vec::new(), Vec::new());
+    };
+    let header = join_csv_fields(&header);
+    if let Err(err) = from_csv(&header) {
+        return (Vec::new(), vec![unparseable(err.to_string())]);
+    }
+
+    let mut expenses = Vec::new();
+    let mut findings = Vec::new();
+    for (line, fields) in rows {
+        // Each row on its own under the header reads as line 2
+        let row = format!("{}\n{}\n", header, join_csv_fields(&fields));
+        match from_csv(&row) {
+            Ok(read) => expenses.extend(read),
+            Err(err) => {
+                let message = err
+                    .to_string()
+                    .replacen("line 2", &format!("line {}", line), 1);
+                findings.push(unparseable(message));
+            }
+        }
+    }
+    (expenses, findings)
+}
+
+/// Check loaded expenses for duplicate ids, dates out of range, negative
+/// amounts, categories missing from `registry`, expenses in the config's
+/// closed months and untidy text
+///
+/// An empty registry skips the category check. Duplicate ids come first,
+/// then the findings for each expense in list order.
+pub fn diagnose(
+    expenses: &[Expense],
+    registry: &BTreeSet<String>,
+    config: &Config,
+    today: &str,
+) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut by_id: BTreeMap<u64, usize> = BTreeMap::new();
+    for expense in expenses {
+        *by_id.entry(expense.id).or_insert(0) += 1;
+    }
+    for (id, count) in by_id.into_iter().filter(|(_, count)| *count > 1) {
+        findings.push(Finding {
+            severity: Severity::Error,
+            kind: FindingKind::DuplicateId,
+            id: Some(id),
+            message: format!("id {} is used by {} expenses", id, count),
+        });
+    }
+
+    let latest = add_months(today, 12).ok();
+    for expense in expenses {
+        let date = expense.date.as_str();
+        if date < EARLIEST_SANE_DATE {
+            let message = format!("is dated {}, before {}", date, EARLIEST_SANE_DATE);
+            findings.push(Finding::about(
+                expense,
+                Severity::Warning,
+                FindingKind::DateOutOfRange,
+                message,
+            ));
+        } else if latest.as_deref().is_some_and(|latest| date > latest) {
+            let message = format!("is dated {}, over a year after today", date);
+            findings.push(Finding::about(
+                expense,
+                Severity::Warning,
+                FindingKind::DateOutOfRange,
+                message,
+            ));
+        }
+        if expense.amount < 0.0 {
+            let message = format!("has a negative amount ({:.2})", expense.amount);
+            findings.push(Finding::about(
+                expense,
+                Severity::Warning,
+                FindingKind::NegativeAmount,
+                message,
+            ));
+        }
+        let category = tidy_category(&expense.category);
+        if !registry.is_empty() && !registry.contains(&category) {
+            let message = format!("has category `{}`, which isn't configured", category);
+            findings.push(Finding::about(
+                expense,
+                Severity::Warning,
+                FindingKind::UnknownCategory,
+                message,
+            ));
+        }
+        if let Some(month) = month_of(date).filter(|m| config.closed_periods.contains(*m)) {
+            findings.push(Finding::about(
+                expense,
+                Severity::Info,
+                FindingKind::ClosedPeriod,
+                format!("is in closed month {}", month),
+            ));
+        }
+        if tidied(expense) != *expense {
+            let message = "has stray whitespace or an uppercase category (--fix tidies it)";
+            findings.push(Finding::about(
+                expense,
+                Severity::Warning,
+                FindingKind::Untidy,
+                message.to_string(),
+            ));
+        }
+    }
+    findings
+}
+
+/// Whether any finding is an error
+pub fn has_errors(findings: &[Finding]) -> bool {
+    findings.iter().any(|f| f.severity == Severity::Error)
+}
+
+/// Trim whitespace from every text field and lowercase categories,
+/// returning how many expenses changed
+///
+/// Nothing else is touched, so the fixes are safe to apply blindly.
+pub fn apply_safe_fixes(expenses: &mut [Expense]) -> usize {
+    let mut fixed = 0;
+    for expense in expenses.iter_mut() {
+        let tidy = tidied(expense);
+        if tidy != *expense {
+            *expense = tidy;
+            fixed += 1;
+        }
+    }
+    fixed
+}
+
+fn tidy_category(category: &str) -> String {
+    category.trim().to_lowercase()
+}
+
+/// The expense after `apply_safe_fixes`
+fn tidied(expense: &Expense) -> Expense {
+    let text = |value: &Option<String>| {
+        value
+            .as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+    };
+    Expense {
+        category: tidy_category(&expense.category),
+        date: expense.date.trim().to_string(),
+        note: text(&expense.note),
+        payee: text(&expense.payee),
+        tags: expense.tags.iter().map(|t| t.trim().to_string()).collect(),
+        currency: text(&expense.currency),
+        subcategory: text(&expense.subcategory),
+        receipt_number: text(&expense.receipt_number),
+        ..expense.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(findings: &[Finding]) -> Vec<(FindingKind, Option<u64>)> {
+        findings.iter().map(|f| (f.kind, f.id)).collect()
+    }
+
+    #[test]
+    fn test_read_rows_reports_bad_rows_by_line() {
+        let text = "# next_id=9\nid,amount,category,date\n1,45.5,food,2026-01-08\n2,lots,food,2026-01-08\n3,20,transport,2026-02-30\n";
+        let (expenses, findings) = read_rows(text);
+        assert_eq!(
+            expenses,
+            vec![Expense::with_id(1, 45.5, "food", "2026-01-08")]
+        );
+        assert_eq!(findings.len(), 2);
+        assert!(
+            findings
+                .iter()
+                .all(|f| f.kind == FindingKind::UnparseableRow)
+        );
+        assert!(findings[0].message.contains("line 4"), "{}", findings[0]);
+        assert!(findings[1].message.contains("line 5"), "{}", findings[1]);
+        assert!(has_errors(&findings));
+
+        let (expenses, findings) = read_rows("id,amount\n1,2\n");
+        assert!(expenses.is_empty());
+        assert_eq!(findings.len(), 1);
+        assert_eq!(read_rows(""), (Vec::new(), Vec::new()));
+    }
+
+    #[test]
+    fn test_diagnose_finds_each_kind_of_problem() {
+        let mut config = Config::default();
+        config
+            .categories
+            .insert("food".to_string(), Default::default());
+        config.closed_periods.insert("2026-01".to_string());
+        let registry = category_registry(&config);
+        let today = "2026-03-01";
+
+        let expenses = vec![
+            Expense::with_id(1, 45.5, "food", "2026-02-08"),
+            Expense::with_id(2, 10.0, "food", "1969-12-31"),
+            Expense::with_id(3, 10.0, "food", "2027-03-02"),
+            Expense::with_id(4, -5.0, "food", "2026-02-08"),
+            Expense::with_id(5, 20.0, "travel", "2026-02-08"),
+            Expense::with_id(6, 20.0, "food", "2026-01-31"),
+            Expense::with_id(7, 20.0, " Food", "2026-02-08"),
+            Expense::with_id(1, 9.0, "food", "2026-02-09"),
+        ];
+        let findings = diagnose(&expenses, &registry, &config, today);
+        assert_eq!(
+            kinds(&findings),
+            vec![
+                (FindingKind::DuplicateId, Some(1)),
+                (FindingKind::DateOutOfRange, Some(2)),
+                (FindingKind::DateOutOfRange, Some(3)),
+                (FindingKind::NegativeAmount, Some(4)),
+                (FindingKind::UnknownCategory, Some(5)),
+                (FindingKind::ClosedPeriod, Some(6)),
+                (FindingKind::Untidy, Some(7)),
+            ]
+        );
+        assert_eq!(findings[0].to_string(), "error: id 1 is used by 2 expenses");
+        assert_eq!(findings[6].severity, Severity::Warning);
+
+        // Exactly a year ahead is still fine, and no registry means no
+        // category check
+        let expenses = vec![Expense::with_id(1, 1.0, "travel", "2027-03-01")];
+        assert!(diagnose(&expenses, &BTreeSet::new(), &Config::default(), today).is_empty());
+    }
+
+    #[test]
+    fn test_apply_safe_fixes_tidies_text() {
+        let mut expenses = vec![
+            Expense::with_id(1, 45.5, " Food ", "2026-01-08").with_note(" lunch"),
+            Expense::with_id(2, 20.0, "transport", "2026-01-08"),
+        ];
+        expenses[1].payee = Some("  ".to_string());
+        assert_eq!(apply_safe_fixes(&mut expenses), 2);
+        assert_eq!(
+            expenses,
+            vec![
+                Expense::with_id(1, 45.5, "food", "2026-01-08").with_note("lunch"),
+                Expense::with_id(2, 20.0, "transport", "2026-01-08"),
+            ]
+        );
+        assert_eq!(apply_safe_fixes(&mut expenses), 0);
+    }
+}
@@ -0,0 +1,61 @@
+// This module defines the error type shared by the expense tracker
+
+use std::fmt;
+
+/// Everything that can go wrong while working with expenses
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExpenseError {
+    /// An amount that is negative, not a number, or otherwise unusable
+    InvalidAmount(String),
+    /// A date that is not a valid `YYYY-MM-DD` date
+    InvalidDate(String),
+    /// Data that could not be decoded (bad file contents, bad format)
+    ParseError(String),
+    /// Reading or writing a file failed
+    IoError(String),
+    /// A network request failed or was rejected by the server
+    HttpError(String),
+    /// Something looked up by name or id doesn't exist
+    NotFound(String),
+    /// A change to an expense dated in a month that has been closed
+    PeriodClosed(String),
+}
+
+impl fmt::Display for ExpenseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExpenseError::InvalidAmount(msg) => write!(f, "invalid amount: {}", msg),
+            ExpenseError::InvalidDate(msg) => write!(f, "invalid date: {}", msg),
+            ExpenseError::ParseError(msg) => write!(f, "parse error: {}", msg),
+            ExpenseError::IoError(msg) => write!(f, "io error: {}", msg),
+            ExpenseError::HttpError(msg) => write!(f, "http error: {}", msg),
+            ExpenseError::NotFound(msg) => write!(f, "not found: {}", msg),
+            ExpenseError::PeriodClosed(msg) => write!(f, "period closed: {}", msg),
+        }
+    }
+}
+
+impl ExpenseError {
+    /// The same error with `context` (e.g. a file name) in front of its
+    /// message
+    pub fn with_context(self, context: &str) -> ExpenseError {
+        let add = |msg: String| format!("{}: {}", context, msg);
+        match self {
+            ExpenseError::InvalidAmount(msg) => ExpenseError::InvalidAmount(add(msg)),
+            ExpenseError::InvalidDate(msg) => ExpenseError::InvalidDate(add(msg)),
+            ExpenseError::ParseError(msg) => ExpenseError::ParseError(add(msg)),
+            ExpenseError::IoError(msg) => ExpenseError::IoError(add(msg)),
+            ExpenseError::HttpError(msg) => ExpenseError::HttpError(add(msg)),
+            ExpenseError::NotFound(msg) => ExpenseError::NotFound(add(msg)),
+            ExpenseError::PeriodClosed(msg) => ExpenseError::PeriodClosed(add(msg)),
+        }
+    }
+}
+
+impl std::error::Error for ExpenseError {}
+
+impl From<std::io::Error> for ExpenseError {
+    fn from(err: std::io::Error) -> Self {
+        ExpenseError::IoError(err.to_string())
+    }
+}
@@ -0,0 +1,380 @@
+// This module defines the Expense struct and its methods
+
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+use crate::currency::{DEFAULT_CURRENCY, format_amount};
+use crate::dates::validate_date_format;
+use crate::error::ExpenseError;
+
+/// Largest amount a single expense can have
+///
+/// Far below what `i64` cents can hold, so even many maximal amounts can
+/// be summed exactly in cents.
+pub const MAX_AMOUNT: f64 = 1e12;
+
+/// Whether an entry is money spent or money received
+///
+/// Most functions add up every entry alike; `cashflow` keeps income apart
+/// from spending.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransactionKind {
+    #[default]
+    Expense,
+    Income,
+}
+
+impl TransactionKind {
+    /// Whether this is the default kind, which files leave unwritten
+    pub fn is_expense(&self) -> bool {
+        *self == TransactionKind::Expense
+    }
+}
+
+impl fmt::Display for TransactionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransactionKind::Expense => write!(f, "expense"),
+            TransactionKind::Income => write!(f, "income"),
+        }
+    }
+}
+
+/// Source of ids for newly created expenses
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Expense {
+    pub id: u64,
+    pub amount: f64,
+    pub category: String,
+    pub date: String,
+    /// Free-form remark, e.g. what was bought
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// Who was paid, e.g. the shop or merchant
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payee: Option<String>,
+    /// Free-form labels such as `work` or `holiday`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// ISO 4217 code such as `NGN`; `None` means the configured default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+    /// Spent or received
+    #[serde(default, skip_serializing_if = "TransactionKind::is_expense")]
+    pub kind: TransactionKind,
+    /// A finer grouping within the category, e.g. `grocery` under `food`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subcategory: Option<String>,
+    /// The number printed on the receipt, to find it again when auditing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub receipt_number: Option<String>,
+}
+
+impl Expense {
+    /// Creates a new Expense with a fresh id
+    pub fn new(amount: f64, category: &str, date: &str) -> Expense {
+        Expense::with_id(
+            NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            amount,
+            category,
+            date,
+        )
+    }
+
+    /// Creates a new Expense after checking the amount and date
+    pub fn try_new(amount: f64, category: &str, date: &str) -> Result<Expense, ExpenseError> {
+        validate_amount(amount)?;
+        validate_date_format(date)?;
+        Ok(Expense::new(amount, category, date))
+    }
+
+    /// Whether the expense passes `validate_expense`
+    pub fn is_valid(&self) -> bool {
+        validate_expense(self).is_ok()
+    }
+
+    /// Creates an Expense with a known id (e.g. one read back from a file)
+    ///
+    /// Ids handed out by `Expense::new` afterwards are always greater, so a
+    /// loaded expense never shares its id with a new one.
+    pub fn with_id(id: u64, amount: f64, category: &str, date: &str) -> Expense {
+        reserve_ids_through(id);
+        Expense {
+            id,
+            amount,
+            category: category.to_string(),
+            date: date.to_string(),
+            note: None,
+            payee: None,
+            tags: Vec::new(),
+            currency: None,
+            kind: TransactionKind::Expense,
+            subcategory: None,
+            receipt_number: None,
+        }
+    }
+
+    /// Returns the expense with a fresh id, e.g. for a copy of it that
+    /// becomes a new expense; every other field is kept
+    pub fn with_fresh_id(mut self) -> Expense {
+        self.id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        self
+    }
+
+    /// Returns the expense with a note attached
+    pub fn with_note(mut self, note: &str) -> Expense {
+        self.note = Some(note.to_string());
+        self
+    }
+
+    /// Returns the expense with a payee attached
+    pub fn with_payee(mut self, payee: &str) -> Expense {
+        self.payee = Some(payee.to_string());
+        self
+    }
+
+    /// Returns the expense with a tag added
+    pub fn with_tag(mut self, tag: &str) -> Expense {
+        self.tags.push(tag.to_string());
+        self
+    }
+
+    /// Returns the expense in the currency with ISO code `currency`
+    pub fn with_currency(mut self, currency: &str) -> Expense {
+        self.currency = Some(currency.to_string());
+        self
+    }
+
+    /// Returns the entry as the given kind, e.g. `Income` for a salary
+    pub fn with_kind(mut self, kind: TransactionKind) -> Expense {
+        self.kind = kind;
+        self
+    }
+
+    /// Returns the expense with a subcategory attached
+    pub fn with_subcategory(mut self, subcategory: &str) -> Expense {
+        self.subcategory = Some(subcategory.to_string());
+        self
+    }
+
+    /// Returns the expense with a receipt number attached
+    pub fn with_receipt_number(mut self, receipt_number: &str) -> Expense {
+        self.receipt_number = Some(receipt_number.to_string());
+        self
+    }
+
+    /// Whether the expense has `tag`
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
+    /// The expense in canonical form, for comparing copies of it that went
+    /// through different storage formats
+    ///
+    /// Strings are trimmed, the category is lowercased and the currency
+    /// uppercased, empty optional fields become `None`, blank tags are
+    /// dropped and the amount is rounded to whole cents.
+    pub fn canonicalize(&self) -> Expense {
+        let text = |value: &Option<String>| {
+            value
+                .as_deref()
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        };
+        Expense {
+            id: self.id,
+            // Adding 0.0 turns -0.0 into 0.0
+            amount: (self.amount * 100.0).round() / 100.0 + 0.0,
+            category: self.category.trim().to_lowercase(),
+            date: self.date.trim().to_string(),
+            note: text(&self.note),
+            payee: text(&self.payee),
+            tags: self
+                .tags
+                .iter()
+                .map(|t| t.trim())
+                .filter(|t| !t.is_empty())
+                .map(str::to_string)
+                .collect(),
+            currency: text(&self.currency).map(|c| c.to_uppercase()),
+            kind: self.kind,
+            subcategory: text(&self.subcategory).map(|s| s.to_lowercase()),
+            receipt_number: text(&self.receipt_number),
+        }
+    }
+
+    /// Display an expense nicely
+    #[cfg(feature = "fs")]
+    pub fn display(&self) {
+        println!("{}", self);
+    }
+}
+
+impl fmt::Display for Expense {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let currency = self.currency.as_deref().unwrap_or(DEFAULT_CURRENCY);
+        write!(
+            f,
+            "{} - {} ({})",
+            format_amount(self.amount, currency),
+            self.category,
+            self.date
+        )
+    }
+}
+
+/// Check that an amount is a finite number from 0 to `MAX_AMOUNT`
+pub fn validate_amount(amount: f64) -> Result<(), ExpenseError> {
+    if amount < 0.0 {
+        return Err(ExpenseError::InvalidAmount(format!(
+            "`{}` is negative",
+            amount
+        )));
+    }
+    check_magnitude(amount)
+}
+
+/// Check an expense's amount and date, as `Expense::try_new` does
+pub fn validate_expense(expense: &Expense) -> Result<(), ExpenseError> {
+    validate_amount(expense.amount)?;
+    validate_date_format(&expense.date)
+}
+
+/// Parse an amount such as `12.50`
+///
+/// Negative amounts (refunds in imported statements) are allowed, but not
+/// infinity, NaN or anything bigger than `MAX_AMOUNT` either way.
+pub fn parse_amount(text: &str) -> Result<f64, ExpenseError> {
+    let amount = text
+        .trim()
+        .parse::<f64>()
+        .map_err(|_| ExpenseError::InvalidAmount(format!("`{}` is not a number", text)))?;
+    check_magnitude(amount)?;
+    Ok(amount)
+}
+
+/// Convert an amount to whole cents, rounding to the nearest cent
+///
+/// Fails rather than wrapping for amounts outside `MAX_AMOUNT`.
+pub fn to_cents(amount: f64) -> Result<i64, ExpenseError> {
+    check_magnitude(amount)?;
+    Ok((amount * 100.0).round() as i64)
+}
+
+fn check_magnitude(amount: f64) -> Result<(), ExpenseError> {
+    if !amount.is_finite() {
+        Err(ExpenseError::InvalidAmount(format!(
+            "`{}` is not finite",
+            amount
+        )))
+    } else if amount.abs() > MAX_AMOUNT {
+        Err(ExpenseError::InvalidAmount(format!(
+            "`{}` is larger than {}",
+            amount, MAX_AMOUNT
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+/// The id the next `Expense::new` will hand out
+pub fn next_id() -> u64 {
+    NEXT_ID.load(Ordering::Relaxed)
+}
+
+/// Make sure `Expense::new` only hands out ids greater than `id`
+pub fn reserve_ids_through(id: u64) {
+    NEXT_ID.fetch_max(id.saturating_add(1), Ordering::Relaxed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_try_new_checks_amount_bounds() {
+        assert!(Expense::try_new(MAX_AMOUNT, "rent", "2026-01-08").is_ok());
+        assert!(Expense::try_new(0.0, "rent", "2026-01-08").is_ok());
+        assert_eq!(
+            Expense::try_new(MAX_AMOUNT + 1.0, "rent", "2026-01-08"),
+            Err(ExpenseError::InvalidAmount(
+                "`1000000000001` is larger than 1000000000000".to_string()
+            ))
+        );
+        assert!(Expense::try_new(-1.0, "rent", "2026-01-08").is_err());
+        assert!(Expense::try_new(f64::INFINITY, "rent", "2026-01-08").is_err());
+        assert!(Expense::try_new(f64::NAN, "rent", "2026-01-08").is_err());
+        assert!(Expense::try_new(10.0, "rent", "2026-02-30").is_err());
+    }
+
+    #[test]
+    fn test_is_valid() {
+        assert!(Expense::new(45.5, "food", "2026-01-08").is_valid());
+        let negative = Expense {
+            id: 1,
+            amount: -1.0,
+            category: "food".to_string(),
+            date: "2026-01-08".to_string(),
+            note: None,
+            payee: None,
+            tags: Vec::new(),
+            currency: None,
+            kind: TransactionKind::Expense,
+            subcategory: None,
+            receipt_number: None,
+        };
+        assert!(!negative.is_valid());
+        assert!(validate_expense(&negative).is_err());
+        assert!(!Expense::new(45.5, "food", "2026-13-01").is_valid());
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        let mut expense = Expense::with_id(7, 12.345, "  Café ", " 2026-01-08")
+            .with_note("  lunch ")
+            .with_tag(" work")
+            .with_tag("  ")
+            .with_currency("eur");
+        expense.payee = Some("   ".to_string());
+        let canonical = expense.canonicalize();
+        assert_eq!(
+            canonical,
+            Expense::with_id(7, 12.35, "café", "2026-01-08")
+                .with_note("lunch")
+                .with_tag("work")
+                .with_currency("EUR")
+        );
+        assert_eq!(canonical.canonicalize(), canonical);
+
+        let rounded = Expense::with_id(8, -0.001, "food", "2026-01-08").canonicalize();
+        assert!(rounded.amount == 0.0 && rounded.amount.is_sign_positive());
+    }
+
+    #[test]
+    fn test_parse_amount() {
+        assert_eq!(parse_amount(" 12.50 "), Ok(12.5));
+        assert_eq!(parse_amount("-3"), Ok(-3.0));
+        assert_eq!(parse_amount("1000000000000"), Ok(MAX_AMOUNT));
+        assert!(parse_amount("1000000000000.01").is_err());
+        assert!(parse_amount("-1e18").is_err());
+        assert!(parse_amount("inf").is_err());
+        assert!(parse_amount("NaN").is_err());
+        assert!(parse_amount("ten").is_err());
+    }
+
+    #[test]
+    fn test_to_cents() {
+        assert_eq!(to_cents(12.345), Ok(1235));
+        assert_eq!(to_cents(MAX_AMOUNT), Ok(100_000_000_000_000));
+        assert_eq!(to_cents(-MAX_AMOUNT), Ok(-100_000_000_000_000));
+        assert!(to_cents(1e18).is_err());
+        assert!(to_cents(f64::INFINITY).is_err());
+        assert!(to_cents(f64::NAN).is_err());
+    }
+}
@@ -0,0 +1,217 @@
+// This module exposes the core operations through a C ABI (enabled by the
+// `ffi` feature), for callers such as Python's ctypes
+//
+// A list is an opaque pointer made by `expense_list_new` and released by
+// `expense_list_free`. No function panics across the boundary: every one
+// checks its pointers and strings, and catches panics, reporting problems
+// through its return value.
+
+use std::ffi::{CStr, c_char};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+
+use crate::dates::validate_date_format;
+use crate::expense::{Expense, validate_amount};
+use crate::operations::{calculate_total, total_by_category};
+
+/// The call succeeded
+pub const EXPENSE_OK: i32 = 0;
+/// A required pointer was null
+pub const EXPENSE_ERR_NULL: i32 = -1;
+/// A string was not valid UTF-8
+pub const EXPENSE_ERR_UTF8: i32 = -2;
+/// The amount was negative, infinite, NaN or above `MAX_AMOUNT`
+pub const EXPENSE_ERR_AMOUNT: i32 = -3;
+/// The date was not a valid `YYYY-MM-DD` date
+pub const EXPENSE_ERR_DATE: i32 = -4;
+/// Something unexpected went wrong inside the library
+pub const EXPENSE_ERR_PANIC: i32 = -5;
+
+/// A list of expenses owned by the caller
+pub struct ExpenseList {
+    expenses: Vec<Expense>,
+}
+
+/// Create an empty list; release it with `expense_list_free`
+///
+/// Returns null if the list could not be created.
+#[unsafe(no_mangle)]
+pub extern "C" fn expense_list_new() -> *mut ExpenseList {
+    catch_unwind(|| {
+        Box::into_raw(Box::new(ExpenseList {
+            expenses: Vec::new(),
+        }))
+    })
+    .unwrap_or(std::ptr::null_mut())
+}
+
+/// Release a list made by `expense_list_new`; null is ignored
+///
+/// # Safety
+///
+/// `list` must be null or a pointer from `expense_list_new` that has not
+/// been freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn expense_list_free(list: *mut ExpenseList) {
+    if list.is_null() {
+        return;
+    }
+    let _ = catch_unwind(AssertUnwindSafe(|| {
+        // SAFETY: the caller guarantees `list` came from `expense_list_new`
+        drop(unsafe { Box::from_raw(list) });
+    }));
+}
+
+/// Add an expense; returns `EXPENSE_OK` or one of the `EXPENSE_ERR_` codes
+///
+/// # Safety
+///
+/// `list` must be null or a live pointer from `expense_list_new`;
+/// `category` and `date` must be null or NUL-terminated strings.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn expense_list_add(
+    list: *mut ExpenseList,
+    amount: f64,
+    category: *const c_char,
+    date: *const c_char,
+) -> i32 {
+    catch_unwind(AssertUnwindSafe(|| {
+        // SAFETY: the caller guarantees the pointers are null or valid
+        let (Some(list), Some(category), Some(date)) = (
+            unsafe { list.as_mut() },
+            unsafe { c_str(category) },
+            unsafe { c_str(date) },
+        ) else {
+            return EXPENSE_ERR_NULL;
+        };
+        let (Ok(category), Ok(date)) = (category.to_str(), date.to_str()) else {
+            return EXPENSE_ERR_UTF8;
+        };
+        if validate_amount(amount).is_err() {
+            return EXPENSE_ERR_AMOUNT;
+        }
+        if validate_date_format(date).is_err() {
+            return EXPENSE_ERR_DATE;
+        }
+        list.expenses.push(Expense::new(amount, category, date));
+        EXPENSE_OK
+    }))
+    .unwrap_or(EXPENSE_ERR_PANIC)
+}
+
+/// Sum of all amounts, or NaN if `list` is null
+///
+/// # Safety
+///
+/// `list` must be null or a live pointer from `expense_list_new`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn expense_list_total(list: *const ExpenseList) -> f64 {
+    catch_unwind(AssertUnwindSafe(|| {
+        // SAFETY: the caller guarantees `list` is null or valid
+        match unsafe { list.as_ref() } {
+            Some(list) => calculate_total(&list.expenses),
+            None => f64::NAN,
+        }
+    }))
+    .unwrap_or(f64::NAN)
+}
+
+/// Sum of the amounts in one category, or NaN if an argument is null or
+/// the category isn't UTF-8
+///
+/// # Safety
+///
+/// `list` must be null or a live pointer from `expense_list_new`;
+/// `category` must be null or a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn expense_list_total_by_category(
+    list: *const ExpenseList,
+    category: *const c_char,
+) -> f64 {
+    catch_unwind(AssertUnwindSafe(|| {
+        // SAFETY: the caller guarantees the pointers are null or valid
+        let (Some(list), Some(category)) = (unsafe { list.as_ref() }, unsafe { c_str(category) })
+        else {
+            return f64::NAN;
+        };
+        match category.to_str() {
+            Ok(category) => total_by_category(&list.expenses, category),
+            Err(_) => f64::NAN,
+        }
+    }))
+    .unwrap_or(f64::NAN)
+}
+
+/// Borrow a C string, or `None` for a null pointer
+///
+/// # Safety
+///
+/// `ptr` must be null or point to a NUL-terminated string.
+unsafe fn c_str<'a>(ptr: *const c_char) -> Option<&'a CStr> {
+    // SAFETY: checked for null; the caller guarantees termination
+    (!ptr.is_null()).then(|| unsafe { CStr::from_ptr(ptr) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    #[test]
+    fn test_add_and_totals() {
+        let list = expense_list_new();
+        let food = CString::new("food").unwrap();
+        let rent = CString::new("rent").unwrap();
+        let date = CString::new("2026-01-08").unwrap();
+        unsafe {
+            assert_eq!(
+                expense_list_add(list, 45.5, food.as_ptr(), date.as_ptr()),
+                EXPENSE_OK
+            );
+            assert_eq!(
+                expense_list_add(list, 30.0, food.as_ptr(), date.as_ptr()),
+                EXPENSE_OK
+            );
+            assert_eq!(
+                expense_list_add(list, 500.0, rent.as_ptr(), date.as_ptr()),
+                EXPENSE_OK
+            );
+            assert_eq!(expense_list_total(list), 575.5);
+            assert_eq!(expense_list_total_by_category(list, food.as_ptr()), 75.5);
+            expense_list_free(list);
+        }
+    }
+
+    #[test]
+    fn test_null_and_invalid_inputs() {
+        let list = expense_list_new();
+        let food = CString::new("food").unwrap();
+        let date = CString::new("2026-01-08").unwrap();
+        let bad_date = CString::new("2026-02-30").unwrap();
+        let not_utf8 = CString::new(vec![0xff, 0xfe]).unwrap();
+        let null = std::ptr::null();
+        unsafe {
+            let add = |amount, category, date| expense_list_add(list, amount, category, date);
+            assert_eq!(add(1.0, null, date.as_ptr()), EXPENSE_ERR_NULL);
+            assert_eq!(add(1.0, food.as_ptr(), null), EXPENSE_ERR_NULL);
+            assert_eq!(
+                expense_list_add(std::ptr::null_mut(), 1.0, food.as_ptr(), date.as_ptr()),
+                EXPENSE_ERR_NULL
+            );
+            assert_eq!(add(1.0, not_utf8.as_ptr(), date.as_ptr()), EXPENSE_ERR_UTF8);
+            assert_eq!(add(-1.0, food.as_ptr(), date.as_ptr()), EXPENSE_ERR_AMOUNT);
+            assert_eq!(
+                add(f64::NAN, food.as_ptr(), date.as_ptr()),
+                EXPENSE_ERR_AMOUNT
+            );
+            assert_eq!(add(1.0, food.as_ptr(), bad_date.as_ptr()), EXPENSE_ERR_DATE);
+
+            assert_eq!(expense_list_total(list), 0.0);
+            assert!(expense_list_total(std::ptr::null()).is_nan());
+            assert!(expense_list_total_by_category(list, null).is_nan());
+            assert!(expense_list_total_by_category(list, not_utf8.as_ptr()).is_nan());
+
+            expense_list_free(list);
+            expense_list_free(std::ptr::null_mut());
+        }
+    }
+}
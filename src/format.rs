@@ -0,0 +1,785 @@
+// This module turns expenses into text for the terminal: tables, reports
+// and category names decorated with the configured emoji and colors
+
+use std::collections::BTreeMap;
+
+use crate::cashflow::CashFlow;
+use crate::config::Config;
+use crate::currency::{DEFAULT_CURRENCY, format_amount};
+use crate::dates::month_of;
+use crate::expense::Expense;
+use crate::operations::*;
+
+/// Settings that control how expenses are rendered
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DisplayOptions {
+    /// Use ANSI colors for categories that have one configured
+    pub color: bool,
+    /// Category metadata and other display settings
+    pub config: Config,
+}
+
+impl DisplayOptions {
+    /// Options that render plain text with no category metadata
+    pub fn plain() -> DisplayOptions {
+        DisplayOptions::default()
+    }
+}
+
+/// ANSI escape sequence for a color name
+fn ansi_code(color: &str) -> Option<&'static str> {
+    match color.to_ascii_lowercase().as_str() {
+        "red" => Some("\x1b[31m"),
+        "green" => Some("\x1b[32m"),
+        "yellow" => Some("\x1b[33m"),
+        "blue" => Some("\x1b[34m"),
+        "magenta" => Some("\x1b[35m"),
+        "cyan" => Some("\x1b[36m"),
+        "white" => Some("\x1b[37m"),
+        _ => None,
+    }
+}
+
+const ANSI_RESET: &str = "\x1b[0m";
+
+/// How wide a string looks in a terminal (emoji take two columns)
+pub fn display_width(text: &str) -> usize {
+    text.chars()
+        .map(|c| match c as u32 {
+            0xFE0F | 0x200D => 0,
+            0x1F000..=0x1FAFF | 0x2600..=0x27BF => 2,
+            _ => 1,
+        })
+        .sum()
+}
+
+/// Pad `text` with spaces on the right to `width` columns
+fn pad_right(text: &str, width: usize) -> String {
+    let padding = width.saturating_sub(display_width(text));
+    format!("{}{}", text, " ".repeat(padding))
+}
+
+/// Pad `text` with spaces on the left to `width` columns
+fn pad_left(text: &str, width: usize) -> String {
+    let padding = width.saturating_sub(display_width(text));
+    format!("{}{}", " ".repeat(padding), text)
+}
+
+/// A category's name as shown to the user, without any color
+///
+/// Uses the configured label and emoji when present and falls back to the
+/// plain name for unknown categories.
+pub fn category_text(category: &str, opts: &DisplayOptions) -> String {
+    match opts.config.style(category) {
+        Some(style) => {
+            let name = style.label.as_deref().unwrap_or(category);
+            match &style.emoji {
+                Some(emoji) => format!("{} {}", emoji, name),
+                None => name.to_string(),
+            }
+        }
+        None => category.to_string(),
+    }
+}
+
+/// Wrap already padded category text in its configured color
+fn colorize(category: &str, text: String, opts: &DisplayOptions) -> String {
+    if !opts.color {
+        return text;
+    }
+    let code = opts
+        .config
+        .style(category)
+        .and_then(|style| style.color.as_deref())
+        .and_then(ansi_code);
+    match code {
+        Some(code) => format!("{}{}{}", code, text, ANSI_RESET),
+        None => text,
+    }
+}
+
+/// A category's name as shown to the user, padded to `width` columns
+pub fn render_category(category: &str, width: usize, opts: &DisplayOptions) -> String {
+    let text = pad_right(&category_text(category, opts), width);
+    colorize(category, text, opts)
+}
+
+/// Total of each currency in `expenses`, keyed by currency code
+fn totals_by_currency<'a>(
+    expenses: impl IntoIterator<Item = &'a Expense>,
+    opts: &'a DisplayOptions,
+) -> BTreeMap<&'a str, f64> {
+    let mut totals = BTreeMap::new();
+    for expense in expenses {
+        *totals
+            .entry(opts.config.currency_of(expense))
+            .or_insert(0.0) += expense.amount;
+    }
+    totals
+}
+
+/// Render expenses as a table with a total line
+///
+/// Each amount is shown in its own currency. A list in more than one
+/// currency gets one total line per currency instead of a single total.
+/// Income is listed but isn't spending: it is left out of the total and
+/// summed on an `Income` line of its own.
+pub fn render_table(expenses: &[&Expense], opts: &DisplayOptions) -> String {
+    let ids: Vec<String> = expenses.iter().map(|e| e.id.to_string()).collect();
+    let amounts: Vec<String> = expenses
+        .iter()
+        .map(|e| format_amount(e.amount, opts.config.currency_of(e)))
+        .collect();
+    let (spending, income): (Vec<&Expense>, Vec<&Expense>) =
+        expenses.iter().partition(|e| e.kind.is_expense());
+    let currency_totals = totals_by_currency(spending, opts);
+    let mut totals: Vec<(String, String)> = if currency_totals.len() > 1 {
+        currency_totals
+            .iter()
+            .map(|(currency, total)| {
+                (
+                    format!("Total {}", currency),
+                    format_amount(*total, currency),
+                )
+            })
+            .collect()
+    } else {
+        let currency = currency_totals
+            .keys()
+            .next()
+            .copied()
+            .unwrap_or(opts.config.currency.as_deref().unwrap_or(DEFAULT_CURRENCY));
+        let total = currency_totals.values().sum::<f64>();
+        vec![("Total".to_string(), format_amount(total, currency))]
+    };
+    if !income.is_empty() {
+        totals.push(("Income".to_string(), overall_total(income, opts)));
+    }
+
+    let id_width = ids.iter().map(|s| s.len()).max().unwrap_or(0).max(2);
+    let category_width = expenses
+        .iter()
+        .map(|e| display_width(&category_text(&e.category, opts)))
+        .max()
+        .unwrap_or(0)
+        .max("Category".len());
+    let amount_width = amounts
+        .iter()
+        .map(|s| display_width(s))
+        .max()
+        .unwrap_or(0)
+        .max("Amount".len())
+        .max(
+            totals
+                .iter()
+                .map(|(_, t)| display_width(t))
+                .max()
+                .unwrap_or(0),
+        );
+
+    let mut out = format!(
+        "{}  {}  {}  {}\n",
+        pad_right("ID", id_width),
+        pad_right("Date", 10),
+        pad_right("Category", category_width),
+        pad_left("Amount", amount_width)
+    );
+    for (i, expense) in expenses.iter().enumerate() {
+        out.push_str(&format!(
+            "{}  {}  {}  {}\n",
+            pad_right(&ids[i], id_width),
+            pad_right(&expense.date, 10),
+            render_category(&expense.category, category_width, opts),
+            pad_left(&amounts[i], amount_width)
+        ));
+    }
+    let label_width = id_width + 2 + 10 + 2 + category_width;
+    for (label, total) in &totals {
+        out.push_str(&format!(
+            "{}  {}\n",
+            pad_right(label, label_width),
+            pad_left(total, amount_width)
+        ));
+    }
+    out
+}
+
+/// What `render_grouped` puts expenses into sections by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupKey {
+    Category,
+    Date,
+    /// The `YYYY-MM` month
+    Month,
+}
+
+/// Render expenses as one table per category, date or month, each with its
+/// subtotal, followed by the grand total
+///
+/// Categories come alphabetically, dates and months in time order, and
+/// each section keeps the expenses in list order. As in `render_table`,
+/// income is totalled apart from spending.
+pub fn render_grouped(expenses: &[&Expense], key: GroupKey, opts: &DisplayOptions) -> String {
+    let mut groups: BTreeMap<&str, Vec<&Expense>> = BTreeMap::new();
+    for &expense in expenses {
+        let group = match key {
+            GroupKey::Category => expense.category.as_str(),
+            GroupKey::Date => expense.date.as_str(),
+            GroupKey::Month => month_of(&expense.date).unwrap_or(&expense.date),
+        };
+        groups.entry(group).or_default().push(expense);
+    }
+
+    let mut out = String::new();
+    for (group, members) in &groups {
+        let heading = match key {
+            GroupKey::Category => colorize(group, category_text(group, opts), opts),
+            GroupKey::Date | GroupKey::Month => group.to_string(),
+        };
+        out.push_str(&format!("{}\n{}\n", heading, render_table(members, opts)));
+    }
+    let (spending, income): (Vec<&Expense>, Vec<&Expense>) =
+        expenses.iter().partition(|e| e.kind.is_expense());
+    out.push_str(&format!("Grand total: {}\n", overall_total(spending, opts)));
+    if !income.is_empty() {
+        out.push_str(&format!("Income: {}\n", overall_total(income, opts)));
+    }
+    out
+}
+
+/// The total of `expenses` in words for a summary line, one amount per
+/// currency
+fn overall_total<'a>(
+    expenses: impl IntoIterator<Item = &'a Expense>,
+    opts: &'a DisplayOptions,
+) -> String {
+    let currency_totals = totals_by_currency(expenses, opts);
+    if currency_totals.is_empty() {
+        let currency = opts.config.currency.as_deref().unwrap_or(DEFAULT_CURRENCY);
+        format_amount(0.0, currency)
+    } else {
+        currency_totals
+            .iter()
+            .map(|(currency, total)| format_amount(*total, currency))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Characters used by `sparkline`, from nothing spent to the most
+const SPARK_LEVELS: &[u8] = b"_.:-=+*#%@";
+
+/// Draw values as a one-line ASCII chart, one character per value
+///
+/// Values are scaled against the largest; zero (and anything negative or
+/// not a number) is drawn as `_` and any spending at all as at least `.`.
+pub fn sparkline(values: &[f64]) -> String {
+    let max = values.iter().cloned().fold(0.0, f64::max);
+    let top = (SPARK_LEVELS.len() - 1) as f64;
+    values
+        .iter()
+        .map(|&value| {
+            let level = if max > 0.0 && value > 0.0 {
+                (value / max * top).ceil() as usize
+            } else {
+                0
+            };
+            SPARK_LEVELS[level] as char
+        })
+        .collect()
+}
+
+/// Render a summary report: overall total, totals per category, the most
+/// expensive expense and spending by day of the month
+///
+/// Categories are listed from the largest total to the smallest, ties in
+/// alphabetical order. Totals are kept apart per currency; since amounts
+/// in different currencies can't be compared, a report over several
+/// currencies leaves out the most expensive expense and the day of month
+/// section. Income isn't spending: it is left out of every section and
+/// shown on a line of its own under the total.
+pub fn render_report(entries: &[Expense], opts: &DisplayOptions) -> String {
+    let (expenses, income): (Vec<Expense>, Vec<Expense>) =
+        entries.iter().cloned().partition(|e| e.kind.is_expense());
+    let expenses = &expenses[..];
+    let mut totals: BTreeMap<(&str, &str), f64> = BTreeMap::new();
+    for expense in expenses {
+        let key = (expense.category.as_str(), opts.config.currency_of(expense));
+        *totals.entry(key).or_insert(0.0) += expense.amount;
+    }
+    let mut totals: Vec<((&str, &str), f64)> = totals.into_iter().collect();
+    totals.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+    let currency_totals = totals_by_currency(expenses, opts);
+    let overall = overall_total(expenses, opts);
+    let mut out = format!("Total: {} ({} expenses)\n", overall, expenses.len());
+    if !income.is_empty() {
+        out.push_str(&format!("Income: {}\n", overall_total(&income, opts)));
+    }
+    if totals.is_empty() {
+        return out;
+    }
+
+    let category_width = totals
+        .iter()
+        .map(|((c, _), _)| display_width(&category_text(c, opts)))
+        .max()
+        .unwrap_or(0);
+    let amounts: Vec<String> = totals
+        .iter()
+        .map(|((_, currency), t)| format_amount(*t, currency))
+        .collect();
+    let amount_width = amounts.iter().map(|s| display_width(s)).max().unwrap_or(0);
+
+    out.push_str("\nBy category:\n");
+    for (((category, _), _), amount) in totals.iter().zip(&amounts) {
+        out.push_str(&format!(
+            "  {}  {}\n",
+            render_category(category, category_width, opts),
+            pad_left(amount, amount_width)
+        ));
+    }
+    let [(currency, _)] = currency_totals.into_iter().collect::<Vec<_>>()[..] else {
+        return out;
+    };
+    if let Some(max) = find_max(expenses) {
+        out.push_str(&format!(
+            "\nMost expensive: {} - {} ({})\n",
+            format_amount(max.amount, currency),
+            colorize(&max.category, category_text(&max.category, opts), opts),
+            max.date
+        ));
+    }
+    if let Some((index, total)) = peak_spending_day_of_month(expenses) {
+        out.push_str(&format!(
+            "\nBy day of month (1-31):\n  {}\n  most on day {}: {}\n",
+            sparkline(&totals_by_day_of_month(expenses)),
+            index + 1,
+            format_amount(total, currency)
+        ));
+    }
+    if let Some(income) = opts.config.monthly_income
+        && let Some(month) = latest_expense(expenses).and_then(|e| month_of(&e.date))
+    {
+        out.push_str(&render_income_ratios(
+            expenses, income, month, currency, opts,
+        ));
+    }
+    out
+}
+
+/// The report's share-of-income section for `month`, or nothing if the
+/// income isn't above zero
+fn render_income_ratios(
+    expenses: &[Expense],
+    income: f64,
+    month: &str,
+    currency: &str,
+    opts: &DisplayOptions,
+) -> String {
+    let ratios = income_ratio_report(expenses, income, month);
+    let Some((_, categories)) = ratios.split_last() else {
+        return String::new();
+    };
+    let width = categories
+        .iter()
+        .map(|(c, _)| display_width(&category_text(c, opts)))
+        .chain([display_width(SAVINGS_RATE)])
+        .max()
+        .unwrap_or(0);
+    let percents: Vec<String> = ratios.iter().map(|(_, p)| format!("{:.1}%", p)).collect();
+    let percent_width = percents.iter().map(|p| display_width(p)).max().unwrap_or(0);
+
+    let mut out = format!(
+        "\nShare of income in {} ({}):\n",
+        month,
+        format_amount(income, currency)
+    );
+    for ((category, _), percent) in categories.iter().zip(&percents) {
+        out.push_str(&format!(
+            "  {}  {}\n",
+            render_category(category, width, opts),
+            pad_left(percent, percent_width)
+        ));
+    }
+    out.push_str(&format!(
+        "  {}  {}\n",
+        pad_right(SAVINGS_RATE, width),
+        pad_left(&percents[categories.len()], percent_width)
+    ));
+    out
+}
+
+/// Render a statement from `cash_flow_by_month`: each month's income,
+/// spending, net and the balance after it
+///
+/// Amounts are shown in the configured currency.
+pub fn format_cash_flow_table(flows: &BTreeMap<String, CashFlow>, opts: &DisplayOptions) -> String {
+    let currency = opts.config.currency.as_deref().unwrap_or(DEFAULT_CURRENCY);
+    let header = ["Month", "Income", "Expenses", "Net", "Balance"].map(str::to_string);
+    let rows: Vec<[String; 5]> = flows
+        .iter()
+        .map(|(month, flow)| {
+            [
+                month.clone(),
+                format_amount(flow.income, currency),
+                format_amount(flow.expenses, currency),
+                format_amount(flow.net, currency),
+                format_amount(flow.running_balance, currency),
+            ]
+        })
+        .collect();
+    let widths: Vec<usize> = (0..header.len())
+        .map(|i| {
+            std::iter::once(&header)
+                .chain(&rows)
+                .map(|row| display_width(&row[i]))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let mut out = String::new();
+    for row in std::iter::once(&header).chain(&rows) {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .enumerate()
+            .map(|(i, (cell, &width))| match i {
+                0 => pad_right(cell, width),
+                _ => pad_left(cell, width),
+            })
+            .collect();
+        out.push_str(&cells.join("  "));
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CategoryStyle;
+
+    fn demo() -> Vec<Expense> {
+        vec![
+            Expense::with_id(1, 45.5, "food", "2026-01-08"),
+            Expense::with_id(2, 20.0, "transport", "2026-01-08"),
+            Expense::with_id(3, 100.0, "rent", "2026-01-08"),
+            Expense::with_id(4, 30.0, "food", "2026-01-07"),
+        ]
+    }
+
+    fn styled(color: bool) -> DisplayOptions {
+        let mut config = Config::default();
+        config.categories.insert(
+            "food".to_string(),
+            CategoryStyle {
+                emoji: Some("🍔".to_string()),
+                label: None,
+                color: Some("yellow".to_string()),
+            },
+        );
+        DisplayOptions { color, config }
+    }
+
+    #[test]
+    fn test_table_without_metadata() {
+        let expenses = demo();
+        let refs: Vec<&Expense> = expenses.iter().collect();
+        assert_eq!(
+            render_table(&refs, &DisplayOptions::plain()),
+            "\
+ID  Date        Category    Amount
+1   2026-01-08  food        $45.50
+2   2026-01-08  transport   $20.00
+3   2026-01-08  rent       $100.00
+4   2026-01-07  food        $30.00
+Total                      $195.50
+"
+        );
+    }
+
+    #[test]
+    fn test_table_with_metadata_and_color() {
+        let expenses = demo();
+        let refs: Vec<&Expense> = expenses.iter().take(2).collect();
+        assert_eq!(
+            render_table(&refs, &styled(true)),
+            "\
+ID  Date        Category   Amount
+1   2026-01-08  \x1b[33m🍔 food  \x1b[0m  $45.50
+2   2026-01-08  transport  $20.00
+Total                      $65.50
+"
+        );
+    }
+
+    #[test]
+    fn test_report_with_metadata_color_disabled() {
+        assert_eq!(
+            render_report(&demo(), &styled(false)),
+            "\
+Total: $195.50 (4 expenses)
+
+By category:
+  rent       $100.00
+  🍔 food     $75.50
+  transport   $20.00
+
+Most expensive: $100.00 - rent (2026-01-08)
+
+By day of month (1-31):
+  ______:@_______________________
+  most on day 8: $165.50
+"
+        );
+    }
+
+    #[test]
+    fn test_list_totals_keep_income_apart() {
+        use crate::expense::TransactionKind;
+
+        let salary =
+            Expense::with_id(9, 1000.0, "salary", "2026-01-31").with_kind(TransactionKind::Income);
+        let expenses = demo();
+        let mut refs: Vec<&Expense> = expenses.iter().take(2).collect();
+        refs.push(&salary);
+        assert_eq!(
+            render_table(&refs, &styled(false)),
+            "\
+ID  Date        Category     Amount
+1   2026-01-08  🍔 food      $45.50
+2   2026-01-08  transport    $20.00
+9   2026-01-31  salary     $1000.00
+Total                        $65.50
+Income                     $1000.00
+"
+        );
+        let grouped = render_grouped(&refs, GroupKey::Month, &styled(false));
+        assert!(
+            grouped.ends_with("Grand total: $65.50\nIncome: $1000.00\n"),
+            "{}",
+            grouped
+        );
+    }
+
+    #[test]
+    fn test_report_keeps_income_apart() {
+        use crate::expense::TransactionKind;
+
+        let mut entries = demo();
+        entries.push(
+            Expense::with_id(5, 1000.0, "salary", "2026-01-31").with_kind(TransactionKind::Income),
+        );
+        let report = render_report(&entries, &styled(false));
+        assert!(
+            report.starts_with("Total: $195.50 (4 expenses)\nIncome: $1000.00\n"),
+            "{}",
+            report
+        );
+        assert!(!report.contains("salary"), "{}", report);
+        assert!(
+            report.contains("Most expensive: $100.00 - rent"),
+            "{}",
+            report
+        );
+    }
+
+    #[test]
+    fn test_report_with_monthly_income() {
+        let mut opts = styled(false);
+        opts.config.monthly_income = Some(0.0);
+        let without = render_report(&demo(), &styled(false));
+        assert_eq!(render_report(&demo(), &opts), without);
+
+        opts.config.monthly_income = Some(150.0);
+        let mut expenses = demo();
+        expenses.push(Expense::with_id(5, 12.0, "food", "2025-12-31"));
+        let report = render_report(&expenses, &opts);
+        assert!(
+            report.ends_with(
+                "
+Share of income in 2026-01 ($150.00):
+  rent           66.7%
+  🍔 food        50.3%
+  transport      13.3%
+  savings rate  -30.3%
+"
+            ),
+            "{}",
+            report
+        );
+    }
+
+    fn naira_default() -> DisplayOptions {
+        let config = Config {
+            currency: Some("NGN".to_string()),
+            ..Config::default()
+        };
+        DisplayOptions {
+            color: false,
+            config,
+        }
+    }
+
+    fn mixed() -> Vec<Expense> {
+        vec![
+            Expense::with_id(1, 4500.0, "food", "2026-01-08"),
+            Expense::with_id(2, 45.5, "food", "2026-01-08").with_currency("EUR"),
+            Expense::with_id(3, 20.0, "transport", "2026-01-09").with_currency("USD"),
+            Expense::with_id(4, 1500.0, "transport", "2026-01-09"),
+        ]
+    }
+
+    #[test]
+    fn test_table_with_mixed_currencies() {
+        let expenses = mixed();
+        let refs: Vec<&Expense> = expenses.iter().collect();
+        assert_eq!(
+            render_table(&refs, &naira_default()),
+            "\
+ID  Date        Category     Amount
+1   2026-01-08  food       ₦4500.00
+2   2026-01-08  food        45,50 €
+3   2026-01-09  transport    $20.00
+4   2026-01-09  transport  ₦1500.00
+Total EUR                   45,50 €
+Total NGN                  ₦6000.00
+Total USD                    $20.00
+"
+        );
+
+        let naira: Vec<&Expense> = vec![&expenses[0], &expenses[3]];
+        assert_eq!(
+            render_table(&naira, &naira_default()),
+            "\
+ID  Date        Category     Amount
+1   2026-01-08  food       ₦4500.00
+4   2026-01-09  transport  ₦1500.00
+Total                      ₦6000.00
+"
+        );
+    }
+
+    #[test]
+    fn test_report_with_mixed_currencies() {
+        assert_eq!(
+            render_report(&mixed(), &naira_default()),
+            "\
+Total: 45,50 €, ₦6000.00, $20.00 (4 expenses)
+
+By category:
+  food       ₦4500.00
+  transport  ₦1500.00
+  food        45,50 €
+  transport    $20.00
+"
+        );
+    }
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(
+            sparkline(&[0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0]),
+            "_.:-=+*#%@"
+        );
+        assert_eq!(sparkline(&[100.0, 0.5, -3.0, f64::NAN, 55.0]), "@.__+");
+        assert_eq!(sparkline(&[0.0, 0.0]), "__");
+        assert_eq!(sparkline(&[]), "");
+    }
+
+    #[test]
+    fn test_cash_flow_table() {
+        use crate::cashflow::cash_flow_by_month;
+        use crate::expense::TransactionKind;
+
+        let expenses = vec![
+            Expense::with_id(1, 2000.0, "salary", "2026-01-31").with_kind(TransactionKind::Income),
+            Expense::with_id(2, 620.5, "rent", "2026-01-01"),
+            Expense::with_id(3, 2080.0, "travel", "2026-02-03"),
+            Expense::with_id(4, 300.0, "freelance", "2026-03-15")
+                .with_kind(TransactionKind::Income),
+        ];
+        let flows = cash_flow_by_month(&expenses, Some(100.0));
+        assert_eq!(
+            format_cash_flow_table(&flows, &DisplayOptions::plain()),
+            "\
+Month      Income  Expenses        Net   Balance
+2026-01  $2000.00   $620.50   $1379.50  $1479.50
+2026-02     $0.00  $2080.00  -$2080.00  -$600.50
+2026-03   $300.00     $0.00    $300.00  -$300.50
+"
+        );
+    }
+
+    #[test]
+    fn test_grouped_by_category() {
+        let expenses = demo();
+        let refs: Vec<&Expense> = expenses.iter().collect();
+        assert_eq!(
+            render_grouped(&refs, GroupKey::Category, &DisplayOptions::plain()),
+            "\
+food
+ID  Date        Category  Amount
+1   2026-01-08  food      $45.50
+4   2026-01-07  food      $30.00
+Total                     $75.50
+
+rent
+ID  Date        Category   Amount
+3   2026-01-08  rent      $100.00
+Total                     $100.00
+
+transport
+ID  Date        Category   Amount
+2   2026-01-08  transport  $20.00
+Total                      $20.00
+
+Grand total: $195.50
+"
+        );
+    }
+
+    #[test]
+    fn test_grouped_by_date_and_month() {
+        let expenses = demo();
+        let refs: Vec<&Expense> = expenses.iter().collect();
+        assert_eq!(
+            render_grouped(&refs, GroupKey::Date, &DisplayOptions::plain()),
+            "\
+2026-01-07
+ID  Date        Category  Amount
+4   2026-01-07  food      $30.00
+Total                     $30.00
+
+2026-01-08
+ID  Date        Category    Amount
+1   2026-01-08  food        $45.50
+2   2026-01-08  transport   $20.00
+3   2026-01-08  rent       $100.00
+Total                      $165.50
+
+Grand total: $195.50
+"
+        );
+        assert_eq!(
+            render_grouped(&refs, GroupKey::Month, &DisplayOptions::plain()),
+            "\
+2026-01
+ID  Date        Category    Amount
+1   2026-01-08  food        $45.50
+2   2026-01-08  transport   $20.00
+3   2026-01-08  rent       $100.00
+4   2026-01-07  food        $30.00
+Total                      $195.50
+
+Grand total: $195.50
+"
+        );
+        assert_eq!(
+            render_grouped(&[], GroupKey::Month, &DisplayOptions::plain()),
+            "Grand total: $0.00\n"
+        );
+    }
+}
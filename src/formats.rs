@@ -0,0 +1,164 @@
+// This module puts every way of persisting a list of expenses (CSV, JSON,
+// binary, ...) behind one `Format` trait, so they can be checked the same
+// way
+//
+// When adding a storage format, implement `Format` for it and list it in
+// `all_formats`; the round-trip test below then runs it against the shared
+// fixture.
+
+use crate::binary;
+use crate::error::ExpenseError;
+use crate::expense::Expense;
+use crate::storage;
+
+/// A way of turning expenses into bytes and back
+pub trait Format {
+    /// Short name used in messages, e.g. `csv`
+    fn name(&self) -> &'static str;
+    /// Encode expenses
+    fn write(&self, expenses: &[Expense]) -> Vec<u8>;
+    /// Decode expenses written by `write`
+    fn read(&self, bytes: &[u8]) -> Result<Vec<Expense>, ExpenseError>;
+}
+
+/// The CSV data file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CsvFormat;
+
+impl Format for CsvFormat {
+    fn name(&self) -> &'static str {
+        "csv"
+    }
+
+    fn write(&self, expenses: &[Expense]) -> Vec<u8> {
+        storage::to_csv(expenses).into_bytes()
+    }
+
+    fn read(&self, bytes: &[u8]) -> Result<Vec<Expense>, ExpenseError> {
+        let text = std::str::from_utf8(bytes)
+            .map_err(|e| ExpenseError::ParseError(format!("not UTF-8: {}", e)))?;
+        storage::from_csv(text)
+    }
+}
+
+/// A JSON array of expenses, as the webhook sends them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct JsonFormat;
+
+impl Format for JsonFormat {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn write(&self, expenses: &[Expense]) -> Vec<u8> {
+        serde_json::to_vec(expenses).expect("expenses are always representable as JSON")
+    }
+
+    fn read(&self, bytes: &[u8]) -> Result<Vec<Expense>, ExpenseError> {
+        serde_json::from_slice(bytes).map_err(|e| ExpenseError::ParseError(e.to_string()))
+    }
+}
+
+/// The plain binary format (`binary::to_binary`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BinaryFormat;
+
+impl Format for BinaryFormat {
+    fn name(&self) -> &'static str {
+        "binary"
+    }
+
+    fn write(&self, expenses: &[Expense]) -> Vec<u8> {
+        binary::to_binary(expenses)
+    }
+
+    fn read(&self, bytes: &[u8]) -> Result<Vec<Expense>, ExpenseError> {
+        binary::from_binary(bytes)
+    }
+}
+
+/// The run-length encoded binary format (`binary::compress`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RleFormat;
+
+impl Format for RleFormat {
+    fn name(&self) -> &'static str {
+        "rle"
+    }
+
+    fn write(&self, expenses: &[Expense]) -> Vec<u8> {
+        binary::compress(expenses)
+    }
+
+    fn read(&self, bytes: &[u8]) -> Result<Vec<Expense>, ExpenseError> {
+        binary::decompress(bytes)
+    }
+}
+
+/// Every format expenses can be stored in
+pub fn all_formats() -> Vec<Box<dyn Format>> {
+    vec![
+        Box::new(CsvFormat),
+        Box::new(JsonFormat),
+        Box::new(BinaryFormat),
+        Box::new(RleFormat),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expense::TransactionKind;
+
+    /// Expenses that have tripped up formats before: non-ASCII text,
+    /// characters CSV has to quote, zero amounts and every optional field
+    /// both set and unset
+    fn tricky_fixture() -> Vec<Expense> {
+        let mut payee_only = Expense::with_id(4, 0.0, "transport", "2026-02-28");
+        payee_only.payee = Some("Uber, Lagos".to_string());
+        vec![
+            Expense::with_id(1, 45.5, "café", "2026-01-08")
+                .with_note("lunch, with \"friends\"")
+                .with_payee("Chez Éloïse")
+                .with_tag("work")
+                .with_tag("travel")
+                .with_currency("EUR"),
+            Expense::with_id(2, 1200.0, "日本食", "2026-01-09").with_note("line one\nline two"),
+            Expense::with_id(3, 0.01, "food", "2026-01-10").with_currency("NGN"),
+            payee_only,
+            Expense::with_id(u64::MAX, 999_999.99, "rent", "2026-12-31").with_tag("home"),
+            Expense::with_id(5, 2000.0, "salary", "2026-01-31").with_kind(TransactionKind::Income),
+            Expense::with_id(6, 3.5, "food", "2026-02-01")
+                .with_subcategory("snacks")
+                .with_receipt_number("0042; \"A\""),
+        ]
+    }
+
+    /// Write then read `fixture` with `format`, checking nothing but the
+    /// canonical form changes and that writing again gives the same bytes
+    fn assert_round_trip<F: Format + ?Sized>(format: &F, fixture: &[Expense]) {
+        let bytes = format.write(fixture);
+        let read = format
+            .read(&bytes)
+            .unwrap_or_else(|e| panic!("{}: could not read back: {}", format.name(), e));
+        let canonical = |expenses: &[Expense]| -> Vec<Expense> {
+            expenses.iter().map(Expense::canonicalize).collect()
+        };
+        assert_eq!(canonical(&read), canonical(fixture), "{}", format.name());
+        assert_eq!(
+            format.write(&read),
+            bytes,
+            "{}: rewrite differs",
+            format.name()
+        );
+    }
+
+    #[test]
+    fn test_every_format_round_trips() {
+        let fixture = tricky_fixture();
+        for format in all_formats() {
+            assert_round_trip(format.as_ref(), &fixture);
+            assert_round_trip(format.as_ref(), &[]);
+        }
+    }
+}
@@ -0,0 +1,62 @@
+// This module exports expenses as CSV that GnuCash's transaction importer
+// understands
+
+use crate::expense::Expense;
+use crate::storage::join_csv_fields;
+
+/// Header line GnuCash expects
+pub const GNUCASH_HEADER: &str = "Date,Description,Transfer,Withdrawal,Deposit,Total";
+
+/// Write expenses as GnuCash-compatible CSV
+///
+/// Each expense is one withdrawal described by its category; `Transfer` is
+/// left blank for the user to pick an account while importing. A negative
+/// amount (such as a refund) is written as a deposit instead. `Total` is
+/// the running balance, starting from zero.
+pub fn to_gnucash_csv(expenses: &[Expense]) -> String {
+    let mut out = String::new();
+    out.push_str(GNUCASH_HEADER);
+    out.push('\n');
+    let mut balance = 0.0;
+    for expense in expenses {
+        balance -= expense.amount;
+        let (withdrawal, deposit) = if expense.amount < 0.0 {
+            (String::new(), format!("{:.2}", -expense.amount))
+        } else {
+            (format!("{:.2}", expense.amount), String::new())
+        };
+        let fields = [
+            expense.date.clone(),
+            expense.category.clone(),
+            String::new(),
+            withdrawal,
+            deposit,
+            format!("{:.2}", balance),
+        ];
+        out.push_str(&join_csv_fields(&fields));
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gnucash_header_and_withdrawal_column() {
+        let expenses = vec![
+            Expense::new(45.5, "food", "2026-01-05"),
+            Expense::new(-10.0, "food", "2026-01-06"),
+        ];
+        let csv = to_gnucash_csv(&expenses);
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(
+            lines[0],
+            "Date,Description,Transfer,Withdrawal,Deposit,Total"
+        );
+        assert_eq!(lines[1], "2026-01-05,food,,45.50,,-45.50");
+        assert_eq!(lines[2], "2026-01-06,food,,,10.00,-35.50");
+    }
+}
@@ -0,0 +1,73 @@
+// This module exports expenses as iCalendar (RFC 5545) events, so planned
+// expenses can show up in a calendar app
+
+use crate::expense::Expense;
+
+/// One expense as a calendar holding a single all-day VEVENT
+///
+/// `SUMMARY` is the category and amount, `DESCRIPTION` the note (left out
+/// when there is none). Lines end in CRLF as the standard requires.
+pub fn expense_to_ical_event(expense: &Expense) -> String {
+    let date = ical_date(&expense.date);
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//Rust_Collections//Expense Tracker//EN".to_string(),
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:expense-{}@rust-collections", expense.id),
+        format!("DTSTAMP:{}T000000Z", date),
+        format!("DTSTART;VALUE=DATE:{}", date),
+        format!(
+            "SUMMARY:{}",
+            escape_text(&format!("{} ${:.2}", expense.category, expense.amount))
+        ),
+    ];
+    if let Some(note) = &expense.note {
+        lines.push(format!("DESCRIPTION:{}", escape_text(note)));
+    }
+    lines.push("END:VEVENT".to_string());
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut out = lines.join("\r\n");
+    out.push_str("\r\n");
+    out
+}
+
+/// `YYYY-MM-DD` as the `YYYYMMDD` form iCalendar uses
+fn ical_date(date: &str) -> String {
+    date.replace('-', "")
+}
+
+/// Escape the characters that are special in iCalendar text values
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_contains_date_and_summary() {
+        let expense = Expense::new(1200.0, "rent", "2026-02-01");
+        let ical = expense_to_ical_event(&expense);
+
+        assert!(ical.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ical.contains("\r\nBEGIN:VEVENT\r\n"));
+        assert!(ical.contains("\r\nEND:VEVENT\r\n"));
+        assert!(ical.contains("\r\nDTSTART;VALUE=DATE:20260201\r\n"));
+        assert!(ical.contains("\r\nSUMMARY:rent $1200.00\r\n"));
+        assert!(!ical.contains("DESCRIPTION"));
+    }
+
+    #[test]
+    fn test_event_description_is_escaped_note() {
+        let expense =
+            Expense::new(9.99, "music", "2026-02-03").with_note("family plan; 6 seats, yearly");
+        let ical = expense_to_ical_event(&expense);
+        assert!(ical.contains("\r\nDESCRIPTION:family plan\\; 6 seats\\, yearly\r\n"));
+    }
+}
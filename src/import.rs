@@ -0,0 +1,1036 @@
+// This module reads expenses exported by other tools (bank statements,
+// spreadsheets) into the tracker
+
+use std::collections::BTreeMap;
+#[cfg(feature = "fs")]
+use std::path::Path;
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::currency::format_amount;
+use crate::dates::{checked_ymd, format_ymd};
+use crate::diff::split_recorded;
+use crate::error::ExpenseError;
+use crate::expense::{Expense, parse_amount};
+use crate::operations::date_span;
+use crate::storage::{
+    CSV_HEADER, CsvOptions, parse_amount_field, parse_amount_field_with, parse_csv_with,
+};
+
+/// Category given to imported rows that have none and match no keyword
+pub const UNCATEGORIZED: &str = "uncategorized";
+
+/// Header names that can hold a row's description, in order of preference
+const DESCRIPTION_COLUMNS: [&str; 4] = ["description", "payee", "name", "memo"];
+
+/// The date format of the tracker's own files, and of `ColumnMapping` by
+/// default
+pub const ISO_DATE_FORMAT: &str = "YYYY-MM-DD";
+
+/// Which column of an export holds what, for files whose headers can't be
+/// matched by name
+///
+/// Columns are numbered from 0. Missing keys take their defaults when read
+/// from the config (see `Config::import`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColumnMapping {
+    pub amount_col: usize,
+    pub date_col: usize,
+    /// Rows get a category from the description (or `UNCATEGORIZED`)
+    /// when this is unset or the column is empty
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category_col: Option<usize>,
+    /// Kept as the note and used to pick a category
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description_col: Option<usize>,
+    /// How dates are written, built from `YYYY`, `MM` and `DD`, e.g.
+    /// `DD/MM/YYYY`
+    #[serde(default = "default_date_format")]
+    pub date_format: String,
+    /// Flip the sign of every amount, for banks that export debits as
+    /// negative numbers
+    #[serde(default)]
+    pub negate: bool,
+    /// Whether the first row holds column names rather than an expense
+    #[serde(default = "default_has_header")]
+    pub has_header: bool,
+}
+
+fn default_date_format() -> String {
+    ISO_DATE_FORMAT.to_string()
+}
+
+fn default_has_header() -> bool {
+    true
+}
+
+impl ColumnMapping {
+    /// A mapping with ISO dates, a header row and no category or
+    /// description column
+    pub fn new(amount_col: usize, date_col: usize) -> ColumnMapping {
+        ColumnMapping {
+            amount_col,
+            date_col,
+            category_col: None,
+            description_col: None,
+            date_format: default_date_format(),
+            negate: false,
+            has_header: true,
+        }
+    }
+}
+
+/// What an import brought in, for review afterwards
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct ImportSummary {
+    /// Number of expenses imported
+    pub imported: usize,
+    /// Sum of the imported amounts
+    pub total_amount: f64,
+    /// Number of imported expenses per category
+    pub per_category: BTreeMap<String, usize>,
+    /// Earliest and latest imported date
+    pub date_range: Option<(String, String)>,
+    /// Rows that had no category and were given one
+    pub auto_categorized: Vec<AutoCategorizedRow>,
+    /// Rows that could not be imported
+    pub skipped: Vec<SkippedRow>,
+    /// Rows left out because they were already recorded
+    pub duplicates: usize,
+}
+
+/// A row that was given a category during import
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AutoCategorizedRow {
+    pub line: usize,
+    pub description: String,
+    pub category: String,
+}
+
+/// A row that was left out of an import
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SkippedRow {
+    pub line: usize,
+    pub reason: String,
+}
+
+impl ImportSummary {
+    /// Count one imported expense in the summary (`date_range` is filled
+    /// in once every row is read)
+    fn record(&mut self, expense: &Expense) {
+        self.imported += 1;
+        self.total_amount += expense.amount;
+        *self
+            .per_category
+            .entry(expense.category.clone())
+            .or_insert(0) += 1;
+    }
+
+    /// Count only `added`, the rows left once duplicates of expenses
+    /// already recorded are dropped (see `split_recorded`), and note how
+    /// many were dropped
+    pub fn recount(&mut self, added: &[Expense]) {
+        let read = self.imported;
+        self.imported = 0;
+        self.total_amount = 0.0;
+        self.per_category.clear();
+        for expense in added {
+            self.record(expense);
+        }
+        self.date_range = date_span(added);
+        self.duplicates += read.saturating_sub(added.len());
+    }
+
+    /// Leave a row out of the import
+    fn skip(&mut self, line: usize, reason: String) {
+        warn!("skipping import line {}: {}", line, reason);
+        self.skipped.push(SkippedRow { line, reason });
+    }
+}
+
+/// Pick a category for a description from keyword rules
+///
+/// `rules` maps a category to keywords; the first category (alphabetically)
+/// with a keyword contained in the description wins. Matching ignores case.
+pub fn suggest_category(
+    description: &str,
+    rules: &BTreeMap<String, Vec<String>>,
+) -> Option<String> {
+    let description = description.to_lowercase();
+    rules
+        .iter()
+        .find(|(_, keywords)| {
+            keywords
+                .iter()
+                .any(|k| !k.is_empty() && description.contains(&k.to_lowercase()))
+        })
+        .map(|(category, _)| category.clone())
+}
+
+/// The expenses still waiting for a category: those in `UNCATEGORIZED` or
+/// with a blank category, in list order
+pub fn uncategorized(expenses: &[Expense]) -> Vec<&Expense> {
+    expenses
+        .iter()
+        .filter(|e| e.category == UNCATEGORIZED || e.category.trim().is_empty())
+        .collect()
+}
+
+/// The rows of an export, each tagged with the line it starts on
+type Rows = std::vec::IntoIter<(usize, Vec<String>)>;
+
+/// Split an export into rows laid out as `csv` says, or as
+/// `CsvOptions::detect` guesses without it, and take off the header
+///
+/// A file without a header row gets the column names of `CSV_HEADER`.
+/// The header is `None` for an empty file.
+fn split_export(
+    text: &str,
+    csv: Option<CsvOptions>,
+) -> Result<(CsvOptions, Option<Vec<String>>, Rows), ExpenseError> {
+    let csv = csv.unwrap_or_else(|| CsvOptions::detect(text));
+    let mut rows = parse_csv_with(text, csv.delimiter)?.into_iter();
+    let header = if csv.include_header {
+        rows.next().map(|(_, header)| header)
+    } else {
+        Some(CSV_HEADER.split(',').map(str::to_string).collect())
+    };
+    Ok((csv, header, rows))
+}
+
+/// Read expenses from CSV text with `amount`, `category` and `date` columns
+///
+/// Columns are found by header name (case-insensitive) and may appear in
+/// any order; other columns are ignored. The delimiter and decimal
+/// separator are those of `csv`, or detected from the text without it.
+/// Every imported expense gets a fresh id.
+pub fn import_from_csv(text: &str, csv: Option<CsvOptions>) -> Result<Vec<Expense>, ExpenseError> {
+    let (csv, header, rows) = split_export(text, csv)?;
+    let Some(header) = header else {
+        return Ok(Vec::new());
+    };
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|h| h.trim().eq_ignore_ascii_case(name))
+            .ok_or_else(|| ExpenseError::ParseError(format!("line 1: missing `{}` column", name)))
+    };
+    let amount_col = column("amount")?;
+    let category_col = column("category")?;
+    let date_col = column("date")?;
+
+    let mut expenses = Vec::new();
+    for (line, fields) in rows {
+        let field = |col: usize| {
+            fields.get(col).map(|f| f.trim()).ok_or_else(|| {
+                ExpenseError::ParseError(format!("line {}: missing column {}", line, col + 1))
+            })
+        };
+        let amount = parse_amount_field_with(field(amount_col)?, line, csv.decimal_separator)?;
+        expenses.push(Expense::new(amount, field(category_col)?, field(date_col)?));
+    }
+    Ok(expenses)
+}
+
+/// Read expenses from an exported CSV, skipping rows that can't be used
+///
+/// Like `import_from_csv`, but a row with a bad amount or date is skipped
+/// (and listed in the summary) instead of failing the whole import. Rows
+/// with an empty or missing category are categorized from their
+/// description column using `rules` (see `suggest_category`), or get
+/// `UNCATEGORIZED`.
+pub fn import_with_summary(
+    text: &str,
+    csv: Option<CsvOptions>,
+    rules: &BTreeMap<String, Vec<String>>,
+) -> Result<(Vec<Expense>, ImportSummary), ExpenseError> {
+    let (csv, header, rows) = split_export(text, csv)?;
+    let Some(header) = header else {
+        return Ok((Vec::new(), ImportSummary::default()));
+    };
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|h| h.trim().eq_ignore_ascii_case(name))
+    };
+    let missing =
+        |name: &str| ExpenseError::ParseError(format!("line 1: missing `{}` column", name));
+    let mapping = ColumnMapping {
+        category_col: column("category"),
+        description_col: DESCRIPTION_COLUMNS.iter().find_map(|name| column(name)),
+        ..ColumnMapping::new(
+            column("amount").ok_or_else(|| missing("amount"))?,
+            column("date").ok_or_else(|| missing("date"))?,
+        )
+    };
+    Ok(import_rows(rows, &mapping, csv.decimal_separator, rules))
+}
+
+/// Read expenses from an exported CSV laid out as `mapping` says
+///
+/// Rows are skipped and categorized as in `import_with_summary`, and `csv`
+/// is used as there, except that `mapping.has_header` decides whether
+/// the first row is skipped. Fails only if the CSV can't be split into
+/// rows or `mapping.date_format` lacks one of `YYYY`, `MM` and `DD`.
+pub fn import_with_mapping(
+    text: &str,
+    mapping: &ColumnMapping,
+    csv: Option<CsvOptions>,
+    rules: &BTreeMap<String, Vec<String>>,
+) -> Result<(Vec<Expense>, ImportSummary), ExpenseError> {
+    let format = &mapping.date_format;
+    if ["YYYY", "MM", "DD"]
+        .iter()
+        .any(|part| !format.contains(part))
+    {
+        return Err(ExpenseError::ParseError(format!(
+            "date format `{}` needs YYYY, MM and DD",
+            format
+        )));
+    }
+    let csv = csv.unwrap_or_else(|| CsvOptions::detect(text));
+    let mut rows = parse_csv_with(text, csv.delimiter)?.into_iter();
+    if mapping.has_header {
+        rows.next();
+    }
+    Ok(import_rows(rows, mapping, csv.decimal_separator, rules))
+}
+
+/// Turn data rows into expenses, recording each one in a summary
+fn import_rows(
+    rows: impl Iterator<Item = (usize, Vec<String>)>,
+    mapping: &ColumnMapping,
+    decimal_separator: char,
+    rules: &BTreeMap<String, Vec<String>>,
+) -> (Vec<Expense>, ImportSummary) {
+    let mut summary = ImportSummary::default();
+    let mut expenses = Vec::new();
+    for (line, fields) in rows {
+        let field = |col: usize| fields.get(col).map(|f| f.trim()).unwrap_or("");
+        let amount =
+            match parse_amount_field_with(field(mapping.amount_col), line, decimal_separator) {
+                Ok(amount) if mapping.negate => -amount,
+                Ok(amount) => amount,
+                Err(_) => {
+                    summary.skip(line, format!("bad amount `{}`", field(mapping.amount_col)));
+                    continue;
+                }
+            };
+        let written = field(mapping.date_col);
+        let Some(date) = convert_date(written, &mapping.date_format) else {
+            summary.skip(line, format!("bad date `{}`", written));
+            continue;
+        };
+
+        let mut category = mapping.category_col.map(field).unwrap_or("").to_string();
+        let description = mapping.description_col.map(field).unwrap_or("");
+        if category.is_empty() {
+            category =
+                suggest_category(description, rules).unwrap_or_else(|| UNCATEGORIZED.to_string());
+            summary.auto_categorized.push(AutoCategorizedRow {
+                line,
+                description: description.to_string(),
+                category: category.clone(),
+            });
+        }
+
+        let mut expense = Expense::new(amount, &category, &date);
+        if !description.is_empty() {
+            expense.note = Some(description.to_string());
+        }
+        summary.record(&expense);
+        expenses.push(expense);
+    }
+    summary.date_range = date_span(&expenses);
+    info!(
+        "imported {} expenses totaling {:.2} ({} auto-categorized, {} skipped)",
+        summary.imported,
+        summary.total_amount,
+        summary.auto_categorized.len(),
+        summary.skipped.len()
+    );
+    (expenses, summary)
+}
+
+/// Convert a date written as `format` (e.g. `DD/MM/YYYY`) to `YYYY-MM-DD`
+///
+/// `YYYY` matches four digits, `MM` and `DD` two; anything else in the
+/// format must appear as-is.
+fn convert_date(value: &str, format: &str) -> Option<String> {
+    let (mut year, mut month, mut day) = (None, None, None);
+    let (mut rest, mut pattern) = (value, format);
+    while let Some(c) = pattern.chars().next() {
+        let (slot, width) = if pattern.starts_with("YYYY") {
+            (&mut year, 4)
+        } else if pattern.starts_with("MM") {
+            (&mut month, 2)
+        } else if pattern.starts_with("DD") {
+            (&mut day, 2)
+        } else {
+            rest = rest.strip_prefix(c)?;
+            pattern = &pattern[c.len_utf8()..];
+            continue;
+        };
+        let digits = rest
+            .get(..width)
+            .filter(|d| d.bytes().all(|b| b.is_ascii_digit()))?;
+        *slot = Some(digits.parse::<u32>().ok()?);
+        rest = &rest[width..];
+        pattern = &pattern[width..];
+    }
+    if !rest.is_empty() {
+        return None;
+    }
+    let date = format_ymd(year? as i64, month?, day?);
+    checked_ymd(&date)?;
+    Some(date)
+}
+
+/// Read expenses from a Quicken Interchange Format (QIF) export
+///
+/// Understands the `D` (date, `DD/MM/YYYY`), `T` (amount), `M` (memo,
+/// kept as the note) and `L` (category) fields; records end at `^`. Header
+/// lines such as `!Type:Bank` and other fields are ignored. Amounts are
+/// stored as-is, so a debit exported as `-45.50` stays negative; commas
+/// used as thousands separators are allowed. A record with no `L` line gets
+/// `UNCATEGORIZED`. Every imported expense gets a fresh id.
+pub fn from_qif_string(qif: &str) -> Result<Vec<Expense>, ExpenseError> {
+    let mut expenses = Vec::new();
+    let mut record = QifRecord::default();
+    for (index, raw) in qif.lines().enumerate() {
+        let line = index + 1;
+        let raw = raw.trim();
+        let mut chars = raw.chars();
+        let (code, value) = match chars.next() {
+            Some(code) => (code, chars.as_str().trim()),
+            None => continue,
+        };
+        match code {
+            '!' => {}
+            'D' => record.date = Some(parse_qif_date(value, line)?),
+            'T' => {
+                let amount = value.replace(',', "");
+                record.amount = Some(parse_amount_field(&amount, line)?);
+            }
+            'M' => record.memo = Some(value.to_string()),
+            'L' => record.category = Some(value.to_string()),
+            '^' => {
+                let finished = std::mem::take(&mut record);
+                expenses.push(finished.into_expense(line)?);
+            }
+            _ => {}
+        }
+        if record.start == 0 && code != '^' && code != '!' {
+            record.start = line;
+        }
+    }
+    // The last record doesn't always end with `^`
+    if record.start != 0 {
+        let line = record.start;
+        expenses.push(record.into_expense(line)?);
+    }
+    Ok(expenses)
+}
+
+/// The fields of one QIF record seen so far
+#[derive(Default)]
+struct QifRecord {
+    /// Line the record starts on, 0 until a field has been read
+    start: usize,
+    date: Option<String>,
+    amount: Option<f64>,
+    memo: Option<String>,
+    category: Option<String>,
+}
+
+impl QifRecord {
+    /// Turn a finished record into an expense, `line` is used in errors
+    fn into_expense(self, line: usize) -> Result<Expense, ExpenseError> {
+        let missing = |field: &str| {
+            ExpenseError::ParseError(format!("line {}: record has no {} line", line, field))
+        };
+        let date = self.date.ok_or_else(|| missing("`D` (date)"))?;
+        let amount = self.amount.ok_or_else(|| missing("`T` (amount)"))?;
+        let category = self
+            .category
+            .filter(|c| !c.is_empty())
+            .unwrap_or_else(|| UNCATEGORIZED.to_string());
+        let mut expense = Expense::new(amount, &category, &date);
+        expense.note = self.memo.filter(|m| !m.is_empty());
+        Ok(expense)
+    }
+}
+
+/// Convert a QIF `DD/MM/YYYY` date to `YYYY-MM-DD`
+fn parse_qif_date(value: &str, line: usize) -> Result<String, ExpenseError> {
+    let bad = || ExpenseError::InvalidDate(format!("line {}: `{}`", line, value));
+    let parts: Vec<&str> = value.split('/').collect();
+    let [day, month, year] = parts.as_slice() else {
+        return Err(bad());
+    };
+    let (day, month, year) = match (day.parse(), month.parse(), year.parse()) {
+        (Ok(d), Ok(m), Ok(y)) if year.len() == 4 => (d, m, y),
+        _ => return Err(bad()),
+    };
+    let date = format_ymd(year, month, day);
+    checked_ymd(&date).ok_or_else(bad)?;
+    Ok(date)
+}
+
+/// Read expenses from an SGML-style Open Financial Exchange (OFX) statement
+///
+/// Each `<STMTTRN>` transaction becomes an expense from its `<DTPOSTED>`
+/// (only the `YYYYMMDD` part is used), `<TRNAMT>`, `<NAME>` (kept as the
+/// payee) and `<MEMO>` (kept as the note). Closing tags are optional, as
+/// in OFX 1.x; XML OFX is not supported. OFX has no categories, so every
+/// expense gets `UNCATEGORIZED`, and amounts are stored as-is (debits stay
+/// negative). Every imported expense gets a fresh id.
+pub fn from_ofx_string(ofx: &str) -> Result<Vec<Expense>, ExpenseError> {
+    let mut expenses = Vec::new();
+    let mut transaction: Option<OfxTransaction> = None;
+    // Everything after each `<` is a tag, then `>`, then its value
+    for piece in ofx.split('<').skip(1) {
+        let (tag, value) = piece.split_once('>').ok_or_else(|| {
+            ExpenseError::ParseError(format!("unterminated OFX tag `<{}`", piece.trim()))
+        })?;
+        let tag = tag.trim().to_ascii_uppercase();
+        let value = unescape_sgml(value.trim());
+        match tag.as_str() {
+            "STMTTRN" => transaction = Some(OfxTransaction::default()),
+            "/STMTTRN" => {
+                if let Some(finished) = transaction.take() {
+                    expenses.push(finished.into_expense(expenses.len() + 1)?);
+                }
+            }
+            _ => {
+                if let Some(current) = transaction.as_mut() {
+                    match tag.as_str() {
+                        "DTPOSTED" => current.date = Some(value),
+                        "TRNAMT" => current.amount = Some(value),
+                        "NAME" => current.name = Some(value),
+                        "MEMO" => current.memo = Some(value),
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+    Ok(expenses)
+}
+
+/// The fields of one OFX transaction seen so far
+#[derive(Default)]
+struct OfxTransaction {
+    date: Option<String>,
+    amount: Option<String>,
+    name: Option<String>,
+    memo: Option<String>,
+}
+
+impl OfxTransaction {
+    /// Turn a finished transaction into an expense, `number` is used in errors
+    fn into_expense(self, number: usize) -> Result<Expense, ExpenseError> {
+        let missing =
+            |tag: &str| ExpenseError::ParseError(format!("transaction {}: no <{}>", number, tag));
+        let posted = self.date.ok_or_else(|| missing("DTPOSTED"))?;
+        let date = posted
+            .get(..8)
+            .filter(|d| d.bytes().all(|b| b.is_ascii_digit()))
+            .map(|d| format!("{}-{}-{}", &d[..4], &d[4..6], &d[6..]))
+            .filter(|d| checked_ymd(d).is_some())
+            .ok_or_else(|| {
+                ExpenseError::InvalidDate(format!("transaction {}: `{}`", number, posted))
+            })?;
+        let amount = self.amount.ok_or_else(|| missing("TRNAMT"))?;
+        let amount = parse_amount(&amount).map_err(|_| {
+            ExpenseError::ParseError(format!("transaction {}: bad amount `{}`", number, amount))
+        })?;
+        let mut expense = Expense::new(amount, UNCATEGORIZED, &date);
+        expense.payee = self.name.filter(|n| !n.is_empty());
+        expense.note = self.memo.filter(|m| !m.is_empty());
+        Ok(expense)
+    }
+}
+
+/// Replace the character entities OFX uses in values
+fn unescape_sgml(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// Render an import summary for the terminal, with the total in `currency`
+pub fn format_import_summary(summary: &ImportSummary, currency: &str) -> String {
+    let noun = if summary.imported == 1 {
+        "expense"
+    } else {
+        "expenses"
+    };
+    let mut out = format!(
+        "Imported {} {} totaling {}\n",
+        summary.imported,
+        noun,
+        format_amount(summary.total_amount, currency)
+    );
+    if let Some((first, last)) = &summary.date_range {
+        out.push_str(&format!("Dates: {} to {}\n", first, last));
+    }
+    if !summary.per_category.is_empty() {
+        out.push_str("By category:\n");
+        for (category, count) in &summary.per_category {
+            out.push_str(&format!("  {}: {}\n", category, count));
+        }
+    }
+    if !summary.auto_categorized.is_empty() {
+        out.push_str(&format!(
+            "Auto-categorized ({}):\n",
+            summary.auto_categorized.len()
+        ));
+        for row in &summary.auto_categorized {
+            out.push_str(&format!(
+                "  line {}: \"{}\" -> {}\n",
+                row.line, row.description, row.category
+            ));
+        }
+    }
+    if summary.duplicates > 0 {
+        out.push_str(&format!(
+            "Already recorded, left out: {}\n",
+            summary.duplicates
+        ));
+    }
+    if !summary.skipped.is_empty() {
+        out.push_str(&format!("Skipped ({}):\n", summary.skipped.len()));
+        for row in &summary.skipped {
+            out.push_str(&format!("  line {}: {}\n", row.line, row.reason));
+        }
+    }
+    out
+}
+
+/// What importing a file would do, worked out without changing anything
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct ImportPreview {
+    /// Rows that would be added
+    pub new: Vec<Expense>,
+    /// Rows matching an expense already recorded (or an earlier row),
+    /// which the import leaves out
+    pub duplicates: Vec<Expense>,
+    /// Rows that couldn't be read
+    pub failed: Vec<SkippedRow>,
+}
+
+/// Sort the expenses read from an export into new rows and duplicates of
+/// `existing`, the same way the import itself does (see `split_recorded`)
+pub fn preview_imported(
+    existing: &[Expense],
+    imported: &[Expense],
+    summary: &ImportSummary,
+) -> ImportPreview {
+    let (new, duplicates) = split_recorded(existing, imported);
+    ImportPreview {
+        new: new.into_iter().cloned().collect(),
+        duplicates: duplicates.into_iter().cloned().collect(),
+        failed: summary.skipped.clone(),
+    }
+}
+
+/// Preview importing the exported CSV at `path` next to `existing`
+///
+/// Columns are laid out as `mapping` says, or found by header name without
+/// one (as `import_with_summary`), and fields are split as `csv` says or
+/// as detected; rows without a category get one from `rules`.
+#[cfg(feature = "fs")]
+pub fn preview_import(
+    existing: &[Expense],
+    path: &Path,
+    mapping: Option<&ColumnMapping>,
+    csv: Option<CsvOptions>,
+    rules: &BTreeMap<String, Vec<String>>,
+) -> Result<ImportPreview, ExpenseError> {
+    let text = std::fs::read_to_string(path)?;
+    let (imported, summary) = match mapping {
+        Some(mapping) => import_with_mapping(&text, mapping, csv, rules)?,
+        None => import_with_summary(&text, csv, rules)?,
+    };
+    Ok(preview_imported(existing, &imported, &summary))
+}
+
+/// Render an import preview as three sections: new rows, duplicates and
+/// rows that failed to parse
+pub fn format_import_preview(preview: &ImportPreview) -> String {
+    let mut out = format!("New ({}):\n", preview.new.len());
+    for expense in &preview.new {
+        out.push_str(&format!("  {}\n", expense));
+    }
+    out.push_str(&format!("Duplicates ({}):\n", preview.duplicates.len()));
+    for expense in &preview.duplicates {
+        out.push_str(&format!("  {}\n", expense));
+    }
+    out.push_str(&format!("Failed ({}):\n", preview.failed.len()));
+    for row in &preview.failed {
+        out.push_str(&format!("  line {}: {}\n", row.line, row.reason));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_finds_columns_by_name() {
+        let text = "Date,Description,Amount,Category\n2026-01-05,Lunch,12.50,food\n";
+        let expenses = import_from_csv(text, None).unwrap();
+        assert_eq!(expenses.len(), 1);
+        assert_eq!(expenses[0].amount, 12.5);
+        assert_eq!(expenses[0].category, "food");
+        assert_eq!(expenses[0].date, "2026-01-05");
+    }
+
+    #[test]
+    fn test_import_reads_semicolons_and_decimal_commas() {
+        let text = "\
+Date;Description;Amount;Category
+2026-01-05;\"Lunch; with team\";12,50;food
+2026-01-06;Uber to work;8;
+2026-01-07;Refund?;abc;food
+";
+        // Detected from the header line
+        let (expenses, summary) = import_with_summary(text, None, &rules()).unwrap();
+        let read: Vec<(f64, &str)> = expenses
+            .iter()
+            .map(|e| (e.amount, e.category.as_str()))
+            .collect();
+        assert_eq!(read, vec![(12.5, "food"), (8.0, "transport")]);
+        assert_eq!(expenses[0].note.as_deref(), Some("Lunch; with team"));
+        assert_eq!(summary.skipped[0].reason, "bad amount `abc`");
+        let err = import_from_csv(text, None).unwrap_err();
+        assert!(err.to_string().contains("bad amount `abc`"), "{}", err);
+
+        // Given, for a file with no header to detect from
+        let csv = CsvOptions {
+            delimiter: ';',
+            decimal_separator: ',',
+            include_header: false,
+        };
+        let mut mapping = ColumnMapping::new(1, 0);
+        mapping.has_header = false;
+        let statement = "2026-01-05;1234,50\n2026-01-06;0,99\n";
+        let (expenses, _) = import_with_mapping(statement, &mapping, Some(csv), &rules()).unwrap();
+        let amounts: Vec<f64> = expenses.iter().map(|e| e.amount).collect();
+        assert_eq!(amounts, vec![1234.5, 0.99]);
+        let (expenses, summary) = import_with_mapping(statement, &mapping, None, &rules()).unwrap();
+        assert!(expenses.is_empty());
+        assert_eq!(summary.skipped.len(), 2);
+    }
+
+    const FIXTURE: &str = "\
+Date,Description,Amount,Category
+2026-01-05,Lunch,12.50,food
+2026-01-06,Uber to work,8.00,
+2026-01-07,Refund?,abc,food
+2026-01-09,Groceries,37.00,food
+";
+
+    fn rules() -> BTreeMap<String, Vec<String>> {
+        let mut rules = BTreeMap::new();
+        rules.insert("transport".to_string(), vec!["uber".to_string()]);
+        rules
+    }
+
+    #[test]
+    fn test_uncategorized() {
+        let expenses = vec![
+            Expense::new(12.5, "food", "2026-01-05"),
+            Expense::new(3.5, UNCATEGORIZED, "2026-01-05"),
+            Expense::new(8.0, " ", "2026-01-06"),
+            Expense::new(9.0, "Uncategorized", "2026-01-06"),
+        ];
+        let found: Vec<f64> = uncategorized(&expenses).iter().map(|e| e.amount).collect();
+        assert_eq!(found, vec![3.5, 8.0]);
+        assert!(uncategorized(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_import_summary_from_fixture() {
+        let (expenses, summary) = import_with_summary(FIXTURE, None, &rules()).unwrap();
+
+        assert_eq!(expenses.len(), 3);
+        assert_eq!(summary.imported, 3);
+        assert_eq!(summary.total_amount, 57.5);
+        assert_eq!(summary.per_category["food"], 2);
+        assert_eq!(summary.per_category["transport"], 1);
+        assert_eq!(
+            summary.date_range,
+            Some(("2026-01-05".to_string(), "2026-01-09".to_string()))
+        );
+        assert_eq!(
+            summary.auto_categorized,
+            vec![AutoCategorizedRow {
+                line: 3,
+                description: "Uber to work".to_string(),
+                category: "transport".to_string(),
+            }]
+        );
+        assert_eq!(
+            summary.skipped,
+            vec![SkippedRow {
+                line: 4,
+                reason: "bad amount `abc`".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_format_import_summary() {
+        let (_, summary) = import_with_summary(FIXTURE, None, &rules()).unwrap();
+        assert_eq!(
+            format_import_summary(&summary, "USD"),
+            "\
+Imported 3 expenses totaling $57.50
+Dates: 2026-01-05 to 2026-01-09
+By category:
+  food: 2
+  transport: 1
+Auto-categorized (1):
+  line 3: \"Uber to work\" -> transport
+Skipped (1):
+  line 4: bad amount `abc`
+"
+        );
+    }
+
+    #[test]
+    fn test_import_summary_total_in_currency() {
+        let (_, summary) = import_with_summary(FIXTURE, None, &rules()).unwrap();
+        let text = format_import_summary(&summary, "NGN");
+        assert!(
+            text.starts_with("Imported 3 expenses totaling ₦57.50\n"),
+            "{}",
+            text
+        );
+    }
+
+    #[test]
+    fn test_preview_sorts_rows_into_sections() {
+        let existing = vec![Expense::new(37.0, "food", "2026-01-09")];
+        let (imported, summary) = import_with_summary(FIXTURE, None, &rules()).unwrap();
+        let preview = preview_imported(&existing, &imported, &summary);
+        assert_eq!(
+            format_import_preview(&preview),
+            "\
+New (2):
+  $12.50 - food (2026-01-05)
+  $8.00 - transport (2026-01-06)
+Duplicates (1):
+  $37.00 - food (2026-01-09)
+Failed (1):
+  line 4: bad amount `abc`
+"
+        );
+
+        // Importing the same export twice adds nothing the second time
+        let again = preview_imported(&imported, &imported, &summary);
+        assert!(again.new.is_empty());
+        assert_eq!(again.duplicates.len(), 3);
+    }
+
+    #[test]
+    fn test_import_summary_serializes_to_json() {
+        let (_, summary) = import_with_summary(FIXTURE, None, &rules()).unwrap();
+        let json: serde_json::Value = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["imported"], 3);
+        assert_eq!(json["skipped"][0]["line"], 4);
+        assert_eq!(json["date_range"][1], "2026-01-09");
+    }
+
+    #[test]
+    fn test_import_with_mapping_from_bank_fixture() {
+        let statement = "\
+Trans. Date,Reference,Remarks,Debit
+05/01/2026,FT001,POS Shoprite Lekki,-12500.00
+06/01/2026,FT002,Uber trip,-3200.50
+2026-01-07,FT003,Transfer,-100.00
+";
+        let mapping = ColumnMapping {
+            description_col: Some(2),
+            date_format: "DD/MM/YYYY".to_string(),
+            negate: true,
+            ..ColumnMapping::new(3, 0)
+        };
+        let (expenses, summary) = import_with_mapping(statement, &mapping, None, &rules()).unwrap();
+
+        let rows: Vec<(f64, &str, &str)> = expenses
+            .iter()
+            .map(|e| (e.amount, e.category.as_str(), e.date.as_str()))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                (12500.0, UNCATEGORIZED, "2026-01-05"),
+                (3200.5, "transport", "2026-01-06"),
+            ]
+        );
+        assert_eq!(expenses[0].note.as_deref(), Some("POS Shoprite Lekki"));
+        assert_eq!(
+            summary.skipped,
+            vec![SkippedRow {
+                line: 4,
+                reason: "bad date `2026-01-07`".to_string(),
+            }]
+        );
+
+        let bad = ColumnMapping {
+            date_format: "DD/MM".to_string(),
+            ..mapping
+        };
+        assert!(import_with_mapping(statement, &bad, None, &rules()).is_err());
+    }
+
+    #[test]
+    fn test_convert_date() {
+        assert_eq!(
+            convert_date("31/01/2026", "DD/MM/YYYY").as_deref(),
+            Some("2026-01-31")
+        );
+        assert_eq!(
+            convert_date("01.31.2026", "MM.DD.YYYY").as_deref(),
+            Some("2026-01-31")
+        );
+        assert_eq!(
+            convert_date("2026-01-31", ISO_DATE_FORMAT).as_deref(),
+            Some("2026-01-31")
+        );
+        assert_eq!(convert_date("31/02/2026", "DD/MM/YYYY"), None);
+        assert_eq!(convert_date("1/1/2026", "DD/MM/YYYY"), None);
+        assert_eq!(convert_date("31/01/2026 ", "DD/MM/YYYY"), None);
+    }
+
+    #[test]
+    fn test_from_qif_string_parses_three_records() {
+        let qif = "\
+!Type:Bank
+D05/01/2026
+T-45.50
+MWeekly shop
+Lfood
+^
+D06/01/2026
+T1,200.00
+Lrent
+^
+D31/01/2026
+T8
+MBus pass
+";
+        let expenses = from_qif_string(qif).unwrap();
+
+        assert_eq!(expenses.len(), 3);
+        assert_eq!(expenses[0].date, "2026-01-05");
+        assert_eq!(expenses[0].amount, -45.5);
+        assert_eq!(expenses[0].category, "food");
+        assert_eq!(expenses[0].note.as_deref(), Some("Weekly shop"));
+        assert_eq!(expenses[1].amount, 1200.0);
+        assert_eq!(expenses[1].note, None);
+        assert_eq!(expenses[2].date, "2026-01-31");
+        assert_eq!(expenses[2].category, UNCATEGORIZED);
+    }
+
+    #[test]
+    fn test_from_qif_string_rejects_bad_records() {
+        assert!(matches!(
+            from_qif_string("D31/02/2026\nT1\n^\n"),
+            Err(ExpenseError::InvalidDate(_))
+        ));
+        assert_eq!(
+            from_qif_string("D01/02/2026\nLfood\n^\n"),
+            Err(ExpenseError::ParseError(
+                "line 3: record has no `T` (amount) line".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_from_ofx_string_parses_two_transactions() {
+        let ofx = "\
+OFXHEADER:100
+DATA:OFXSGML
+
+<OFX>
+<BANKMSGSRSV1><STMTTRNRS><STMTRS>
+<BANKTRANLIST>
+<STMTTRN>
+<TRNTYPE>DEBIT
+<DTPOSTED>20260105120000[-5:EST]
+<TRNAMT>-45.50
+<NAME>GREEN &amp; CO GROCERS
+<MEMO>Weekly shop
+</STMTTRN>
+<STMTTRN>
+<TRNTYPE>DEBIT
+<DTPOSTED>20260131
+<TRNAMT>-1200.00
+<NAME>LANDLORD LTD
+</STMTTRN>
+</BANKTRANLIST>
+</STMTRS></STMTTRNRS></BANKMSGSRSV1>
+</OFX>
+";
+        let expenses = from_ofx_string(ofx).unwrap();
+
+        assert_eq!(expenses.len(), 2);
+        assert_eq!(expenses[0].date, "2026-01-05");
+        assert_eq!(expenses[0].amount, -45.5);
+        assert_eq!(expenses[0].payee.as_deref(), Some("GREEN & CO GROCERS"));
+        assert_eq!(expenses[0].note.as_deref(), Some("Weekly shop"));
+        assert_eq!(expenses[1].date, "2026-01-31");
+        assert_eq!(expenses[1].amount, -1200.0);
+        assert_eq!(expenses[1].note, None);
+        assert_eq!(expenses[1].category, UNCATEGORIZED);
+    }
+
+    thread_local! {
+        static WARNINGS: std::cell::RefCell<Vec<String>> = const { std::cell::RefCell::new(Vec::new()) };
+    }
+
+    /// Keeps the warnings logged by the current thread
+    struct CaptureLogger;
+
+    impl log::Log for CaptureLogger {
+        fn enabled(&self, _: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            if record.level() == log::Level::Warn {
+                WARNINGS.with(|w| w.borrow_mut().push(record.args().to_string()));
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    /// Run `f` and return the warnings it logged
+    fn capture_warnings(f: impl FnOnce()) -> Vec<String> {
+        static LOGGER: CaptureLogger = CaptureLogger;
+        static INSTALL: std::sync::Once = std::sync::Once::new();
+        INSTALL.call_once(|| {
+            log::set_logger(&LOGGER).unwrap();
+            log::set_max_level(log::LevelFilter::Trace);
+        });
+        WARNINGS.with(|w| w.borrow_mut().clear());
+        f();
+        WARNINGS.with(|w| w.take())
+    }
+
+    #[test]
+    fn test_skipped_row_logs_one_warning() {
+        let warnings = capture_warnings(|| {
+            import_with_summary(FIXTURE, None, &rules()).unwrap();
+        });
+        assert_eq!(warnings, vec!["skipping import line 4: bad amount `abc`"]);
+    }
+}
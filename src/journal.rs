@@ -0,0 +1,311 @@
+// This module keeps a journal of the changes made to a data file, so the
+// latest one can be undone
+//
+// The journal is CSV: one row per expense added or removed, with a few
+// columns of its own in front of the data file's columns. Every row written
+// for one change (a whole import, say) shares a batch number, and a batch
+// is undone as one. The text functions work everywhere; reading and
+// writing journal files is behind the `fs` feature.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::error::ExpenseError;
+use crate::expense::Expense;
+use crate::storage::{CSV_HEADER, from_csv, join_csv_fields, parse_csv, to_csv};
+
+/// Columns a journal row has in front of the data file's columns
+pub const JOURNAL_COLUMNS: &str = "batch,command,action,position,next_id";
+
+/// What a change did to one expense
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JournalAction {
+    Added,
+    Removed,
+}
+
+impl fmt::Display for JournalAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JournalAction::Added => write!(f, "added"),
+            JournalAction::Removed => write!(f, "removed"),
+        }
+    }
+}
+
+/// One expense added to or removed from the data file
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournalEntry {
+    /// Shared by every entry of one change
+    pub batch: u64,
+    /// The command that made the change, e.g. `import`
+    pub command: String,
+    pub action: JournalAction,
+    /// Index of the expense in the list: after the change if it was added,
+    /// before it if it was removed
+    pub position: usize,
+    /// The data file's `# next_id=` value before the change, if it had one
+    pub next_id: Option<u64>,
+    pub expense: Expense,
+}
+
+/// Journal entries for the change from `before` to `after`, matching
+/// expenses by id: removals first, in list order, then additions
+///
+/// An expense edited in place (same id, other fields changed) is recorded
+/// as its old version removed and its new version added.
+pub fn changes_between(
+    batch: u64,
+    command: &str,
+    next_id: Option<u64>,
+    before: &[Expense],
+    after: &[Expense],
+) -> Vec<JournalEntry> {
+    let before_ids: HashMap<u64, &Expense> = before.iter().map(|e| (e.id, e)).collect();
+    let after_ids: HashMap<u64, &Expense> = after.iter().map(|e| (e.id, e)).collect();
+    let entry = |action, position, expense: &Expense| JournalEntry {
+        batch,
+        command: command.to_string(),
+        action,
+        position,
+        next_id,
+        expense: expense.clone(),
+    };
+    let removed = before
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| after_ids.get(&e.id) != Some(e))
+        .map(|(i, e)| entry(JournalAction::Removed, i, e));
+    let added = after
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| before_ids.get(&e.id) != Some(e))
+        .map(|(i, e)| entry(JournalAction::Added, i, e));
+    removed.chain(added).collect()
+}
+
+/// The entries of the latest batch, taken off the end of `entries`
+pub fn split_last_batch(entries: &mut Vec<JournalEntry>) -> Vec<JournalEntry> {
+    let Some(last) = entries.last().map(|e| e.batch) else {
+        return Vec::new();
+    };
+    let start = entries
+        .iter()
+        .rposition(|e| e.batch != last)
+        .map_or(0, |i| i + 1);
+    entries.split_off(start)
+}
+
+/// `expenses` with the change recorded in `batch` reverted
+///
+/// Expenses the change removed go back where they were, so reverting the
+/// latest change to an untouched file restores it exactly. Fails, naming
+/// the command, if the file has changed since in a way that gets in the
+/// way: an added expense is gone or edited, or a removed id is back.
+pub fn undo_batch(
+    expenses: &[Expense],
+    batch: &[JournalEntry],
+) -> Result<Vec<Expense>, ExpenseError> {
+    let stale = |entry: &JournalEntry| {
+        ExpenseError::NotFound(format!(
+            "expense #{} as `{}` left it; the data file changed since",
+            entry.expense.id, entry.command
+        ))
+    };
+    let mut restored = expenses.to_vec();
+    let mut removed: Vec<&JournalEntry> = Vec::new();
+    for entry in batch {
+        match entry.action {
+            JournalAction::Added => {
+                let i = restored
+                    .iter()
+                    .position(|e| *e == entry.expense)
+                    .ok_or_else(|| stale(entry))?;
+                restored.remove(i);
+            }
+            JournalAction::Removed => removed.push(entry),
+        }
+    }
+    removed.sort_by_key(|entry| entry.position);
+    for entry in removed {
+        if restored.iter().any(|e| e.id == entry.expense.id) {
+            return Err(stale(entry));
+        }
+        let at = entry.position.min(restored.len());
+        restored.insert(at, entry.expense.clone());
+    }
+    Ok(restored)
+}
+
+/// Write journal entries as CSV text, one entry per line after the header
+pub fn to_journal_csv(entries: &[JournalEntry]) -> String {
+    let mut out = format!("{},{}\n", JOURNAL_COLUMNS, CSV_HEADER);
+    for entry in entries {
+        let fields = [
+            entry.batch.to_string(),
+            entry.command.clone(),
+            entry.action.to_string(),
+            entry.position.to_string(),
+            entry.next_id.map(|id| id.to_string()).unwrap_or_default(),
+        ];
+        // The expense's row as the data file writes it, minus the header
+        let row = to_csv([&entry.expense]);
+        let row = row.lines().nth(1).unwrap_or("");
+        out.push_str(&format!("{},{}\n", join_csv_fields(&fields), row));
+    }
+    out
+}
+
+/// Read journal entries written by `to_journal_csv`
+pub fn from_journal_csv(text: &str) -> Result<Vec<JournalEntry>, ExpenseError> {
+    // The data file reader picks the expense columns out by name
+    let expenses = from_csv(text)?;
+    let rows = parse_csv(text)?.into_iter().skip(1);
+    let mut entries = Vec::new();
+    for ((line, fields), expense) in rows.zip(expenses) {
+        let bad = |column: &str, value: &str| {
+            ExpenseError::ParseError(format!("line {}: bad {} `{}`", line, column, value))
+        };
+        let number = |column: &str, value: &str| -> Result<u64, ExpenseError> {
+            value.parse().map_err(|_| bad(column, value))
+        };
+        let action = match fields[2].as_str() {
+            "added" => JournalAction::Added,
+            "removed" => JournalAction::Removed,
+            other => return Err(bad("action", other)),
+        };
+        let next_id = match fields[4].as_str() {
+            "" => None,
+            value => Some(number("next_id", value)?),
+        };
+        entries.push(JournalEntry {
+            batch: number("batch", &fields[0])?,
+            command: fields[1].clone(),
+            action,
+            position: number("position", &fields[3])? as usize,
+            next_id,
+            expense,
+        });
+    }
+    Ok(entries)
+}
+
+/// Where the command line journals changes to the data file at `path`
+#[cfg(feature = "fs")]
+pub fn journal_path(path: &str) -> String {
+    format!("{}.journal", path)
+}
+
+/// Read a journal file, treating a missing file as an empty journal
+#[cfg(feature = "fs")]
+pub fn load_journal(path: &str) -> Result<Vec<JournalEntry>, ExpenseError> {
+    if !std::path::Path::new(path).exists() {
+        return Ok(Vec::new());
+    }
+    from_journal_csv(&std::fs::read_to_string(path)?).map_err(|err| err.with_context(path))
+}
+
+/// Replace a journal file's entries
+#[cfg(feature = "fs")]
+pub fn save_journal(path: &str, entries: &[JournalEntry]) -> Result<(), ExpenseError> {
+    std::fs::write(path, to_journal_csv(entries))?;
+    Ok(())
+}
+
+/// Journal the change from `before` to `after` as one new batch
+///
+/// Does nothing if no expense was added or removed.
+#[cfg(feature = "fs")]
+pub fn record_change(
+    path: &str,
+    command: &str,
+    next_id: Option<u64>,
+    before: &[Expense],
+    after: &[Expense],
+) -> Result<(), ExpenseError> {
+    let mut entries = load_journal(path)?;
+    let batch = entries.last().map_or(1, |e| e.batch + 1);
+    let changes = changes_between(batch, command, next_id, before, after);
+    if changes.is_empty() {
+        return Ok(());
+    }
+    entries.extend(changes);
+    save_journal(path, &entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Vec<Expense> {
+        let mut tagged = Expense::with_id(2, 20.0, "transport", "2026-01-08").with_tag("work");
+        tagged.note = Some("bus, then \"train\"".to_string());
+        vec![
+            Expense::with_id(1, 45.5, "food", "2026-01-08"),
+            tagged,
+            Expense::with_id(3, 30.0, "food", "2026-01-07"),
+        ]
+    }
+
+    #[test]
+    fn test_undo_batch_restores_order() {
+        let before = sample();
+        let mut after = vec![before[1].clone()];
+        after.push(Expense::with_id(4, 9.0, "coffee", "2026-01-09"));
+        let batch = changes_between(1, "purge", Some(5), &before, &after);
+        let actions: Vec<(JournalAction, u64)> =
+            batch.iter().map(|e| (e.action, e.expense.id)).collect();
+        assert_eq!(
+            actions,
+            vec![
+                (JournalAction::Removed, 1),
+                (JournalAction::Removed, 3),
+                (JournalAction::Added, 4),
+            ]
+        );
+        assert_eq!(undo_batch(&after, &batch).unwrap(), before);
+
+        // An added expense edited since can't be taken back out
+        let mut edited = after.clone();
+        edited[1].amount = 10.0;
+        assert!(undo_batch(&edited, &batch).is_err());
+    }
+
+    #[test]
+    fn test_edits_in_place_are_recorded() {
+        let before = sample();
+        let mut after = before.clone();
+        after[1].category = "travel".to_string();
+        let batch = changes_between(1, "categorize", None, &before, &after);
+        let actions: Vec<(JournalAction, usize, &str)> = batch
+            .iter()
+            .map(|e| (e.action, e.position, e.expense.category.as_str()))
+            .collect();
+        assert_eq!(
+            actions,
+            vec![
+                (JournalAction::Removed, 1, "transport"),
+                (JournalAction::Added, 1, "travel"),
+            ]
+        );
+        assert_eq!(undo_batch(&after, &batch).unwrap(), before);
+        assert!(changes_between(1, "categorize", None, &before, &before).is_empty());
+    }
+
+    #[test]
+    fn test_journal_csv_round_trip() {
+        let before = sample();
+        let mut entries = changes_between(1, "delete", Some(4), &before, &before[..1]);
+        entries.extend(changes_between(2, "add", None, &[], &before[..1]));
+        let read = from_journal_csv(&to_journal_csv(&entries)).unwrap();
+        assert_eq!(read, entries);
+
+        let mut entries = read;
+        let last = split_last_batch(&mut entries);
+        assert_eq!(last.len(), 1);
+        assert_eq!(last[0].command, "add");
+        assert_eq!(entries.len(), 2);
+        assert_eq!(split_last_batch(&mut entries).len(), 2);
+        assert!(split_last_batch(&mut entries).is_empty());
+    }
+}
@@ -0,0 +1,63 @@
+// This module turns expenses into JSON shaped for JavaScript callers, such
+// as a web UI using the library compiled to WASM
+
+use serde::Serialize;
+
+use crate::expense::Expense;
+
+/// One expense as JavaScript sees it
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsExpense<'a> {
+    /// A string, since a JavaScript number can't hold every `u64`
+    id: String,
+    amount: f64,
+    category: &'a str,
+    date: &'a str,
+    /// `null` rather than missing, so every object has the same keys
+    note: Option<&'a str>,
+    payee: Option<&'a str>,
+    tags: &'a [String],
+    currency: Option<&'a str>,
+}
+
+/// Expenses as a JSON array that is easy to use from JavaScript
+///
+/// Every object has the same keys (`id`, `amount`, `category`, `date`,
+/// `note`, `payee`, `tags`, `currency`), with ids as strings and missing
+/// values as `null`.
+pub fn to_js_friendly_json(expenses: &[Expense]) -> String {
+    let rows: Vec<JsExpense> = expenses
+        .iter()
+        .map(|e| JsExpense {
+            id: e.id.to_string(),
+            amount: e.amount,
+            category: &e.category,
+            date: &e.date,
+            note: e.note.as_deref(),
+            payee: e.payee.as_deref(),
+            tags: &e.tags,
+            currency: e.currency.as_deref(),
+        })
+        .collect();
+    serde_json::to_string(&rows).expect("expenses are always representable as JSON")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_js_friendly_json_shape() {
+        let expenses = vec![
+            Expense::with_id(u64::MAX, 45.5, "food", "2026-01-08").with_note("lunch"),
+            Expense::with_id(2, 20.0, "transport", "2026-01-09")
+                .with_tag("work")
+                .with_currency("NGN"),
+        ];
+        assert_eq!(
+            to_js_friendly_json(&expenses),
+            r#"[{"id":"18446744073709551615","amount":45.5,"category":"food","date":"2026-01-08","note":"lunch","payee":null,"tags":[],"currency":null},{"id":"2","amount":20.0,"category":"transport","date":"2026-01-09","note":null,"payee":null,"tags":["work"],"currency":"NGN"}]"#
+        );
+    }
+}
@@ -0,0 +1,46 @@
+// Library root: the expense tracker's modules live here so that both the
+// binary and the tests can use them
+
+#[cfg(feature = "fs")]
+pub mod archive;
+pub mod binary;
+pub mod budget;
+pub mod cashflow;
+#[cfg(feature = "fs")]
+pub mod cli;
+pub mod clock;
+pub mod config;
+pub mod currency;
+pub mod dates;
+pub mod diff;
+pub mod doctor;
+pub mod error;
+pub mod expense;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod format;
+pub mod formats;
+pub mod gnucash;
+pub mod ical;
+pub mod import;
+pub mod journal;
+#[cfg(feature = "fs")]
+pub mod lock;
+pub mod js;
+pub mod operations;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+pub mod query;
+pub mod recurring;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod slack;
+pub mod slice_ext;
+pub mod storage;
+pub mod templates;
+pub mod timeseries;
+pub mod tracker;
+#[cfg(feature = "watch")]
+pub mod watch;
+#[cfg(feature = "http")]
+pub mod webhook;
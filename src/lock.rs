@@ -0,0 +1,249 @@
+// This module keeps two `expense` processes from changing one data file at
+// the same time
+//
+// The lock is a file next to the data file, created only if it doesn't
+// exist yet and holding the owner's process id. It is advisory: only code
+// that takes the lock is kept out. A lock left behind by a process that
+// crashed is noticed and removed by the next one to come along.
+//
+// Taking over a stale lock renames it out of the way first and checks it
+// again there, so two waiters that both find it stale can't end up with
+// one of them deleting the lock the other just created.
+
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::error::ExpenseError;
+
+/// How long `FileLock::acquire` waits by default before giving up
+pub const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A lock this old is stale when its process can't be checked
+pub const STALE_AFTER: Duration = Duration::from_secs(10 * 60);
+
+/// How often a waiting process looks at the lock again
+const RETRY_EVERY: Duration = Duration::from_millis(50);
+
+/// Tells apart the names stale locks are moved to by one process
+static TAKEOVERS: AtomicU64 = AtomicU64::new(0);
+
+/// Where the lock for the data file at `path` lives
+pub fn lock_path(path: &str) -> String {
+    format!("{}.lock", path)
+}
+
+/// An exclusive lock on a data file, released when dropped
+#[derive(Debug)]
+pub struct FileLock {
+    path: PathBuf,
+}
+
+impl FileLock {
+    /// Lock the data file at `path`, waiting up to `timeout` for another
+    /// process to let go of it
+    ///
+    /// A stale lock is removed and taken over. Fails with "another expense
+    /// process is running" if the lock is still held after `timeout`.
+    pub fn acquire(path: &str, timeout: Duration) -> Result<FileLock, ExpenseError> {
+        let lock = PathBuf::from(lock_path(path));
+        let start = Instant::now();
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&lock) {
+                Ok(mut file) => {
+                    writeln!(file, "{}", std::process::id())?;
+                    log::debug!("locked {}", path);
+                    return Ok(FileLock { path: lock });
+                }
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => {}
+                Err(err) => return Err(err.into()),
+            }
+            if is_stale(&lock) && remove_stale(&lock) {
+                log::warn!("removed stale lock {}", lock.display());
+                continue;
+            }
+            if start.elapsed() >= timeout {
+                let owner = std::fs::read_to_string(&lock).unwrap_or_default();
+                return Err(ExpenseError::IoError(format!(
+                    "another expense process is running (pid {} holds {})",
+                    owner.trim(),
+                    lock.display()
+                )));
+            }
+            std::thread::sleep(RETRY_EVERY);
+        }
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Whether the lock at `lock` was left behind by a process that is gone
+///
+/// Only when its process can't be checked (the lock can't be read yet, or
+/// the platform can't tell) is a lock judged by its age instead, and
+/// stale once older than `STALE_AFTER`.
+fn is_stale(lock: &Path) -> bool {
+    let pid = std::fs::read_to_string(lock)
+        .ok()
+        .and_then(|text| text.trim().parse::<u32>().ok());
+    if let Some(running) = pid.and_then(process_is_running) {
+        return !running;
+    }
+    let Ok(modified) = std::fs::metadata(lock).and_then(|m| m.modified()) else {
+        return false;
+    };
+    let age = SystemTime::now()
+        .duration_since(modified)
+        .unwrap_or_default();
+    age > STALE_AFTER
+}
+
+/// Remove the stale lock at `lock`, returning whether it was removed
+///
+/// The lock is renamed to a name of this waiter's own and judged again
+/// there: if another waiter replaced it with a fresh lock in the meantime,
+/// that one is what got renamed, and it is put back instead.
+fn remove_stale(lock: &Path) -> bool {
+    let mut aside = lock.as_os_str().to_owned();
+    aside.push(format!(
+        ".stale-{}-{}",
+        std::process::id(),
+        TAKEOVERS.fetch_add(1, Ordering::Relaxed)
+    ));
+    let aside = PathBuf::from(aside);
+    // Gone already: another waiter took it over first
+    if std::fs::rename(lock, &aside).is_err() {
+        return false;
+    }
+    if is_stale(&aside) {
+        let _ = std::fs::remove_file(&aside);
+        return true;
+    }
+    // Linking back fails rather than overwrite a lock created since
+    if std::fs::hard_link(&aside, lock).is_err() {
+        log::warn!("couldn't put back the lock {}", lock.display());
+    }
+    let _ = std::fs::remove_file(&aside);
+    false
+}
+
+/// Whether process `pid` is running, or `None` if that can't be told
+#[cfg(target_os = "linux")]
+fn process_is_running(pid: u32) -> Option<bool> {
+    let proc = Path::new("/proc");
+    proc.join("self")
+        .exists()
+        .then(|| proc.join(pid.to_string()).exists())
+}
+
+/// Without a cheap way to check, let the lock's age decide
+#[cfg(not(target_os = "linux"))]
+fn process_is_running(_pid: u32) -> Option<bool> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> TempDir {
+            let dir = std::env::temp_dir().join(format!("lock_{}_{}", name, std::process::id()));
+            std::fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+
+        fn data_file(&self) -> String {
+            self.0.join("expenses.csv").to_str().unwrap().to_string()
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_held_lock_times_out_until_released() {
+        let dir = TempDir::new("held");
+        let path = dir.data_file();
+        let held = FileLock::acquire(&path, LOCK_TIMEOUT).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(lock_path(&path)).unwrap(),
+            format!("{}\n", std::process::id())
+        );
+
+        let err = FileLock::acquire(&path, Duration::from_millis(120)).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("another expense process is running"),
+            "{}",
+            err
+        );
+
+        drop(held);
+        assert!(!Path::new(&lock_path(&path)).exists());
+        let _lock = FileLock::acquire(&path, Duration::ZERO).unwrap();
+    }
+
+    #[test]
+    fn test_stale_locks_are_taken_over() {
+        let dir = TempDir::new("stale");
+        let path = dir.data_file();
+
+        // Left by a process that is no longer running
+        if cfg!(target_os = "linux") {
+            std::fs::write(lock_path(&path), format!("{}\n", u32::MAX)).unwrap();
+            let lock = FileLock::acquire(&path, Duration::ZERO).unwrap();
+            drop(lock);
+        }
+
+        // Too old to trust, and its process can't be told
+        let age = |text: &str| {
+            std::fs::write(lock_path(&path), text).unwrap();
+            let old = SystemTime::now() - STALE_AFTER - Duration::from_secs(1);
+            std::fs::File::options()
+                .write(true)
+                .open(lock_path(&path))
+                .unwrap()
+                .set_modified(old)
+                .unwrap();
+        };
+        age("");
+        let lock = FileLock::acquire(&path, Duration::ZERO).unwrap();
+        drop(lock);
+
+        // Old, but its process is alive
+        if cfg!(target_os = "linux") {
+            age(&format!("{}\n", std::process::id()));
+            assert!(FileLock::acquire(&path, Duration::ZERO).is_err());
+            std::fs::remove_file(lock_path(&path)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_late_takeover_keeps_the_fresh_lock() {
+        let dir = TempDir::new("late");
+        let path = dir.data_file();
+        // A second waiter that found the old lock stale only gets to it
+        // after the first one replaced it with a lock of its own
+        let held = FileLock::acquire(&path, Duration::ZERO).unwrap();
+        assert!(!remove_stale(Path::new(&lock_path(&path))));
+        assert_eq!(
+            std::fs::read_to_string(lock_path(&path)).unwrap(),
+            format!("{}\n", std::process::id())
+        );
+        assert_eq!(std::fs::read_dir(&dir.0).unwrap().count(), 1);
+        drop(held);
+        assert!(!Path::new(&lock_path(&path)).exists());
+    }
+}
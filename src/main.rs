@@ -84,15 +84,33 @@
 
 // Entry point of the application
 
-// Declare modules (tells Rust about other files)
-mod expense;      // Looks for expense.rs
-mod operations;   // Looks for operations.rs
-
-// Import what we need from our modules
-use expense::Expense;
-use operations::*;
+// Import what we need from the library (see lib.rs for the modules)
+use rust_folder::cli;
+use rust_folder::clock::SystemClock;
+use rust_folder::operations::*;
+use rust_folder::slice_ext::ExpenseSliceExt;
 
 fn main() {
+    // With arguments, act as the `expense` command; without, run the demo
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        cli::init_logging(&args);
+        let stdin = std::io::stdin();
+        if let Err(err) = cli::run(
+            &args,
+            &SystemClock,
+            &mut stdin.lock(),
+            &mut std::io::stdout(),
+        ) {
+            eprintln!("error: {}", err);
+            std::process::exit(match err {
+                cli::CliError::Usage(_) => 2,
+                _ => 1,
+            });
+        }
+        return;
+    }
+
     println!("=== Expense Tracker ===\n");
 
     // Create a list to store expenses
@@ -112,21 +130,27 @@ fn main() {
 
     // Calculate total
     println!("\nTotal: ${:.2}", calculate_total(&expenses));
+    if let Some(average) = average_amount(&expenses) {
+        println!("Average: ${:.2}", average);
+    }
 
     // View today's expenses
     println!("\nExpenses for 2026-01-08:");
-    let today = view_expenses_by_date(&expenses, "2026-01-08");
-    for expense in today {
+    for expense in expenses.by_date("2026-01-08").iter() {
         expense.display();
     }
 
     // Get food expenses
     println!("\nFood expenses:");
-    let food = get_by_category(&expenses, "food");
-    for expense in food {
-        expense.display();
+    let food = expenses.by_category("food");
+    if food.count() == 0 {
+        println!("(none)");
+    } else {
+        for expense in food.iter() {
+            expense.display();
+        }
+        println!("Food total: ${:.2}", food.total());
     }
-    println!("Food total: ${:.2}", total_by_category(&expenses, "food"));
 
     // Find max expense
     if let Some(max) = find_max(&expenses) {
@@ -135,7 +159,7 @@ fn main() {
     }
 
     // Count by category
-    println!("\nFood expense count: {}", count_by_category(&expenses, "food"));
+    println!("\nFood expense count: {}", food.count());
 }
 
 // Tests
@@ -160,6 +184,798 @@ mod tests {
         assert_eq!(calculate_total(&expenses), 30.0);
     }
 
+    #[test]
+    fn test_calculate_total_exact() {
+        let mut expenses = Vec::new();
+        add_expense(&mut expenses, 0.1, "food", "2024-06-01");
+        add_expense(&mut expenses, 0.2, "food", "2024-06-01");
+        assert_eq!(calculate_total_exact(&expenses), Ok(0.3));
+
+        add_expense(&mut expenses, 1e18, "rent", "2024-06-01");
+        assert!(calculate_total_exact(&expenses).is_err());
+
+        let mut expenses = Vec::new();
+        add_expense(&mut expenses, f64::NAN, "food", "2024-06-01");
+        assert!(calculate_total_exact(&expenses).is_err());
+    }
+
+    #[test]
+    fn test_group_by_week() {
+        let mut expenses = Vec::new();
+        add_expense(&mut expenses, 10.0, "food", "2026-01-05");
+        add_expense(&mut expenses, 20.0, "food", "2026-01-11");
+        add_expense(&mut expenses, 5.0, "transport", "2026-01-12");
+        add_expense(&mut expenses, 7.0, "food", "2025-01-13");
+
+        let weeks = group_by_week(&expenses);
+        assert_eq!(weeks.len(), 2);
+        assert_eq!(weeks[&2].len(), 2);
+        assert_eq!(weeks[&3].len(), 2);
+
+        let totals = total_by_week(&expenses);
+        assert_eq!(totals[&2], 30.0);
+        assert_eq!(totals[&3], 12.0);
+
+        let year_weeks = group_by_year_week(&expenses);
+        assert_eq!(year_weeks.len(), 3);
+        assert_eq!(year_weeks[&(2025, 3)][0].amount, 7.0);
+    }
+
+    #[test]
+    fn test_weekly_totals_leave_out_unreal_dates() {
+        let expenses = vec![
+            rust_folder::expense::Expense::new(1.0, "food", "2026-02-30"),
+            rust_folder::expense::Expense::new(4.0, "food", "2026-03-03"),
+        ];
+        let totals = weekly_totals(&expenses);
+        assert_eq!(totals.len(), 1);
+        assert_eq!(totals["2026-03-02"], 4.0);
+    }
+
+    #[test]
+    fn test_group_by_quarter() {
+        let mut expenses = Vec::new();
+        add_expense(&mut expenses, 10.0, "food", "2025-12-20");
+        add_expense(&mut expenses, 20.0, "food", "2026-03-31");
+        add_expense(&mut expenses, 5.0, "transport", "2026-01-02");
+        add_expense(&mut expenses, 7.0, "food", "2026-04-01");
+
+        let quarters = group_by_quarter(&expenses);
+        assert_eq!(quarters.len(), 3);
+        assert_eq!(quarters[&(2025, 4)][0].amount, 10.0);
+        assert_eq!(quarters[&(2026, 1)].len(), 2);
+
+        let totals = total_by_quarter(&expenses);
+        assert_eq!(totals[&(2026, 1)], 25.0);
+        assert_eq!(totals[&(2026, 2)], 7.0);
+    }
+
+    #[test]
+    fn test_totals_by_day_of_month() {
+        let mut expenses = Vec::new();
+        add_expense(&mut expenses, 10.0, "food", "2026-01-01");
+        add_expense(&mut expenses, 20.0, "food", "2026-02-01");
+        add_expense(&mut expenses, 5.0, "transport", "2026-01-31");
+        add_expense(&mut expenses, 7.0, "food", "2026-02-30");
+
+        let totals = totals_by_day_of_month(&expenses);
+        assert_eq!(totals[0], 30.0);
+        assert_eq!(totals[30], 5.0);
+        assert_eq!(totals[29], 0.0);
+        assert_eq!(totals.iter().sum::<f64>(), 35.0);
+
+        assert_eq!(peak_spending_day_of_month(&expenses), Some((0, 30.0)));
+        assert_eq!(peak_spending_day_of_month(&[]), None);
+    }
+
+    #[test]
+    fn test_daily_totals_for_month() {
+        let totals = daily_totals_for_month(&[], 2024, 2);
+        assert_eq!(totals.len(), 29);
+        assert!(totals.iter().all(|&(_, total)| total == 0.0));
+        assert_eq!(totals[28], (29, 0.0));
+
+        let mut expenses = Vec::new();
+        add_expense(&mut expenses, 10.0, "food", "2026-01-08");
+        add_expense(&mut expenses, 2.5, "transport", "2026-01-08");
+        add_expense(&mut expenses, 5.0, "food", "2026-01-31");
+        add_expense(&mut expenses, 7.0, "food", "2026-02-08");
+        let totals = daily_totals_for_month(&expenses, 2026, 1);
+        assert_eq!(totals.len(), 31);
+        assert_eq!(totals[7], (8, 12.5));
+        assert_eq!(totals[30], (31, 5.0));
+        assert_eq!(totals[0], (1, 0.0));
+        assert!(daily_totals_for_month(&expenses, 2026, 13).is_empty());
+    }
+
+    #[test]
+    fn test_fiscal_year_total() {
+        let mut expenses = Vec::new();
+        add_expense(&mut expenses, 1.0, "food", "2025-03-31");
+        add_expense(&mut expenses, 10.0, "food", "2025-04-01");
+        add_expense(&mut expenses, 20.0, "rent", "2025-12-31");
+        add_expense(&mut expenses, 40.0, "food", "2026-03-15");
+        add_expense(&mut expenses, 80.0, "food", "2026-04-01");
+
+        assert_eq!(fiscal_year_total(&expenses, 4, 2025), 70.0);
+        assert_eq!(fiscal_year_total(&expenses, 4, 2024), 1.0);
+        assert_eq!(fiscal_year_total(&expenses, 1, 2025), 31.0);
+        assert_eq!(fiscal_year_total(&expenses, 13, 2025), 0.0);
+    }
+
+    #[test]
+    fn test_top_n_dates_by_spend() {
+        let mut expenses = Vec::new();
+        let amounts = [5.0, 40.0, 12.0, 40.0, 3.0, 25.0, 8.0, 12.0, 60.0, 1.0];
+        for (day, amount) in amounts.iter().enumerate() {
+            add_expense(&mut expenses, *amount, "food", &format!("2026-01-{:02}", day + 1));
+        }
+        add_expense(&mut expenses, 30.0, "rent", "2026-01-06");
+
+        let top = top_n_dates_by_spend(&expenses, 5);
+        let expected = [
+            ("2026-01-09", 60.0),
+            ("2026-01-06", 55.0),
+            ("2026-01-04", 40.0),
+            ("2026-01-02", 40.0),
+            ("2026-01-08", 12.0),
+        ];
+        let expected: Vec<(String, f64)> = expected.iter().map(|(d, t)| (d.to_string(), *t)).collect();
+        assert_eq!(top, expected);
+
+        assert_eq!(top_n_dates_by_spend(&expenses, 50).len(), 10);
+        assert!(top_n_dates_by_spend(&expenses, 0).is_empty());
+    }
+
+    #[test]
+    fn test_longest_gap_between_expenses() {
+        let mut expenses = Vec::new();
+        add_expense(&mut expenses, 10.0, "food", "2026-01-06");
+        add_expense(&mut expenses, 10.0, "food", "2026-01-01");
+        add_expense(&mut expenses, 10.0, "food", "2026-02-05");
+        add_expense(&mut expenses, 10.0, "rent", "2026-01-06");
+        add_expense(&mut expenses, 10.0, "food", "2026-02-10");
+        add_expense(&mut expenses, 10.0, "food", "not a date");
+
+        assert_eq!(
+            longest_gap_between_expenses(&expenses),
+            Some(("2026-01-06".to_string(), "2026-02-05".to_string(), 30))
+        );
+
+        let mut expenses = Vec::new();
+        add_expense(&mut expenses, 10.0, "food", "2026-01-06");
+        add_expense(&mut expenses, 10.0, "rent", "2026-01-06");
+        assert_eq!(longest_gap_between_expenses(&expenses), None);
+    }
+
+    #[test]
+    fn test_categorize_by_amount_tier() {
+        let mut expenses = Vec::new();
+        add_expense(&mut expenses, 9.99, "coffee", "2024-06-01");
+        add_expense(&mut expenses, 10.0, "food", "2024-06-01");
+        add_expense(&mut expenses, 100.0, "transport", "2024-06-01");
+        add_expense(&mut expenses, 100.01, "rent", "2024-06-01");
+
+        let tiers = categorize_by_amount_tier(&expenses);
+        assert_eq!(tiers.len(), 3);
+        assert_eq!(tiers.values().map(|t| t.len()).sum::<usize>(), 4);
+        assert_eq!(tiers["small"][0].amount, 9.99);
+        let medium: Vec<f64> = tiers["medium"].iter().map(|e| e.amount).collect();
+        assert_eq!(medium, vec![10.0, 100.0]);
+        assert_eq!(tiers["large"][0].amount, 100.01);
+
+        assert!(categorize_by_amount_tier(&[])["large"].is_empty());
+    }
+
+    #[test]
+    fn test_most_frequent_category() {
+        let mut expenses = Vec::new();
+        add_expense(&mut expenses, 5.0, "food", "2024-06-01");
+        add_expense(&mut expenses, 500.0, "transport", "2024-06-01");
+        add_expense(&mut expenses, 5.0, "food", "2024-06-02");
+        add_expense(&mut expenses, 500.0, "transport", "2024-06-02");
+        add_expense(&mut expenses, 5.0, "food", "2024-06-03");
+        assert_eq!(most_frequent_category(&expenses), Some("food".to_string()));
+
+        add_expense(&mut expenses, 5.0, "transport", "2024-06-03");
+        add_expense(&mut expenses, 5.0, "coffee", "2024-06-03");
+        assert_eq!(most_frequent_category(&expenses), Some("food".to_string()));
+        assert_eq!(most_frequent_category(&[]), None);
+    }
+
+    #[test]
+    fn test_least_frequent_category() {
+        let mut expenses = Vec::new();
+        add_expense(&mut expenses, 5.0, "food", "2024-06-01");
+        add_expense(&mut expenses, 5.0, "food", "2024-06-02");
+        add_expense(&mut expenses, 20.0, "transport", "2024-06-02");
+        add_expense(&mut expenses, 5.0, "food", "2024-06-03");
+        assert_eq!(least_frequent_category(&expenses), Some("transport".to_string()));
+        assert_eq!(least_frequent_category(&[]), None);
+
+        let mut expenses = Vec::new();
+        add_expense(&mut expenses, 20.0, "transport", "2024-06-01");
+        add_expense(&mut expenses, 5.0, "coffee", "2024-06-01");
+        assert_eq!(least_frequent_category(&expenses), Some("coffee".to_string()));
+    }
+
+    #[test]
+    fn test_expense_entropy() {
+        let mut expenses = Vec::new();
+        assert_eq!(expense_entropy(&expenses), 0.0);
+        add_expense(&mut expenses, 5.0, "food", "2024-06-01");
+        add_expense(&mut expenses, 50.0, "food", "2024-06-02");
+        assert_eq!(expense_entropy(&expenses), 0.0);
+
+        add_expense(&mut expenses, 20.0, "transport", "2024-06-02");
+        add_expense(&mut expenses, 20.0, "transport", "2024-06-03");
+        assert!((expense_entropy(&expenses) - 1.0).abs() < 1e-12);
+
+        add_expense(&mut expenses, 3.0, "coffee", "2024-06-03");
+        add_expense(&mut expenses, 3.0, "coffee", "2024-06-04");
+        assert!((expense_entropy(&expenses) - 1.585).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_impact_of_removing() {
+        let mut expenses = Vec::new();
+        add_expense(&mut expenses, 45.5, "food", "2024-06-01");
+        add_expense(&mut expenses, 20.0, "transport", "2024-06-02");
+        let id = expenses[1].id;
+        assert_eq!(impact_of_removing(&expenses, id), Some(20.0));
+        assert_eq!(impact_of_removing(&expenses, 0), None);
+    }
+
+    #[test]
+    fn test_get_by_categories() {
+        let mut expenses = Vec::new();
+        add_expense(&mut expenses, 45.5, "food", "2024-06-01");
+        add_expense(&mut expenses, 20.0, "transport", "2024-06-02");
+        add_expense(&mut expenses, 500.0, "rent", "2024-06-03");
+        add_expense(&mut expenses, 10.0, "food", "2024-06-04");
+
+        let found: Vec<f64> = get_by_categories(&expenses, &["food", "transport"]).iter().map(|e| e.amount).collect();
+        assert_eq!(found, vec![45.5, 20.0, 10.0]);
+        assert_eq!(total_by_categories(&expenses, &["food", "transport", "coffee"]), 75.5);
+        assert_eq!(total_by_categories(&expenses, &["food", "food"]), 55.5);
+
+        assert!(get_by_categories(&expenses, &[]).is_empty());
+        assert_eq!(total_by_categories(&expenses, &[]), 0.0);
+    }
+
+    #[test]
+    fn test_clone_expense_for_date() {
+        let coffee = rust_folder::expense::Expense::new(4.5, "coffee", "2026-01-08")
+            .with_note("subscription")
+            .with_tag("monthly");
+        let copy = clone_expense_for_date(&coffee, "2026-02-08").unwrap();
+        assert_eq!(copy.date, "2026-02-08");
+        assert_ne!(copy.id, coffee.id);
+        assert_eq!(
+            rust_folder::expense::Expense { id: coffee.id, date: coffee.date.clone(), ..copy },
+            coffee
+        );
+        assert_eq!(coffee.date, "2026-01-08");
+
+        assert!(clone_expense_for_date(&coffee, "2026-02-30").is_err());
+        assert!(clone_expense_for_date(&coffee, "next month").is_err());
+    }
+
+    #[test]
+    fn test_clone_expense_with_amount() {
+        let netflix = rust_folder::expense::Expense::new(9.99, "entertainment", "2026-01-15").with_note("netflix");
+        let copy = clone_expense_with_amount(&netflix, 12.49).unwrap();
+        assert_eq!(copy.amount, 12.49);
+        assert_eq!(copy.category, "entertainment");
+        assert_eq!(copy.date, "2026-01-15");
+        assert_eq!(copy.note.as_deref(), Some("netflix"));
+        assert_ne!(copy.id, netflix.id);
+        assert_eq!(netflix.amount, 9.99);
+
+        assert!(clone_expense_with_amount(&netflix, -1.0).is_err());
+        assert!(clone_expense_with_amount(&netflix, f64::NAN).is_err());
+    }
+
+    #[test]
+    fn test_get_by_parent_category() {
+        let mut expenses = Vec::new();
+        add_expense(&mut expenses, 45.5, "food", "2024-06-01");
+        add_expense(&mut expenses, 12.0, "food:grocery", "2024-06-02");
+        add_expense(&mut expenses, 30.0, "food:restaurant", "2024-06-03");
+        add_expense(&mut expenses, 5.0, "foodtruck", "2024-06-04");
+        add_expense(&mut expenses, 20.0, "transport", "2024-06-05");
+
+        let found: Vec<&str> = get_by_parent_category(&expenses, "food").iter().map(|e| e.category.as_str()).collect();
+        assert_eq!(found, vec!["food", "food:grocery", "food:restaurant"]);
+        let found: Vec<f64> = get_by_parent_category(&expenses, "food:grocery").iter().map(|e| e.amount).collect();
+        assert_eq!(found, vec![12.0]);
+        assert!(get_by_parent_category(&expenses, "grocery").is_empty());
+    }
+
+    #[test]
+    fn test_fully_qualified_category() {
+        let plain = rust_folder::expense::Expense::new(45.5, "food", "2024-06-01");
+        assert_eq!(fully_qualified_category(&plain), "food");
+        let grocery = plain.clone().with_subcategory("grocery");
+        assert_eq!(fully_qualified_category(&grocery), "food:grocery");
+
+        let odd = rust_folder::expense::Expense::new(5.0, "a:b, \"c\"", "2024-06-01").with_subcategory("naïve/ü:x");
+        assert_eq!(fully_qualified_category(&odd), "a:b, \"c\":naïve/ü:x");
+    }
+
+    #[test]
+    fn test_earliest_latest_and_date_span() {
+        let mut expenses = Vec::new();
+        add_expense(&mut expenses, 10.0, "food", "2024-06-05");
+        add_expense(&mut expenses, 20.0, "rent", "2024-06-01");
+        add_expense(&mut expenses, 30.0, "food", "2024-06-09");
+        add_expense(&mut expenses, 40.0, "coffee", "2024-06-01");
+        add_expense(&mut expenses, 50.0, "coffee", "2024-06-09");
+
+        // Ties go to the first in the list
+        assert_eq!(earliest_expense(&expenses).unwrap().amount, 20.0);
+        assert_eq!(latest_expense(&expenses).unwrap().amount, 30.0);
+        assert_eq!(date_span(&expenses), Some(("2024-06-01".to_string(), "2024-06-09".to_string())));
+
+        let single = &expenses[..1];
+        assert_eq!(earliest_expense(single), latest_expense(single));
+        assert_eq!(date_span(single), Some(("2024-06-05".to_string(), "2024-06-05".to_string())));
+
+        assert_eq!(earliest_expense(&[]), None);
+        assert_eq!(date_span(&[]), None);
+    }
+
+    #[test]
+    fn test_projected_end_of_month_total() {
+        let mut expenses = Vec::new();
+        add_expense(&mut expenses, 100.0, "food", "2024-02-01");
+        add_expense(&mut expenses, 50.0, "transport", "2024-02-10");
+
+        // 2024 is a leap year, so 19 days follow the 10th
+        assert_eq!(projected_end_of_month_total_on(&expenses, 10.0, "2024-02-10"), 340.0);
+        assert_eq!(projected_end_of_month_total_on(&expenses, 10.0, "2024-02-29"), 150.0);
+        assert_eq!(projected_end_of_month_total_on(&expenses, 10.0, "2024-04-30"), 150.0);
+        assert_eq!(projected_end_of_month_total_on(&[], 2.5, "2024-12-01"), 75.0);
+        assert_eq!(projected_end_of_month_total_on(&expenses, 10.0, "not a date"), 150.0);
+    }
+
+    fn paid(expenses: &mut Vec<rust_folder::expense::Expense>, payee: &str, amount: f64, date: &str) {
+        add_expense(expenses, amount, "subscriptions", date);
+        expenses.last_mut().unwrap().payee = Some(payee.to_string());
+    }
+
+    #[test]
+    fn test_payee_month_pivot() {
+        let mut expenses = Vec::new();
+        paid(&mut expenses, "Netflix", 15.99, "2025-12-03");
+        paid(&mut expenses, "Shoprite", 40.0, "2025-12-10");
+        paid(&mut expenses, "Shoprite", 25.5, "2025-12-20");
+        paid(&mut expenses, "Shoprite", 30.0, "2026-01-04");
+        add_expense(&mut expenses, 9.0, "coffee", "2025-12-03");
+
+        let pivot = payee_month_pivot(&expenses);
+        assert_eq!(pivot.len(), 2);
+        assert_eq!(pivot["Netflix"]["2025-12"], 15.99);
+        assert_eq!(pivot["Shoprite"]["2025-12"], 65.5);
+        assert_eq!(pivot["Shoprite"]["2026-01"], 30.0);
+    }
+
+    #[test]
+    fn test_probable_subscriptions() {
+        let mut expenses = Vec::new();
+        // Three months in a row across New Year, amounts within 5%
+        paid(&mut expenses, "Netflix", 15.99, "2025-11-03");
+        paid(&mut expenses, "Netflix", 15.99, "2025-12-03");
+        paid(&mut expenses, "Netflix", 16.49, "2026-01-03");
+        // A month missing in the middle
+        paid(&mut expenses, "Gym", 30.0, "2026-01-05");
+        paid(&mut expenses, "Gym", 30.0, "2026-02-05");
+        paid(&mut expenses, "Gym", 30.0, "2026-04-05");
+        // The amount creeps up by more than 5%
+        paid(&mut expenses, "Spotify", 10.0, "2026-01-10");
+        paid(&mut expenses, "Spotify", 10.2, "2026-02-10");
+        paid(&mut expenses, "Spotify", 10.8, "2026-03-10");
+
+        let found = probable_subscriptions(&expenses);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0, "Netflix");
+        assert!((found[0].1 - 16.156_666).abs() < 1e-5);
+
+        // Once Spotify settles, its latest three months qualify
+        paid(&mut expenses, "Spotify", 10.8, "2026-04-10");
+        paid(&mut expenses, "Spotify", 10.8, "2026-05-10");
+        let payees: Vec<String> = probable_subscriptions(&expenses).into_iter().map(|(payee, _)| payee).collect();
+        assert_eq!(payees, vec!["Netflix", "Spotify"]);
+    }
+
+    #[test]
+    fn test_apply_inflation() {
+        let mut expenses = Vec::new();
+        add_expense(&mut expenses, 100.0, "rent", "2024-06-01");
+        add_expense(&mut expenses, 50.0, "food", "2024-06-02");
+        apply_inflation(&mut expenses, 0.1, 1.0).unwrap();
+        assert!((expenses[0].amount - 110.0).abs() < 1e-9);
+        assert!((expenses[1].amount - 55.0).abs() < 1e-9);
+
+        apply_inflation(&mut expenses, 0.1, 0.0).unwrap();
+        assert!((expenses[0].amount - 110.0).abs() < 1e-9);
+        apply_inflation(&mut expenses, -1.0, 2.0).unwrap();
+        assert_eq!(expenses[0].amount, 0.0);
+
+        let mut expenses = Vec::new();
+        add_expense(&mut expenses, 100.0, "rent", "2024-06-01");
+        assert!(apply_inflation(&mut expenses, -1.5, 1.0).is_err());
+        assert!(apply_inflation(&mut expenses, 0.1, -1.0).is_err());
+        assert!(apply_inflation(&mut expenses, f64::NAN, 1.0).is_err());
+        assert_eq!(expenses[0].amount, 100.0);
+    }
+
+    #[test]
+    fn test_net_present_value() {
+        let mut expenses = Vec::new();
+        assert_eq!(net_present_value(&expenses, 0.1).unwrap(), 0.0);
+
+        add_expense(&mut expenses, 100.0, "rent", "2024-01-01");
+        add_expense(&mut expenses, 100.0, "rent", "2024-12-31");
+        add_expense(&mut expenses, 50.0, "food", "2024-07-01");
+        let total = calculate_total(&expenses);
+        assert!((net_present_value(&expenses, 0.0).unwrap() - total).abs() < 1e-9);
+
+        let npv = net_present_value(&expenses, 0.1).unwrap();
+        assert!(npv < total);
+        // The first expense isn't discounted, the one 365 days later is by 10%
+        let expected = 100.0 + 100.0 / 1.1 + 50.0 / 1.1f64.powf(182.0 / 365.0);
+        assert!((npv - expected).abs() < 1e-9);
+
+        assert!(net_present_value(&expenses, -1.0).is_err());
+        assert!(net_present_value(&expenses, -2.0).is_err());
+    }
+
+    #[test]
+    fn test_filter_valid_and_invalid() {
+        let mut expenses = Vec::new();
+        add_expense(&mut expenses, 45.5, "food", "2026-01-08");
+        add_expense(&mut expenses, -1.0, "refund", "2026-01-08");
+        add_expense(&mut expenses, 20.0, "transport", "2026-02-30");
+        add_expense(&mut expenses, 9.0, "coffee", "2026-01-09");
+
+        let valid: Vec<u64> = filter_valid(&expenses).iter().map(|e| e.id).collect();
+        let invalid: Vec<u64> = filter_invalid(&expenses).iter().map(|e| e.id).collect();
+        assert_eq!(valid, vec![expenses[0].id, expenses[3].id]);
+        assert_eq!(invalid, vec![expenses[1].id, expenses[2].id]);
+        assert!(filter_valid(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_expenses_with_complete_and_missing_fields() {
+        let complete = rust_folder::expense::Expense::new(45.5, "food", "2026-01-08")
+            .with_note("lunch")
+            .with_payee("Mama Put")
+            .with_tag("work")
+            .with_receipt_number("R-1042");
+        let no_receipt = rust_folder::expense::Expense::new(12.0, "food", "2026-01-10").with_note("snack").with_payee("Shop").with_tag("home");
+        let note_only = rust_folder::expense::Expense::new(20.0, "transport", "2026-01-08").with_note("bus");
+        let no_tags = rust_folder::expense::Expense::new(9.0, "coffee", "2026-01-09").with_note("latte").with_payee("Cafe");
+        let bare = rust_folder::expense::Expense::new(500.0, "rent", "2026-01-01");
+        let expenses = vec![complete, note_only, no_tags, bare, no_receipt];
+
+        let ids = |found: Vec<&rust_folder::expense::Expense>| found.iter().map(|e| e.id).collect::<Vec<_>>();
+        assert_eq!(ids(expenses_with_complete_fields(&expenses)), vec![expenses[0].id]);
+        assert_eq!(ids(expenses_with_missing_fields(&expenses)), vec![expenses[1].id, expenses[2].id, expenses[3].id, expenses[4].id]);
+    }
+
+    #[test]
+    fn test_count_unique_dates_and_categories() {
+        let mut expenses = Vec::new();
+        assert_eq!(count_unique_dates(&expenses), 0);
+        assert_eq!(count_unique_categories(&expenses), 0);
+
+        add_expense(&mut expenses, 45.5, "food", "2026-01-08");
+        add_expense(&mut expenses, 20.0, "transport", "2026-01-08");
+        add_expense(&mut expenses, 12.0, "food", "2026-01-08");
+        assert_eq!(count_unique_dates(&expenses), 1);
+        assert_eq!(count_unique_categories(&expenses), 2);
+
+        add_expense(&mut expenses, 9.0, "food", "2026-01-09");
+        assert_eq!(count_unique_dates(&expenses), 2);
+        assert_eq!(count_unique_categories(&expenses), 2);
+    }
+
+    #[test]
+    fn test_maximum_single_day_total() {
+        let mut expenses = Vec::new();
+        assert_eq!(maximum_single_day_total(&expenses), None);
+
+        add_expense(&mut expenses, 45.5, "food", "2026-01-08");
+        add_expense(&mut expenses, 30.0, "food", "2026-01-09");
+        add_expense(&mut expenses, 20.0, "transport", "2026-01-09");
+        assert_eq!(
+            maximum_single_day_total(&expenses),
+            Some(("2026-01-09".to_string(), 50.0))
+        );
+
+        // A tie goes to the most recent date
+        add_expense(&mut expenses, 4.5, "coffee", "2026-01-08");
+        assert_eq!(
+            maximum_single_day_total(&expenses),
+            Some(("2026-01-09".to_string(), 50.0))
+        );
+        add_expense(&mut expenses, 50.0, "rent", "2026-01-01");
+        assert_eq!(
+            maximum_single_day_total(&expenses),
+            Some(("2026-01-09".to_string(), 50.0))
+        );
+    }
+
+    #[test]
+    fn test_minimum_single_day_total() {
+        let mut expenses = Vec::new();
+        assert_eq!(minimum_single_day_total(&expenses), None);
+
+        // 2026-01-09 has no data and must not count as a zero day
+        add_expense(&mut expenses, 45.5, "food", "2026-01-08");
+        add_expense(&mut expenses, 30.0, "food", "2026-01-10");
+        add_expense(&mut expenses, 20.0, "transport", "2026-01-10");
+        add_expense(&mut expenses, 0.0, "food", "2026-01-11");
+        assert_eq!(
+            minimum_single_day_total(&expenses),
+            Some(("2026-01-08".to_string(), 45.5))
+        );
+
+        // A tie goes to the most recent date
+        add_expense(&mut expenses, 45.5, "rent", "2026-01-12");
+        assert_eq!(
+            minimum_single_day_total(&expenses),
+            Some(("2026-01-12".to_string(), 45.5))
+        );
+    }
+
+    #[test]
+    fn test_top_n_expenses_and_nth_largest() {
+        let mut expenses = Vec::new();
+        add_expense(&mut expenses, 20.0, "food", "2026-01-08");
+        add_expense(&mut expenses, 45.5, "rent", "2026-01-08");
+        add_expense(&mut expenses, 20.0, "transport", "2026-01-09");
+        add_expense(&mut expenses, 9.0, "coffee", "2026-01-09");
+
+        // Equal amounts keep their list order
+        let top: Vec<&str> = top_n_expenses(&expenses, 3).iter().map(|e| e.category.as_str()).collect();
+        assert_eq!(top, vec!["rent", "food", "transport"]);
+        assert_eq!(top_n_expenses(&expenses, 10).len(), 4);
+        assert!(top_n_expenses(&expenses, 0).is_empty());
+
+        assert_eq!(nth_largest(&expenses, 1).unwrap().category, "rent");
+        assert_eq!(nth_largest(&expenses, 3).unwrap().category, "transport");
+        assert!(nth_largest(&expenses, 0).is_none());
+        assert!(nth_largest(&expenses, 5).is_none());
+
+        let cheapest = top_n_by(&expenses, 1, |e| -e.amount);
+        assert_eq!(cheapest[0].category, "coffee");
+    }
+
+    #[test]
+    fn test_top_merchants() {
+        let mut expenses = Vec::new();
+        paid(&mut expenses, "Shoprite", 10.0, "2026-01-08");
+        paid(&mut expenses, "Landlord", 500.0, "2026-01-08");
+        paid(&mut expenses, "Shoprite", 15.0, "2026-01-09");
+        paid(&mut expenses, "Bolt", 25.0, "2026-01-09");
+        add_expense(&mut expenses, 900.0, "misc", "2026-01-08");
+        assert_eq!(
+            top_merchants(&expenses, 2),
+            vec![("Landlord".to_string(), 500.0), ("Bolt".to_string(), 25.0)]
+        );
+        assert_eq!(top_merchants(&expenses, 3)[2], ("Shoprite".to_string(), 25.0));
+    }
+
+    #[test]
+    fn test_top_n_by_matches_sorting() {
+        // A small LCG keeps the test deterministic without a rand crate
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = move || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            state >> 33
+        };
+        for _ in 0..200 {
+            let len = (next() % 40) as usize;
+            let expenses: Vec<rust_folder::expense::Expense> = (0..len)
+                // Few distinct amounts, so ties are common
+                .map(|i| rust_folder::expense::Expense::with_id(i as u64, (next() % 8) as f64 * 2.5, "food", "2026-01-08"))
+                .collect();
+            let n = (next() % 45) as usize;
+
+            let mut naive: Vec<&rust_folder::expense::Expense> = expenses.iter().collect();
+            naive.sort_by(|a, b| b.amount.total_cmp(&a.amount));
+            naive.truncate(n);
+            assert_eq!(top_n_by(&expenses, n, |e| e.amount), naive);
+        }
+    }
+
+    #[test]
+    fn test_expenses_added_after() {
+        let expenses = vec![
+            rust_folder::expense::Expense::with_id(7, 45.5, "food", "2026-01-08"),
+            rust_folder::expense::Expense::with_id(3, 20.0, "transport", "2026-01-08"),
+            rust_folder::expense::Expense::with_id(5, 9.0, "coffee", "2026-01-09"),
+        ];
+        assert_eq!(max_id(&expenses), Some(7));
+        assert_eq!(max_id(&[]), None);
+
+        let ids = |since| -> Vec<u64> { expenses_added_after(&expenses, since).iter().map(|e| e.id).collect() };
+        assert_eq!(ids(0), vec![3, 5, 7]);
+        assert_eq!(ids(4), vec![5, 7]);
+        assert!(ids(max_id(&expenses).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_reindex_ids_after_delete() {
+        let mut expenses = Vec::new();
+        add_expense(&mut expenses, 45.5, "food", "2026-01-08");
+        add_expense(&mut expenses, 20.0, "transport", "2026-01-08");
+        add_expense(&mut expenses, 9.0, "coffee", "2026-01-09");
+        expenses.remove(1);
+
+        reindex_ids(&mut expenses);
+        let ids: Vec<u64> = expenses.iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![1, 2]);
+        assert_eq!(expenses[1].category, "coffee");
+
+        reindex_from(&mut expenses, 1000);
+        let ids: Vec<u64> = expenses.iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![1000, 1001]);
+        assert!(rust_folder::expense::next_id() > 1001);
+    }
+
+    #[test]
+    fn test_most_expensive_category_by_total() {
+        let mut expenses = Vec::new();
+        add_expense(&mut expenses, 1000.0, "rent", "2026-01-01");
+        for day in ["2026-01-02", "2026-01-03", "2026-01-04", "2026-01-05"] {
+            add_expense(&mut expenses, 25.0, "food", day);
+        }
+        assert_eq!(most_frequent_category(&expenses), Some("food".to_string()));
+        assert_eq!(most_expensive_category(&expenses), Some("rent".to_string()));
+        assert_eq!(group_totals_by_category(&expenses)["food"], 100.0);
+
+        // A tie goes to the alphabetically first category
+        add_expense(&mut expenses, 900.0, "food", "2026-01-06");
+        assert_eq!(most_expensive_category(&expenses), Some("food".to_string()));
+    }
+
+    #[test]
+    fn test_least_expensive_category_by_total() {
+        let mut expenses = Vec::new();
+        add_expense(&mut expenses, 1000.0, "rent", "2026-01-01");
+        add_expense(&mut expenses, 15.0, "food", "2026-01-02");
+        add_expense(&mut expenses, 15.0, "food", "2026-01-03");
+        add_expense(&mut expenses, 20.0, "transport", "2026-01-03");
+        assert_eq!(least_expensive_category(&expenses), Some("transport".to_string()));
+
+        // A tie goes to the alphabetically first category
+        add_expense(&mut expenses, 10.0, "transport", "2026-01-04");
+        assert_eq!(least_expensive_category(&expenses), Some("food".to_string()));
+        add_expense(&mut expenses, 30.0, "books", "2026-01-04");
+        assert_eq!(least_expensive_category(&expenses), Some("books".to_string()));
+    }
+
+    #[test]
+    fn test_income_ratio_report() {
+        let mut expenses = Vec::new();
+        add_expense(&mut expenses, 500.0, "rent", "2026-01-01");
+        add_expense(&mut expenses, 150.0, "food", "2026-01-08");
+        add_expense(&mut expenses, 100.0, "food", "2026-01-20");
+        add_expense(&mut expenses, 250.0, "transport", "2026-01-21");
+        add_expense(&mut expenses, 2500.0, "travel", "2026-02-03");
+        expenses.push(rust_folder::expense::Expense::new(2000.0, "salary", "2026-01-31").with_kind(rust_folder::expense::TransactionKind::Income));
+
+        assert_eq!(
+            income_ratio_report(&expenses, 2000.0, "2026-01"),
+            vec![
+                ("rent".to_string(), 25.0),
+                ("food".to_string(), 12.5),
+                ("transport".to_string(), 12.5),
+                (SAVINGS_RATE.to_string(), 50.0),
+            ]
+        );
+        // Spending more than the income saves a negative share
+        assert_eq!(
+            income_ratio_report(&expenses, 2000.0, "2026-02"),
+            vec![("travel".to_string(), 125.0), (SAVINGS_RATE.to_string(), -25.0)]
+        );
+        assert_eq!(income_ratio_report(&expenses, 2000.0, "2026-03"), vec![(SAVINGS_RATE.to_string(), 100.0)]);
+
+        assert!(income_ratio_report(&expenses, 0.0, "2026-01").is_empty());
+        assert!(income_ratio_report(&expenses, -100.0, "2026-01").is_empty());
+        assert!(income_ratio_report(&expenses, f64::NAN, "2026-01").is_empty());
+    }
+
+    #[test]
+    fn test_average_amount() {
+        let mut expenses = Vec::new();
+        add_expense(&mut expenses, 45.0, "food", "2026-01-08");
+        add_expense(&mut expenses, 15.0, "transport", "2026-01-08");
+        assert_eq!(average_amount(&expenses), Some(30.0));
+        assert_eq!(average_amount(&expenses[..0]), None);
+    }
+
+    fn tagged(tag_sets: &[&[&str]]) -> Vec<rust_folder::expense::Expense> {
+        let mut expenses = Vec::new();
+        for tags in tag_sets {
+            add_expense(&mut expenses, 10.0, "food", "2024-06-01");
+            expenses.last_mut().unwrap().tags = tags.iter().map(|t| t.to_string()).collect();
+        }
+        expenses
+    }
+
+    #[test]
+    fn test_tag_frequency_and_most_common_tag() {
+        let expenses = tagged(&[&["work", "personal"], &["work"], &["work", "work"], &["personal"], &[]]);
+        let counts = tag_frequency(&expenses);
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts["work"], 3);
+        assert_eq!(counts["personal"], 2);
+        assert_eq!(most_common_tag(&expenses), Some("work".to_string()));
+
+        // A tie goes to the alphabetically first tag
+        let tied = tagged(&[&["work"], &["personal"]]);
+        assert_eq!(most_common_tag(&tied), Some("personal".to_string()));
+        assert_eq!(most_common_tag(&tagged(&[&[]])), None);
+    }
+
+    #[test]
+    fn test_filter_by_tags_all_and_any() {
+        let expenses = tagged(&[&["work", "travel"], &["work"], &["travel", "family"], &[]]);
+
+        let all: Vec<u64> = filter_by_tags_all(&expenses, &["work", "travel"]).iter().map(|e| e.id).collect();
+        assert_eq!(all, vec![expenses[0].id]);
+        let any: Vec<u64> = filter_by_tags_any(&expenses, &["work", "travel"]).iter().map(|e| e.id).collect();
+        assert_eq!(any, vec![expenses[0].id, expenses[1].id, expenses[2].id]);
+
+        assert_eq!(filter_by_tags_all(&expenses, &[]).len(), 4);
+        assert!(filter_by_tags_any(&expenses, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_untag_all_and_remove_tag_globally() {
+        let mut expenses = tagged(&[&["work", "travel"], &["Work"], &["travel"], &[]]);
+        assert_eq!(remove_tag_globally(&mut expenses, "work"), 2);
+        assert_eq!(expenses[0].tags, vec!["travel".to_string()]);
+        assert!(expenses[1].tags.is_empty());
+        assert_eq!(remove_tag_globally(&mut expenses, "work"), 0);
+
+        assert_eq!(untag_all(&mut expenses), 2);
+        assert!(expenses.iter().all(|e| e.tags.is_empty()));
+        assert_eq!(untag_all(&mut expenses), 0);
+    }
+
+    #[test]
+    fn test_filter_by_tags_ignores_case() {
+        let expenses = tagged(&[&["Work", "travel"], &["work"], &["TRAVEL"], &[]]);
+
+        let all: Vec<u64> = filter_by_tags_all(&expenses, &["WORK", "Travel"]).iter().map(|e| e.id).collect();
+        assert_eq!(all, vec![expenses[0].id]);
+        let any: Vec<u64> = filter_by_tags_any(&expenses, &["work", "travel"]).iter().map(|e| e.id).collect();
+        assert_eq!(any, vec![expenses[0].id, expenses[1].id, expenses[2].id]);
+    }
+
+    #[test]
+    fn test_tag_cooccurrence_normalizes_pairs() {
+        let expenses = tagged(&[
+            &["work", "travel"],
+            &["travel", "work"],
+            &["travel", "family", "work"],
+            &["family", "family"],
+        ]);
+        let pairs = tag_cooccurrence(&expenses);
+        let count = |a: &str, b: &str| pairs.get(&(a.to_string(), b.to_string())).copied();
+        assert_eq!(count("travel", "work"), Some(3));
+        assert_eq!(count("work", "travel"), None);
+        assert_eq!(count("family", "travel"), Some(1));
+        assert_eq!(count("family", "work"), Some(1));
+        assert_eq!(count("family", "family"), None);
+        assert_eq!(pairs.len(), 3);
+    }
+
     #[test]
     fn test_find_max() {
         let mut expenses = Vec::new();
@@ -170,4 +986,146 @@ mod tests {
         let max = find_max(&expenses).unwrap();
         assert_eq!(max.amount, 50.0);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_find_min_nonzero_skips_placeholders() {
+        let mut expenses = Vec::new();
+        add_expense(&mut expenses, 10.0, "food", "2024-06-01");
+        add_expense(&mut expenses, 0.0, "reminder", "2024-06-02");
+        add_expense(&mut expenses, 4.5, "coffee", "2024-06-03");
+        add_expense(&mut expenses, 0.0, "food", "2024-06-04");
+
+        assert_eq!(find_min(&expenses).unwrap().category, "reminder");
+        assert_eq!(find_min_nonzero(&expenses).unwrap().amount, 4.5);
+        let food = MinMaxOptions { ignore_zero: true, category: Some("food") };
+        assert_eq!(extreme_expense(&expenses, Extreme::Min, food).unwrap().amount, 10.0);
+        let all_food = MinMaxOptions { category: Some("food"), ..MinMaxOptions::default() };
+        assert_eq!(extreme_expense(&expenses, Extreme::Min, all_food).unwrap().amount, 0.0);
+        assert_eq!(extreme_expense(&expenses, Extreme::Max, food).unwrap().amount, 10.0);
+        assert!(extreme_expenses(&expenses, Extreme::Max, MinMaxOptions { category: Some("rent"), ..food }).is_empty());
+
+        // Without zero entries it is just find_min
+        let mut no_zeros = Vec::new();
+        add_expense(&mut no_zeros, 10.0, "food", "2024-06-01");
+        add_expense(&mut no_zeros, 4.5, "coffee", "2024-06-03");
+        assert!(std::ptr::eq(find_min_nonzero(&no_zeros).unwrap(), find_min(&no_zeros).unwrap()));
+
+        let mut only_zeros = Vec::new();
+        add_expense(&mut only_zeros, 0.0, "reminder", "2024-06-02");
+        assert_eq!(find_min_nonzero(&only_zeros), None);
+    }
+
+    #[test]
+    fn test_find_all_max_and_min_with_ties() {
+        let mut expenses = Vec::new();
+        add_expense(&mut expenses, 50.0, "rent", "2024-06-01");
+        add_expense(&mut expenses, 10.0, "food", "2024-06-01");
+        add_expense(&mut expenses, 50.0, "travel", "2024-06-02");
+        add_expense(&mut expenses, 20.0, "transport", "2024-06-01");
+        add_expense(&mut expenses, 50.0, "food", "2024-06-03");
+        add_expense(&mut expenses, 10.0, "coffee", "2024-06-03");
+
+        let max: Vec<&str> = find_all_max(&expenses).iter().map(|e| e.category.as_str()).collect();
+        assert_eq!(max, vec!["rent", "travel", "food"]);
+        assert_eq!(find_max(&expenses).unwrap().category, "rent");
+
+        let min: Vec<&str> = find_all_min(&expenses).iter().map(|e| e.category.as_str()).collect();
+        assert_eq!(min, vec!["food", "coffee"]);
+        assert_eq!(find_min(&expenses).unwrap().category, "food");
+
+        assert!(find_all_max(&[]).is_empty());
+        assert!(find_all_min(&[]).is_empty());
+        assert!(find_max(&[]).is_none());
+    }
+}
+// Every read operation with no expenses, so each one has a decided answer
+// for that case (see the top of operations.rs)
+#[cfg(test)]
+mod empty_input {
+    use super::*;
+
+    const NONE: &[rust_folder::expense::Expense] = &[];
+
+    #[test]
+    fn test_totals_and_counts_are_zero() {
+        assert_eq!(calculate_total(NONE), 0.0);
+        assert_eq!(calculate_total_exact(NONE), Ok(0.0));
+        assert_eq!(total_by_category(NONE, "food"), 0.0);
+        assert_eq!(total_by_categories(NONE, &["food"]), 0.0);
+        assert_eq!(fiscal_year_total(NONE, 4, 2025), 0.0);
+        assert_eq!(projected_end_of_month_total_on(NONE, 0.0, "2026-01-08"), 0.0);
+        assert_eq!(net_present_value(NONE, 0.05), Ok(0.0));
+        assert_eq!(expense_entropy(NONE), 0.0);
+        assert_eq!(count_by_category(NONE, "food"), 0);
+        assert_eq!(count_unique_dates(NONE), 0);
+        assert_eq!(count_unique_categories(NONE), 0);
+        assert_eq!(totals_by_day_of_month(NONE), [0.0; 31]);
+        assert_eq!(daily_totals_for_month(NONE, 2026, 2), (1..=28).map(|d| (d, 0.0)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_single_results_are_none() {
+        assert_eq!(average_amount(NONE), None);
+        assert_eq!(max_id(NONE), None);
+        assert_eq!(impact_of_removing(NONE, 1), None);
+        assert_eq!(most_frequent_category(NONE), None);
+        assert_eq!(least_frequent_category(NONE), None);
+        assert_eq!(most_common_tag(NONE), None);
+        assert_eq!(most_expensive_category(NONE), None);
+        assert_eq!(least_expensive_category(NONE), None);
+        assert_eq!(find_max(NONE), None);
+        assert_eq!(find_min(NONE), None);
+        assert_eq!(find_min_nonzero(NONE), None);
+        assert_eq!(extreme_expense(NONE, Extreme::Max, MinMaxOptions::default()), None);
+        assert_eq!(nth_largest(NONE, 1), None);
+        assert_eq!(maximum_single_day_total(NONE), None);
+        assert_eq!(minimum_single_day_total(NONE), None);
+        assert_eq!(earliest_expense(NONE), None);
+        assert_eq!(latest_expense(NONE), None);
+        assert_eq!(date_span(NONE), None);
+        assert_eq!(longest_gap_between_expenses(NONE), None);
+        assert_eq!(peak_spending_day_of_month(NONE), None);
+    }
+
+    #[test]
+    fn test_lists_and_maps_are_empty() {
+        assert!(view_expenses_by_date(NONE, "2026-01-08").is_empty());
+        assert!(filter_valid(NONE).is_empty());
+        assert!(filter_invalid(NONE).is_empty());
+        assert!(expenses_with_complete_fields(NONE).is_empty());
+        assert!(expenses_with_missing_fields(NONE).is_empty());
+        assert!(expenses_added_after(NONE, 0).is_empty());
+        assert!(get_by_category(NONE, "food").is_empty());
+        assert!(get_by_categories(NONE, &["food"]).is_empty());
+        assert!(get_by_parent_category(NONE, "food").is_empty());
+        assert!(find_all_max(NONE).is_empty());
+        assert!(find_all_min(NONE).is_empty());
+        assert!(top_n_expenses(NONE, usize::MAX).is_empty());
+        assert!(top_n_by(NONE, 3, |e| e.amount).is_empty());
+        assert!(top_merchants(NONE, 3).is_empty());
+        assert!(filter_by_tags_all(NONE, &[]).is_empty());
+        assert!(filter_by_tags_any(NONE, &["work"]).is_empty());
+        assert!(tag_cooccurrence(NONE).is_empty());
+        assert!(tag_frequency(NONE).is_empty());
+        assert!(group_totals_by_category(NONE).is_empty());
+        assert_eq!(income_ratio_report(NONE, 1000.0, "2026-01"), vec![(SAVINGS_RATE.to_string(), 100.0)]);
+        assert!(income_ratio_report(NONE, 0.0, "2026-01").is_empty());
+        assert!(filter_by_date_range(NONE, "2026-01-01", "2026-12-31").is_empty());
+        assert!(top_n_dates_by_spend(NONE, 3).is_empty());
+        assert!(daily_totals(NONE).is_empty());
+        assert!(weekly_totals(NONE).is_empty());
+        assert!(group_by_week(NONE).is_empty());
+        assert!(group_by_year_week(NONE).is_empty());
+        assert!(total_by_week(NONE).is_empty());
+        assert!(group_by_quarter(NONE).is_empty());
+        assert!(total_by_quarter(NONE).is_empty());
+        assert!(monthly_totals(NONE).is_empty());
+        assert!(monthly_totals_by_category(NONE, "food").is_empty());
+        assert!(payee_month_pivot(NONE).is_empty());
+        assert!(probable_subscriptions(NONE).is_empty());
+        // Every tier is always there, empty or not
+        let tiers = categorize_by_amount_tier(NONE);
+        assert_eq!(tiers.len(), 3);
+        assert!(tiers.values().all(|tier| tier.is_empty()));
+    }
+}
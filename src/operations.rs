@@ -0,0 +1,963 @@
+// This module contains functions that operate on expenses
+//
+// Every function here gives a defined answer for an empty list: totals and
+// counts are zero, lists and maps are empty, and results that only mean
+// something with at least one expense (the largest, the earliest, an
+// average) are an `Option` that is `None`. The `empty_input` tests in
+// main.rs call each function with no expenses to hold them to this.
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet};
+
+use crate::clock::{Clock, SystemClock};
+use crate::dates::{days_between_dates, days_in_month, iso_year_week, month_of, next_month, parse_date, quarter_of, validate_date_format, week_start};
+use crate::error::ExpenseError;
+use crate::expense::{Expense, reserve_ids_through, to_cents, validate_amount};
+
+/// Add an expense to the list
+pub fn add_expense(expenses: &mut Vec<Expense>, amount: f64, category: &str, date: &str) {
+    let expense = Expense::new(amount, category, date);
+    expenses.push(expense);
+}
+
+/// A copy of `expense` on `new_date` with a fresh id, e.g. to repeat a
+/// monthly subscription; everything else is kept
+pub fn clone_expense_for_date(expense: &Expense, new_date: &str) -> Result<Expense, ExpenseError> {
+    validate_date_format(new_date)?;
+    Ok(Expense { date: new_date.to_string(), ..expense.clone() }.with_fresh_id())
+}
+
+/// A copy of `expense` costing `new_amount` with a fresh id, e.g. after a
+/// subscription changes its price; everything else is kept
+pub fn clone_expense_with_amount(expense: &Expense, new_amount: f64) -> Result<Expense, ExpenseError> {
+    validate_amount(new_amount)?;
+    Ok(Expense { amount: new_amount, ..expense.clone() }.with_fresh_id())
+}
+
+/// View expenses by date
+pub fn view_expenses_by_date<'a>(expenses: &'a [Expense], date: &str) -> Vec<&'a Expense> {
+    expenses.iter().filter(|e| e.date == date).collect()
+}
+
+/// Calculate total of all expenses; 0.0 with no expenses
+pub fn calculate_total(expenses: &[Expense]) -> f64 {
+    expenses.iter().map(|e| e.amount).sum()
+}
+
+/// Calculate total of all expenses in whole cents, so it has no float
+/// rounding error; an amount or total out of range is an error
+pub fn calculate_total_exact(expenses: &[Expense]) -> Result<f64, ExpenseError> {
+    let mut cents: i64 = 0;
+    for expense in expenses {
+        cents = cents.checked_add(to_cents(expense.amount)?)
+            .ok_or_else(|| ExpenseError::InvalidAmount("total is too large".to_string()))?;
+    }
+    Ok(cents as f64 / 100.0)
+}
+
+/// The average amount, or `None` with no expenses rather than a
+/// misleading 0.0
+pub fn average_amount(expenses: &[Expense]) -> Option<f64> {
+    if expenses.is_empty() {
+        return None;
+    }
+    Some(calculate_total(expenses) / expenses.len() as f64)
+}
+
+/// Scale every amount up by `annual_rate` (0.1 for 10%) compounded over
+/// `years`, e.g. to bring old expenses to present value
+///
+/// Fails, changing nothing, for a rate below -1.0 (-100%) or negative
+/// years.
+pub fn apply_inflation(expenses: &mut [Expense], annual_rate: f64, years: f64) -> Result<(), ExpenseError> {
+    if annual_rate.is_nan() || annual_rate < -1.0 {
+        return Err(ExpenseError::InvalidAmount(format!("annual rate `{}` is below -100%", annual_rate)));
+    }
+    if years.is_nan() || years < 0.0 {
+        return Err(ExpenseError::InvalidAmount(format!("`{}` years is negative", years)));
+    }
+    let factor = (1.0 + annual_rate).powf(years);
+    for expense in expenses.iter_mut() {
+        expense.amount *= factor;
+    }
+    Ok(())
+}
+
+/// The expenses that pass `validate_expense`
+pub fn filter_valid(expenses: &[Expense]) -> Vec<&Expense> {
+    expenses.iter().filter(|e| e.is_valid()).collect()
+}
+
+/// The expenses that fail `validate_expense`, e.g. to fix them up
+pub fn filter_invalid(expenses: &[Expense]) -> Vec<&Expense> {
+    expenses.iter().filter(|e| !e.is_valid()).collect()
+}
+
+/// The expenses with every optional detail filled in: a note, a payee, at
+/// least one tag and a receipt number
+///
+/// The currency and subcategory don't count, since leaving them out is
+/// normal. `expenses_with_missing_fields` returns the rest.
+pub fn expenses_with_complete_fields(expenses: &[Expense]) -> Vec<&Expense> {
+    expenses.iter().filter(|e| has_complete_fields(e)).collect()
+}
+
+/// The expenses lacking a note, a payee, tags or a receipt number, e.g. to
+/// fill them in
+pub fn expenses_with_missing_fields(expenses: &[Expense]) -> Vec<&Expense> {
+    expenses.iter().filter(|e| !has_complete_fields(e)).collect()
+}
+
+fn has_complete_fields(expense: &Expense) -> bool {
+    expense.note.is_some() && expense.payee.is_some() && !expense.tags.is_empty()
+        && expense.receipt_number.is_some()
+}
+
+/// Expenses with an id above `since_id`, lowest id first, e.g. the ones
+/// added since the last sync
+pub fn expenses_added_after(expenses: &[Expense], since_id: u64) -> Vec<&Expense> {
+    let mut added: Vec<&Expense> = expenses.iter().filter(|e| e.id > since_id).collect();
+    added.sort_by_key(|e| e.id);
+    added
+}
+
+/// The highest id in the list, to pass as the next `since_id`; `None`
+/// with no expenses
+pub fn max_id(expenses: &[Expense]) -> Option<u64> {
+    expenses.iter().map(|e| e.id).max()
+}
+
+/// Renumber the expenses 1, 2, 3, ... in list order, closing the gaps left
+/// by deletions
+///
+/// This changes every id, so anything that refers to an expense by id
+/// (notes, the undo journal, links elsewhere) no longer points at the
+/// right expense.
+pub fn reindex_ids(expenses: &mut [Expense]) {
+    reindex_from(expenses, 1);
+}
+
+/// Renumber the expenses in list order starting at `start`, e.g. to keep
+/// merged lists in separate id ranges; invalidates ids like `reindex_ids`
+pub fn reindex_from(expenses: &mut [Expense], start: u64) {
+    for (id, expense) in (start..).zip(expenses.iter_mut()) {
+        expense.id = id;
+    }
+    if let Some(last) = expenses.last() {
+        reserve_ids_through(last.id);
+    }
+}
+
+/// Get expenses by category
+pub fn get_by_category<'a>(expenses: &'a [Expense], category: &str) -> Vec<&'a Expense> {
+    expenses.iter().filter(|e| e.category == category).collect()
+}
+
+/// Get expenses in `parent` or any of its subcategories, written as
+/// `parent:sub` (e.g. `food` and `food:grocery` for `food`)
+pub fn get_by_parent_category<'a>(expenses: &'a [Expense], parent: &str) -> Vec<&'a Expense> {
+    let prefix = format!("{}:", parent);
+    expenses
+        .iter()
+        .filter(|e| e.category == parent || e.category.starts_with(&prefix))
+        .collect()
+}
+
+/// The expense's category with its subcategory, as `category:subcategory`,
+/// or the category alone if it has none
+///
+/// Neither part is escaped, so a `:` inside either one comes through as is.
+pub fn fully_qualified_category(expense: &Expense) -> String {
+    match &expense.subcategory {
+        Some(subcategory) => format!("{}:{}", expense.category, subcategory),
+        None => expense.category.clone(),
+    }
+}
+
+/// Get expenses in any of `categories` (none, for no categories)
+pub fn get_by_categories<'a>(expenses: &'a [Expense], categories: &[&str]) -> Vec<&'a Expense> {
+    let wanted: HashSet<&str> = categories.iter().copied().collect();
+    expenses.iter().filter(|e| wanted.contains(e.category.as_str())).collect()
+}
+
+/// How much the total would drop by if the expense with `id` were removed,
+/// or `None` if there is no such expense
+pub fn impact_of_removing(expenses: &[Expense], id: u64) -> Option<f64> {
+    expenses.iter().find(|e| e.id == id).map(|e| e.amount)
+}
+
+/// Count expenses in a category
+pub fn count_by_category(expenses: &[Expense], category: &str) -> usize {
+    expenses.iter().filter(|e| e.category == category).count()
+}
+
+/// Number of different dates the expenses fall on
+pub fn count_unique_dates(expenses: &[Expense]) -> usize {
+    expenses.iter().map(|e| e.date.as_str()).collect::<HashSet<&str>>().len()
+}
+
+/// Number of different categories among the expenses
+pub fn count_unique_categories(expenses: &[Expense]) -> usize {
+    expenses.iter().map(|e| e.category.as_str()).collect::<HashSet<&str>>().len()
+}
+
+/// The category with the most expenses, whatever their amounts; a tie
+/// goes to the alphabetically first category and no expenses give `None`
+pub fn most_frequent_category(expenses: &[Expense]) -> Option<String> {
+    // max_by_key keeps the last maximum, so walk the categories backwards
+    category_counts(expenses).into_iter()
+        .rev()
+        .max_by_key(|&(_, count)| count)
+        .map(|(category, _)| category.to_string())
+}
+
+/// The category with the fewest expenses, alphabetically first on a tie;
+/// `None` with no expenses
+pub fn least_frequent_category(expenses: &[Expense]) -> Option<String> {
+    category_counts(expenses).into_iter()
+        .min_by_key(|&(_, count)| count)
+        .map(|(category, _)| category.to_string())
+}
+
+/// Total spent per category, keyed by category name
+pub fn group_totals_by_category(expenses: &[Expense]) -> BTreeMap<String, f64> {
+    let mut totals = BTreeMap::new();
+    for expense in expenses {
+        *totals.entry(expense.category.clone()).or_insert(0.0) += expense.amount;
+    }
+    totals
+}
+
+/// The category with the highest total spend, however many expenses make
+/// it up; a tie goes to the alphabetically first category and no expenses
+/// give `None`
+pub fn most_expensive_category(expenses: &[Expense]) -> Option<String> {
+    group_totals_by_category(expenses).into_iter()
+        .reduce(|best, next| if next.1 > best.1 { next } else { best })
+        .map(|(category, _)| category)
+}
+
+/// The category with the lowest total spend among those with at least one
+/// expense; a tie goes to the alphabetically first category and no
+/// expenses give `None`
+pub fn least_expensive_category(expenses: &[Expense]) -> Option<String> {
+    group_totals_by_category(expenses).into_iter()
+        .reduce(|best, next| if next.1 < best.1 { next } else { best })
+        .map(|(category, _)| category)
+}
+
+/// Label of the line `income_ratio_report` ends with
+pub const SAVINGS_RATE: &str = "savings rate";
+
+/// Each category's spending in `month` (`YYYY-MM`) as a percentage of
+/// `monthly_income`, largest first (a tie goes to the alphabetically first
+/// category), then a `SAVINGS_RATE` line with the percentage left unspent,
+/// negative in a month that spent more than the income
+///
+/// Income entries aren't spending and are left out. An income that isn't
+/// above zero gives an empty report rather than dividing by zero.
+pub fn income_ratio_report(expenses: &[Expense], monthly_income: f64, month: &str) -> Vec<(String, f64)> {
+    if !monthly_income.is_finite() || monthly_income <= 0.0 {
+        return Vec::new();
+    }
+    let in_month: Vec<Expense> = expenses.iter()
+        .filter(|e| e.kind.is_expense() && month_of(&e.date) == Some(month))
+        .cloned()
+        .collect();
+    let mut report: Vec<(String, f64)> = group_totals_by_category(&in_month).into_iter()
+        .map(|(category, total)| (category, total / monthly_income * 100.0))
+        .collect();
+    // A stable sort keeps tied categories in alphabetical order
+    report.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+    let spent = calculate_total(&in_month);
+    report.push((SAVINGS_RATE.to_string(), (1.0 - spent / monthly_income) * 100.0));
+    report
+}
+
+/// Shannon entropy, in bits, of how the expenses spread over categories
+///
+/// Counts expenses, not amounts: 0.0 when they all share one category (or
+/// there are none), 1.0 for an even split over two, log2(n) for an even
+/// split over n.
+pub fn expense_entropy(expenses: &[Expense]) -> f64 {
+    let total = expenses.len() as f64;
+    let entropy: f64 = category_counts(expenses).values()
+        .map(|&count| {
+            let p = count as f64 / total;
+            -p * p.log2()
+        })
+        .sum();
+    // A single category sums to -0.0
+    entropy + 0.0
+}
+
+fn category_counts(expenses: &[Expense]) -> BTreeMap<&str, usize> {
+    let mut counts = BTreeMap::new();
+    for expense in expenses {
+        *counts.entry(expense.category.as_str()).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Which end of the amounts `extreme_expense` looks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Extreme {
+    Max,
+    Min,
+}
+
+/// Which expenses `extreme_expense` and `extreme_expenses` look at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MinMaxOptions<'a> {
+    /// Skip zero-amount entries, e.g. ones kept as reminders
+    pub ignore_zero: bool,
+    /// Only look at this category
+    pub category: Option<&'a str>,
+}
+
+/// The most or least expensive expense among those `opts` selects
+///
+/// With a tie this is the first of the tied expenses; `extreme_expenses`
+/// returns them all. `None` if nothing is selected.
+pub fn extreme_expense<'a>(
+    expenses: &'a [Expense],
+    which: Extreme,
+    opts: MinMaxOptions,
+) -> Option<&'a Expense> {
+    extreme_expenses(expenses, which, opts).into_iter().next()
+}
+
+/// Every expense with the highest or lowest amount among those `opts`
+/// selects, in list order
+pub fn extreme_expenses<'a>(
+    expenses: &'a [Expense],
+    which: Extreme,
+    opts: MinMaxOptions,
+) -> Vec<&'a Expense> {
+    let selected = || {
+        expenses
+            .iter()
+            .filter(move |e| !(opts.ignore_zero && e.amount == 0.0))
+            .filter(move |e| opts.category.is_none_or(|c| e.category == c))
+    };
+    let pick = match which {
+        Extreme::Max => f64::max,
+        Extreme::Min => f64::min,
+    };
+    let extreme = selected().map(|e| e.amount).fold(f64::NAN, pick);
+    selected().filter(|e| e.amount == extreme).collect()
+}
+
+/// Find the most expensive expense
+///
+/// With a tie this is the first of the tied expenses; `find_all_max`
+/// returns them all. `None` with no expenses.
+pub fn find_max(expenses: &[Expense]) -> Option<&Expense> {
+    extreme_expense(expenses, Extreme::Max, MinMaxOptions::default())
+}
+
+/// Find the least expensive expense
+///
+/// With a tie this is the first of the tied expenses; `find_all_min`
+/// returns them all. `None` with no expenses.
+pub fn find_min(expenses: &[Expense]) -> Option<&Expense> {
+    extreme_expense(expenses, Extreme::Min, MinMaxOptions::default())
+}
+
+/// Find the least expensive expense that isn't a zero-amount placeholder
+pub fn find_min_nonzero(expenses: &[Expense]) -> Option<&Expense> {
+    let opts = MinMaxOptions {
+        ignore_zero: true,
+        ..MinMaxOptions::default()
+    };
+    extreme_expense(expenses, Extreme::Min, opts)
+}
+
+/// Every expense with the highest amount, in list order
+pub fn find_all_max(expenses: &[Expense]) -> Vec<&Expense> {
+    extreme_expenses(expenses, Extreme::Max, MinMaxOptions::default())
+}
+
+/// Every expense with the lowest amount, in list order
+pub fn find_all_min(expenses: &[Expense]) -> Vec<&Expense> {
+    extreme_expenses(expenses, Extreme::Min, MinMaxOptions::default())
+}
+
+/// The `n` most expensive expenses, most expensive first (see `top_n_by`)
+pub fn top_n_expenses(expenses: &[Expense], n: usize) -> Vec<&Expense> {
+    top_n_by(expenses, n, |e| e.amount)
+}
+
+/// The expense with the `n`th highest amount (1 is the most expensive),
+/// counting tied expenses separately in list order; `None` if there are
+/// fewer than `n`, or `n` is 0
+pub fn nth_largest(expenses: &[Expense], n: usize) -> Option<&Expense> {
+    top_n_by(expenses, n, |e| e.amount).get(n.checked_sub(1)?).copied()
+}
+
+/// The `n` payees paid the most in total, biggest first; a tie goes to the
+/// alphabetically first payee
+///
+/// Expenses without a payee are left out.
+pub fn top_merchants(expenses: &[Expense], n: usize) -> Vec<(String, f64)> {
+    let mut totals: BTreeMap<&str, f64> = BTreeMap::new();
+    for expense in expenses {
+        if let Some(payee) = &expense.payee {
+            *totals.entry(payee.as_str()).or_insert(0.0) += expense.amount;
+        }
+    }
+    select_top(totals, n, |(_, total)| *total)
+        .into_iter()
+        .map(|(payee, total)| (payee.to_string(), total))
+        .collect()
+}
+
+/// The `n` expenses with the highest `key`, highest first
+///
+/// Expenses with equal keys keep their list order, so the result is the
+/// same as a stable sort by descending key cut to `n`; keys are compared
+/// with `f64::total_cmp`. Only `n` expenses are held at a time, so this
+/// takes O(len log n) rather than sorting everything.
+pub fn top_n_by<F: Fn(&Expense) -> f64>(expenses: &[Expense], n: usize, key: F) -> Vec<&Expense> {
+    select_top(expenses, n, |e| key(e))
+}
+
+/// One item competing for a place in `select_top`
+struct Candidate<T> {
+    key: f64,
+    /// Position in the input; the earlier of two equal keys ranks higher
+    index: usize,
+    item: T,
+}
+
+impl<T> Candidate<T> {
+    /// Greater means worse, so a max-heap keeps the worst kept item on top
+    fn rank(&self, other: &Self) -> Ordering {
+        other.key.total_cmp(&self.key).then(self.index.cmp(&other.index))
+    }
+}
+
+impl<T> PartialEq for Candidate<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.rank(other) == Ordering::Equal
+    }
+}
+
+impl<T> Eq for Candidate<T> {}
+
+impl<T> PartialOrd for Candidate<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Candidate<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.rank(other)
+    }
+}
+
+/// The `n` items with the highest `key`, highest first, equal keys in
+/// input order
+fn select_top<T>(items: impl IntoIterator<Item = T>, n: usize, key: impl Fn(&T) -> f64) -> Vec<T> {
+    let items = items.into_iter();
+    // Nothing to hold, so nothing to allocate
+    if n == 0 || items.size_hint().1 == Some(0) {
+        return Vec::new();
+    }
+    let capacity = n.min(items.size_hint().0).saturating_add(1);
+    let mut kept: BinaryHeap<Candidate<T>> = BinaryHeap::with_capacity(capacity);
+    for (index, item) in items.enumerate() {
+        let candidate = Candidate { key: key(&item), index, item };
+        if kept.len() < n {
+            kept.push(candidate);
+        } else if kept.peek().is_some_and(|worst| candidate < *worst) {
+            kept.pop();
+            kept.push(candidate);
+        }
+    }
+    kept.into_sorted_vec().into_iter().map(|c| c.item).collect()
+}
+
+/// Sort expenses into "small" (under 10), "medium" (10 to 100, both
+/// included) and "large" (over 100) by amount alone
+///
+/// All three keys are always present, with an empty list if needed.
+pub fn categorize_by_amount_tier(expenses: &[Expense]) -> HashMap<&'static str, Vec<&Expense>> {
+    let mut tiers: HashMap<&'static str, Vec<&Expense>> =
+        ["small", "medium", "large"].into_iter().map(|tier| (tier, Vec::new())).collect();
+    for expense in expenses {
+        let tier = if expense.amount < 10.0 {
+            "small"
+        } else if expense.amount <= 100.0 {
+            "medium"
+        } else {
+            "large"
+        };
+        tiers.get_mut(tier).unwrap().push(expense);
+    }
+    tiers
+}
+
+/// Expenses that have every one of `tags` (all expenses, for no tags),
+/// ignoring case
+pub fn filter_by_tags_all<'a>(expenses: &'a [Expense], tags: &[&str]) -> Vec<&'a Expense> {
+    let wanted = lowercase_tags(tags);
+    expenses.iter().filter(|e| wanted.iter().all(|t| has_tag_ignoring_case(e, t))).collect()
+}
+
+/// Expenses that have at least one of `tags` (none, for no tags),
+/// ignoring case
+pub fn filter_by_tags_any<'a>(expenses: &'a [Expense], tags: &[&str]) -> Vec<&'a Expense> {
+    let wanted = lowercase_tags(tags);
+    expenses.iter().filter(|e| wanted.iter().any(|t| has_tag_ignoring_case(e, t))).collect()
+}
+
+/// Remove every tag from every expense, e.g. before sharing an export,
+/// returning how many expenses had tags
+pub fn untag_all(expenses: &mut [Expense]) -> usize {
+    let mut untagged = 0;
+    for expense in expenses.iter_mut().filter(|e| !e.tags.is_empty()) {
+        expense.tags.clear();
+        untagged += 1;
+    }
+    untagged
+}
+
+/// Remove `tag` (ignoring case, as the tag filters do) from every expense,
+/// returning how many expenses had it
+pub fn remove_tag_globally(expenses: &mut [Expense], tag: &str) -> usize {
+    let tag = tag.to_lowercase();
+    let mut untagged = 0;
+    for expense in expenses.iter_mut().filter(|e| has_tag_ignoring_case(e, &tag)) {
+        expense.tags.retain(|t| t.to_lowercase() != tag);
+        untagged += 1;
+    }
+    untagged
+}
+
+fn lowercase_tags(tags: &[&str]) -> Vec<String> {
+    tags.iter().map(|t| t.to_lowercase()).collect()
+}
+
+/// Whether the expense has `tag`, which must already be lowercase
+fn has_tag_ignoring_case(expense: &Expense, tag: &str) -> bool {
+    expense.tags.iter().any(|t| t.to_lowercase() == tag)
+}
+
+/// How many expenses carry each pair of tags together
+///
+/// A pair is keyed with the alphabetically first tag first, so `(a, b)`
+/// and `(b, a)` are counted as one. A tag repeated on an expense counts
+/// once.
+pub fn tag_cooccurrence(expenses: &[Expense]) -> HashMap<(String, String), usize> {
+    let mut counts = HashMap::new();
+    for expense in expenses {
+        let tags: Vec<&String> = expense.tags.iter().collect::<BTreeSet<_>>().into_iter().collect();
+        for (i, first) in tags.iter().enumerate() {
+            for second in &tags[i + 1..] {
+                *counts.entry(((*first).clone(), (*second).clone())).or_insert(0) += 1;
+            }
+        }
+    }
+    counts
+}
+
+/// How many expenses carry each tag
+///
+/// A tag repeated on an expense counts once, as in `tag_cooccurrence`.
+pub fn tag_frequency(expenses: &[Expense]) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    for expense in expenses {
+        for tag in expense.tags.iter().collect::<HashSet<_>>() {
+            *counts.entry(tag.clone()).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+/// The tag on the most expenses; a tie goes to the alphabetically first
+/// tag and no tags at all give `None`
+pub fn most_common_tag(expenses: &[Expense]) -> Option<String> {
+    tag_frequency(expenses).into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+        .map(|(tag, _)| tag)
+}
+
+/// Get total for a specific category
+pub fn total_by_category(expenses: &[Expense], category: &str) -> f64 {
+    expenses.iter()
+        .filter(|e| e.category == category)
+        .map(|e| e.amount)
+        .sum()
+}
+
+/// Get the total of every expense in any of `categories`
+pub fn total_by_categories(expenses: &[Expense], categories: &[&str]) -> f64 {
+    get_by_categories(expenses, categories).iter()
+        .map(|e| e.amount)
+        .sum()
+}
+
+/// Project the month's total: what `expenses` add up to now, plus
+/// `daily_rate` for each day left in the current month after today
+pub fn projected_end_of_month_total(expenses: &[Expense], daily_rate: f64) -> f64 {
+    projected_end_of_month_total_on(expenses, daily_rate, &SystemClock.today())
+}
+
+/// `projected_end_of_month_total` as if today were `today`
+///
+/// If `today` can't be read, no days are left to add.
+pub fn projected_end_of_month_total_on(expenses: &[Expense], daily_rate: f64, today: &str) -> f64 {
+    let days_left = match parse_date(today) {
+        Ok((year, month, day)) => days_in_month(year as i64, month as u32) - day as u32,
+        Err(_) => 0,
+    };
+    calculate_total(expenses) + daily_rate * days_left as f64
+}
+
+/// Get expenses dated from `from` to `to`, both inclusive
+pub fn filter_by_date_range<'a>(expenses: &'a [Expense], from: &str, to: &str) -> Vec<&'a Expense> {
+    expenses.iter()
+        .filter(|e| e.date.as_str() >= from && e.date.as_str() <= to)
+        .collect()
+}
+
+/// Total (0.0 if nothing matches) of the fiscal year that starts on the 1st of `fy_start_month` in
+/// `year` and runs for twelve months, e.g. 2025-04-01 to 2026-03-31
+///
+/// A start month outside 1-12 matches nothing.
+pub fn fiscal_year_total(expenses: &[Expense], fy_start_month: u8, year: u32) -> f64 {
+    if !(1..=12).contains(&fy_start_month) {
+        return 0.0;
+    }
+    let start = format!("{:04}-{:02}-01", year, fy_start_month);
+    let end = format!("{:04}-{:02}-01", year + 1, fy_start_month);
+    expenses.iter()
+        .filter(|e| e.date.as_str() >= start.as_str() && e.date.as_str() < end.as_str())
+        .map(|e| e.amount)
+        .sum()
+}
+
+/// The `n` dates with the highest total spend, biggest first, as
+/// `(date, total)`; equal totals list the most recent date first
+pub fn top_n_dates_by_spend(expenses: &[Expense], n: usize) -> Vec<(String, f64)> {
+    let mut totals: BTreeMap<&str, f64> = BTreeMap::new();
+    for expense in expenses {
+        *totals.entry(expense.date.as_str()).or_insert(0.0) += expense.amount;
+    }
+    let mut totals: Vec<(&str, f64)> = totals.into_iter().collect();
+    totals.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| b.0.cmp(a.0)));
+    totals.into_iter()
+        .take(n)
+        .map(|(date, total)| (date.to_string(), total))
+        .collect()
+}
+
+/// The date with the highest total spend and that total; with a tie, the
+/// most recent of the tied dates (as in `top_n_dates_by_spend`); `None`
+/// with no expenses
+pub fn maximum_single_day_total(expenses: &[Expense]) -> Option<(String, f64)> {
+    top_n_dates_by_spend(expenses, 1).into_iter().next()
+}
+
+/// The date with the smallest positive total spend and that total; with a
+/// tie, the most recent of the tied dates
+///
+/// Only dates with expenses are considered, so a day with no data never
+/// counts as a zero-spend day; dates whose total isn't above zero (e.g.
+/// refunds cancelling a purchase) are skipped too, and `None` means no
+/// date qualified.
+pub fn minimum_single_day_total(expenses: &[Expense]) -> Option<(String, f64)> {
+    let mut totals: BTreeMap<&str, f64> = BTreeMap::new();
+    for expense in expenses {
+        *totals.entry(expense.date.as_str()).or_insert(0.0) += expense.amount;
+    }
+    totals.into_iter()
+        .filter(|(_, total)| *total > 0.0)
+        .rev()
+        .reduce(|best, day| if day.1 < best.1 { day } else { best })
+        .map(|(date, total)| (date.to_string(), total))
+}
+
+/// The expense with the earliest date; with several on that date, the
+/// first of them in the list; `None` with no expenses
+///
+/// Dates are compared as `YYYY-MM-DD` text.
+pub fn earliest_expense(expenses: &[Expense]) -> Option<&Expense> {
+    expenses.iter().reduce(|best, e| if e.date < best.date { e } else { best })
+}
+
+/// The expense with the latest date; with several on that date, the first
+/// of them in the list; `None` with no expenses
+pub fn latest_expense(expenses: &[Expense]) -> Option<&Expense> {
+    expenses.iter().reduce(|best, e| if e.date > best.date { e } else { best })
+}
+
+/// The earliest and latest date, or `None` with no expenses
+///
+/// Reports and summaries that show the period covered use this.
+pub fn date_span(expenses: &[Expense]) -> Option<(String, String)> {
+    let first = earliest_expense(expenses)?;
+    let last = latest_expense(expenses)?;
+    Some((first.date.clone(), last.date.clone()))
+}
+
+/// The net present value of the expenses as cash flows, discounted at
+/// `discount_rate` a year from the earliest date:
+/// `sum(amount / (1 + discount_rate)^t)` with `t` in years of 365 days
+///
+/// 0.0 for no expenses. A rate of -1.0 or below, or a date that can't be
+/// read, is an error.
+pub fn net_present_value(expenses: &[Expense], discount_rate: f64) -> Result<f64, ExpenseError> {
+    if discount_rate.is_nan() || discount_rate <= -1.0 {
+        return Err(ExpenseError::InvalidAmount(format!("discount rate `{}` is -100% or below", discount_rate)));
+    }
+    let Some((start, _)) = date_span(expenses) else {
+        return Ok(0.0);
+    };
+    let mut npv = 0.0;
+    for expense in expenses {
+        let years = days_between_dates(&start, &expense.date)? as f64 / 365.0;
+        npv += expense.amount / (1.0 + discount_rate).powf(years);
+    }
+    Ok(npv)
+}
+
+/// The longest stretch without expenses, as the dates on either side and
+/// the number of days between them
+///
+/// The earliest gap wins a tie. Expenses whose date can't be read are left
+/// out, and fewer than two distinct dates give `None`.
+pub fn longest_gap_between_expenses(expenses: &[Expense]) -> Option<(String, String, u32)> {
+    let dates: BTreeSet<&str> = expenses.iter()
+        .map(|e| e.date.as_str())
+        .filter(|d| parse_date(d).is_ok())
+        .collect();
+    let dates: Vec<&str> = dates.into_iter().collect();
+    let mut longest: Option<(&str, &str, u32)> = None;
+    for pair in dates.windows(2) {
+        let gap = days_between_dates(pair[0], pair[1]).ok()? as u32;
+        if longest.is_none_or(|(_, _, best)| gap > best) {
+            longest = Some((pair[0], pair[1], gap));
+        }
+    }
+    longest.map(|(from, to, gap)| (from.to_string(), to.to_string(), gap))
+}
+
+/// Total per day, keyed by the `YYYY-MM-DD` date
+///
+/// Expenses whose date can't be read are left out.
+pub fn daily_totals(expenses: &[Expense]) -> BTreeMap<String, f64> {
+    let mut totals = BTreeMap::new();
+    for expense in expenses {
+        if parse_date(&expense.date).is_ok() {
+            *totals.entry(expense.date.clone()).or_insert(0.0) += expense.amount;
+        }
+    }
+    totals
+}
+
+/// Total per ISO week (Monday to Sunday), keyed by the week's Monday
+///
+/// Expenses whose date can't be read are left out.
+pub fn weekly_totals(expenses: &[Expense]) -> BTreeMap<String, f64> {
+    let mut totals = BTreeMap::new();
+    for expense in expenses {
+        if let Some(monday) = week_start(&expense.date) {
+            *totals.entry(monday).or_insert(0.0) += expense.amount;
+        }
+    }
+    totals
+}
+
+/// Group expenses by ISO week number (1 to 53)
+///
+/// The key ignores the year, so week 5 of 2025 and week 5 of 2026 land
+/// together; use `group_by_year_week` when the data spans years.
+/// Expenses whose date can't be read are left out.
+pub fn group_by_week(expenses: &[Expense]) -> HashMap<u8, Vec<&Expense>> {
+    let mut groups: HashMap<u8, Vec<&Expense>> = HashMap::new();
+    for expense in expenses {
+        if let Ok((_, week)) = iso_year_week(&expense.date) {
+            groups.entry(week).or_default().push(expense);
+        }
+    }
+    groups
+}
+
+/// Group expenses by ISO `(year, week)`
+///
+/// Expenses whose date can't be read are left out.
+pub fn group_by_year_week(expenses: &[Expense]) -> HashMap<(u32, u8), Vec<&Expense>> {
+    let mut groups: HashMap<(u32, u8), Vec<&Expense>> = HashMap::new();
+    for expense in expenses {
+        if let Ok(key) = iso_year_week(&expense.date) {
+            groups.entry(key).or_default().push(expense);
+        }
+    }
+    groups
+}
+
+/// Total per ISO week number, with the same year caveat as `group_by_week`
+pub fn total_by_week(expenses: &[Expense]) -> HashMap<u8, f64> {
+    group_by_week(expenses)
+        .into_iter()
+        .map(|(week, group)| (week, group.iter().map(|e| e.amount).sum()))
+        .collect()
+}
+
+/// Group expenses by `(year, quarter)`, with quarters numbered 1 to 4
+///
+/// Expenses whose date can't be read are left out.
+pub fn group_by_quarter(expenses: &[Expense]) -> HashMap<(u32, u8), Vec<&Expense>> {
+    let mut groups: HashMap<(u32, u8), Vec<&Expense>> = HashMap::new();
+    for expense in expenses {
+        let date = &expense.date;
+        if let (Ok((year, _, _)), Ok(quarter)) = (parse_date(date), quarter_of(date)) {
+            groups.entry((year, quarter)).or_default().push(expense);
+        }
+    }
+    groups
+}
+
+/// Total per `(year, quarter)`
+pub fn total_by_quarter(expenses: &[Expense]) -> HashMap<(u32, u8), f64> {
+    group_by_quarter(expenses)
+        .into_iter()
+        .map(|(key, group)| (key, group.iter().map(|e| e.amount).sum()))
+        .collect()
+}
+
+/// Total spent on each day of the month across all months: index 0 is
+/// every 1st, index 30 every 31st
+///
+/// Expenses whose date can't be read are left out.
+pub fn totals_by_day_of_month(expenses: &[Expense]) -> [f64; 31] {
+    let mut totals = [0.0; 31];
+    for expense in expenses {
+        if let Ok((_, _, day)) = parse_date(&expense.date) {
+            totals[day as usize - 1] += expense.amount;
+        }
+    }
+    totals
+}
+
+/// The index into `totals_by_day_of_month` with the biggest total, and
+/// that total; the earliest day wins a tie and `None` means nothing was spent
+pub fn peak_spending_day_of_month(expenses: &[Expense]) -> Option<(usize, f64)> {
+    let totals = totals_by_day_of_month(expenses);
+    let mut peak: Option<(usize, f64)> = None;
+    for (index, &total) in totals.iter().enumerate() {
+        if total > peak.map_or(0.0, |(_, best)| best) {
+            peak = Some((index, total));
+        }
+    }
+    peak
+}
+
+/// Total per month, keyed by `YYYY-MM`
+///
+/// Expenses whose date can't be read are left out.
+pub fn monthly_totals(expenses: &[Expense]) -> BTreeMap<String, f64> {
+    let mut totals = BTreeMap::new();
+    for expense in expenses {
+        if let Some(month) = month_of(&expense.date) {
+            *totals.entry(month.to_string()).or_insert(0.0) += expense.amount;
+        }
+    }
+    totals
+}
+
+/// Total for every day of one month, from the 1st to the last day, with
+/// 0.0 for days without expenses
+///
+/// A month outside 1-12 has no days, so the list is empty.
+pub fn daily_totals_for_month(expenses: &[Expense], year: u32, month: u8) -> Vec<(u8, f64)> {
+    if !(1..=12).contains(&month) {
+        return Vec::new();
+    }
+    let mut totals: Vec<(u8, f64)> = (1..=days_in_month(year as i64, month as u32) as u8)
+        .map(|day| (day, 0.0))
+        .collect();
+    for expense in expenses {
+        if let Ok((y, m, d)) = parse_date(&expense.date)
+            && y == year
+            && m == month
+        {
+            totals[d as usize - 1].1 += expense.amount;
+        }
+    }
+    totals
+}
+
+/// Total per month for one category, keyed by `YYYY-MM`
+pub fn monthly_totals_by_category(expenses: &[Expense], category: &str) -> BTreeMap<String, f64> {
+    let mut totals = BTreeMap::new();
+    for expense in expenses.iter().filter(|e| e.category == category) {
+        if let Some(month) = month_of(&expense.date) {
+            *totals.entry(month.to_string()).or_insert(0.0) += expense.amount;
+        }
+    }
+    totals
+}
+
+/// Total per payee per month (`YYYY-MM`)
+///
+/// Expenses without a payee, or whose date can't be read, are left out.
+pub fn payee_month_pivot(expenses: &[Expense]) -> HashMap<String, BTreeMap<String, f64>> {
+    let mut pivot: HashMap<String, BTreeMap<String, f64>> = HashMap::new();
+    for expense in expenses {
+        if let (Some(payee), Some(month)) = (&expense.payee, month_of(&expense.date)) {
+            *pivot.entry(payee.clone()).or_default()
+                .entry(month.to_string())
+                .or_insert(0.0) += expense.amount;
+        }
+    }
+    pivot
+}
+
+/// Largest spread between months that still counts as the same charge
+const SUBSCRIPTION_TOLERANCE: f64 = 0.05;
+
+/// Payees that look like subscriptions, with their average monthly charge,
+/// sorted by payee
+///
+/// A payee qualifies when it was paid in at least three consecutive months
+/// (December to January counts) and the largest of those monthly totals is
+/// within 5% of the smallest. The average is taken over the latest such
+/// run of months.
+pub fn probable_subscriptions(expenses: &[Expense]) -> Vec<(String, f64)> {
+    let mut found: Vec<(String, f64)> = payee_month_pivot(expenses)
+        .into_iter()
+        .filter_map(|(payee, months)| {
+            let mut run: Vec<f64> = Vec::new();
+            let mut previous: Option<&String> = None;
+            let mut average = None;
+            for (month, &total) in &months {
+                if previous.and_then(|p| next_month(p)).as_ref() != Some(month) {
+                    run.clear();
+                }
+                previous = Some(month);
+                run.push(total);
+                // Drop the oldest months until the rest agree
+                while !within_tolerance(&run) {
+                    run.remove(0);
+                }
+                if run.len() >= 3 {
+                    average = Some(run.iter().sum::<f64>() / run.len() as f64);
+                }
+            }
+            average.map(|average| (payee, average))
+        })
+        .collect();
+    found.sort_by(|a, b| a.0.cmp(&b.0));
+    found
+}
+
+/// Whether every amount is within `SUBSCRIPTION_TOLERANCE` of the smallest
+fn within_tolerance(amounts: &[f64]) -> bool {
+    let min = amounts.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = amounts.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    amounts.len() < 2 || max <= min * (1.0 + SUBSCRIPTION_TOLERANCE)
+}
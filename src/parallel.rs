@@ -0,0 +1,62 @@
+// This module has parallel versions of the heavier operations, for expense
+// lists with millions of entries (enabled by the `rayon` feature)
+
+use rayon::prelude::*;
+
+use crate::expense::Expense;
+
+/// Calculate total of all expenses in parallel
+///
+/// Same result as `calculate_total`, except that adding in a different
+/// order can change the last bits of totals whose amounts are not exactly
+/// representable (e.g. 0.1).
+pub fn parallel_total(expenses: &[Expense]) -> f64 {
+    expenses.par_iter().map(|e| e.amount).sum()
+}
+
+/// Get total for a specific category in parallel
+pub fn parallel_total_by_category(expenses: &[Expense], category: &str) -> f64 {
+    expenses
+        .par_iter()
+        .filter(|e| e.category == category)
+        .map(|e| e.amount)
+        .sum()
+}
+
+/// Get expenses by category in parallel, keeping their original order
+pub fn parallel_filter_by_category<'a>(
+    expenses: &'a [Expense],
+    category: &str,
+) -> Vec<&'a Expense> {
+    expenses
+        .par_iter()
+        .filter(|e| e.category == category)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operations::{calculate_total, get_by_category, total_by_category};
+
+    fn sample() -> Vec<Expense> {
+        let categories = ["food", "transport", "rent"];
+        (0..10_000)
+            .map(|i| Expense::new((i % 40) as f64 * 0.25, categories[i % 3], "2026-01-01"))
+            .collect()
+    }
+
+    #[test]
+    fn test_parallel_matches_sequential() {
+        let expenses = sample();
+        assert_eq!(parallel_total(&expenses), calculate_total(&expenses));
+        assert_eq!(
+            parallel_total_by_category(&expenses, "food"),
+            total_by_category(&expenses, "food")
+        );
+        assert_eq!(
+            parallel_filter_by_category(&expenses, "rent"),
+            get_by_category(&expenses, "rent")
+        );
+    }
+}
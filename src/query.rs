@@ -0,0 +1,310 @@
+// This module describes a search over expenses (filters plus an order) and
+// builds one from string key/value pairs, as found in a URL query string
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::dates::validate_date_format;
+use crate::error::ExpenseError;
+use crate::expense::Expense;
+
+/// The order query results come back in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    /// Oldest first (`date`)
+    Date,
+    /// Newest first (`-date`)
+    DateDesc,
+    /// Cheapest first (`amount`)
+    Amount,
+    /// Most expensive first (`-amount`)
+    AmountDesc,
+    /// Alphabetical by category (`category`)
+    Category,
+}
+
+impl SortKey {
+    /// Parse a sort parameter such as `-amount`
+    pub fn parse(value: &str) -> Option<SortKey> {
+        match value {
+            "date" => Some(SortKey::Date),
+            "-date" => Some(SortKey::DateDesc),
+            "amount" => Some(SortKey::Amount),
+            "-amount" => Some(SortKey::AmountDesc),
+            "category" => Some(SortKey::Category),
+            _ => None,
+        }
+    }
+
+    fn compare(self, a: &Expense, b: &Expense) -> Ordering {
+        let by_amount = |a: &Expense, b: &Expense| a.amount.total_cmp(&b.amount);
+        match self {
+            SortKey::Date => a.date.cmp(&b.date),
+            SortKey::DateDesc => b.date.cmp(&a.date),
+            SortKey::Amount => by_amount(a, b),
+            SortKey::AmountDesc => by_amount(b, a),
+            SortKey::Category => a.category.cmp(&b.category),
+        }
+    }
+}
+
+/// Why a set of query parameters was rejected
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryParseError {
+    /// A parameter this query doesn't understand
+    UnknownKey(String),
+    /// A known parameter with a value that couldn't be used
+    BadValue {
+        key: String,
+        value: String,
+        reason: String,
+    },
+}
+
+impl fmt::Display for QueryParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryParseError::UnknownKey(key) => write!(f, "unknown query parameter `{}`", key),
+            QueryParseError::BadValue { key, value, reason } => {
+                write!(f, "bad value `{}` for `{}`: {}", value, key, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for QueryParseError {}
+
+impl From<QueryParseError> for ExpenseError {
+    fn from(err: QueryParseError) -> Self {
+        ExpenseError::ParseError(err.to_string())
+    }
+}
+
+/// Filters and an order to apply to a list of expenses
+///
+/// Every filter that is set must match; an empty query matches everything
+/// and keeps the original order. Date bounds are inclusive.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ExpenseQuery {
+    pub category: Option<String>,
+    pub date: Option<String>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub tag: Option<String>,
+    pub sort: Option<SortKey>,
+}
+
+impl ExpenseQuery {
+    /// A query that matches every expense
+    pub fn new() -> ExpenseQuery {
+        ExpenseQuery::default()
+    }
+
+    /// Build a query from string parameters
+    ///
+    /// Understands `category`, `date`, `from`, `to` (`YYYY-MM-DD`), `min`,
+    /// `max` (amounts), `tag` and `sort` (`date`, `-date`, `amount`,
+    /// `-amount` or `category`). Any other key is an error, so a typo
+    /// can't silently widen the results.
+    pub fn from_params(params: &HashMap<String, String>) -> Result<ExpenseQuery, QueryParseError> {
+        // Sorted, so the first bad key reported doesn't depend on hashing
+        let mut keys: Vec<&String> = params.keys().collect();
+        keys.sort();
+
+        let mut query = ExpenseQuery::new();
+        for key in keys {
+            let value = &params[key];
+            let bad = |reason: &str| QueryParseError::BadValue {
+                key: key.clone(),
+                value: value.clone(),
+                reason: reason.to_string(),
+            };
+            let date = || {
+                validate_date_format(value)
+                    .map(|()| value.clone())
+                    .map_err(|_| bad("expected a YYYY-MM-DD date"))
+            };
+            let amount = || {
+                value
+                    .trim()
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|a| a.is_finite())
+                    .ok_or_else(|| bad("expected a number"))
+            };
+            match key.as_str() {
+                "category" => query.category = Some(value.clone()),
+                "date" => query.date = Some(date()?),
+                "from" => query.from = Some(date()?),
+                "to" => query.to = Some(date()?),
+                "min" => query.min = Some(amount()?),
+                "max" => query.max = Some(amount()?),
+                "tag" => query.tag = Some(value.clone()),
+                "sort" => {
+                    query.sort =
+                        Some(SortKey::parse(value).ok_or_else(|| {
+                            bad("expected date, -date, amount, -amount or category")
+                        })?)
+                }
+                _ => return Err(QueryParseError::UnknownKey(key.clone())),
+            }
+        }
+        Ok(query)
+    }
+
+    /// Only expenses in `category`
+    pub fn category(mut self, category: &str) -> ExpenseQuery {
+        self.category = Some(category.to_string());
+        self
+    }
+
+    /// Only expenses on `date`
+    pub fn date(mut self, date: &str) -> ExpenseQuery {
+        self.date = Some(date.to_string());
+        self
+    }
+
+    /// Only expenses on or after `date`
+    pub fn from(mut self, date: &str) -> ExpenseQuery {
+        self.from = Some(date.to_string());
+        self
+    }
+
+    /// Only expenses on or before `date`
+    pub fn to(mut self, date: &str) -> ExpenseQuery {
+        self.to = Some(date.to_string());
+        self
+    }
+
+    /// Only expenses of at least `amount`
+    pub fn min(mut self, amount: f64) -> ExpenseQuery {
+        self.min = Some(amount);
+        self
+    }
+
+    /// Only expenses of at most `amount`
+    pub fn max(mut self, amount: f64) -> ExpenseQuery {
+        self.max = Some(amount);
+        self
+    }
+
+    /// Only expenses tagged `tag`
+    pub fn tag(mut self, tag: &str) -> ExpenseQuery {
+        self.tag = Some(tag.to_string());
+        self
+    }
+
+    /// Return the results in this order
+    pub fn sort(mut self, key: SortKey) -> ExpenseQuery {
+        self.sort = Some(key);
+        self
+    }
+
+    /// Whether one expense passes every filter
+    pub fn matches(&self, expense: &Expense) -> bool {
+        let date = expense.date.as_str();
+        self.category
+            .as_ref()
+            .is_none_or(|c| &expense.category == c)
+            && self.date.as_deref().is_none_or(|d| date == d)
+            && self.from.as_deref().is_none_or(|from| date >= from)
+            && self.to.as_deref().is_none_or(|to| date <= to)
+            && self.min.is_none_or(|min| expense.amount >= min)
+            && self.max.is_none_or(|max| expense.amount <= max)
+            && self.tag.as_deref().is_none_or(|tag| expense.has_tag(tag))
+    }
+
+    /// The matching expenses, in the requested order
+    pub fn run<'a>(&self, expenses: &'a [Expense]) -> Vec<&'a Expense> {
+        let mut results: Vec<&Expense> = expenses.iter().filter(|e| self.matches(e)).collect();
+        if let Some(key) = self.sort {
+            results.sort_by(|a, b| key.compare(a, b));
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(query: &str) -> HashMap<String, String> {
+        query
+            .split('&')
+            .map(|pair| {
+                let (key, value) = pair.split_once('=').unwrap();
+                (key.to_string(), value.to_string())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_from_params_matches_built_query() {
+        let query = ExpenseQuery::from_params(&params(
+            "category=food&from=2026-01-01&to=2026-01-31&min=10&max=99.5&tag=work&sort=-amount",
+        ))
+        .unwrap();
+        let built = ExpenseQuery::new()
+            .category("food")
+            .from("2026-01-01")
+            .to("2026-01-31")
+            .min(10.0)
+            .max(99.5)
+            .tag("work")
+            .sort(SortKey::AmountDesc);
+        assert_eq!(query, built);
+
+        let query = ExpenseQuery::from_params(&params("date=2026-01-08")).unwrap();
+        assert_eq!(query, ExpenseQuery::new().date("2026-01-08"));
+        assert_eq!(
+            ExpenseQuery::from_params(&HashMap::new()).unwrap(),
+            ExpenseQuery::new()
+        );
+    }
+
+    #[test]
+    fn test_from_params_names_bad_key_or_value() {
+        assert_eq!(
+            ExpenseQuery::from_params(&params("category=food&catgory=rent")),
+            Err(QueryParseError::UnknownKey("catgory".to_string()))
+        );
+        assert_eq!(
+            ExpenseQuery::from_params(&params("min=ten")),
+            Err(QueryParseError::BadValue {
+                key: "min".to_string(),
+                value: "ten".to_string(),
+                reason: "expected a number".to_string(),
+            })
+        );
+        let err = ExpenseQuery::from_params(&params("from=2026-02-30")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "bad value `2026-02-30` for `from`: expected a YYYY-MM-DD date"
+        );
+        assert!(ExpenseQuery::from_params(&params("sort=price")).is_err());
+        assert!(ExpenseQuery::from_params(&params("max=inf")).is_err());
+    }
+
+    #[test]
+    fn test_run_filters_and_sorts() {
+        let expenses = vec![
+            Expense::new(45.5, "food", "2026-01-08").with_tag("work"),
+            Expense::new(20.0, "transport", "2026-01-08").with_tag("work"),
+            Expense::new(12.0, "food", "2026-01-10").with_tag("work"),
+            Expense::new(80.0, "food", "2026-01-12"),
+        ];
+        let query = ExpenseQuery::new()
+            .category("food")
+            .tag("work")
+            .sort(SortKey::Amount);
+        let amounts: Vec<f64> = query.run(&expenses).iter().map(|e| e.amount).collect();
+        assert_eq!(amounts, vec![12.0, 45.5]);
+
+        let query = ExpenseQuery::new().from("2026-01-09").max(50.0);
+        assert_eq!(query.run(&expenses).len(), 1);
+        assert_eq!(ExpenseQuery::new().run(&expenses).len(), 4);
+    }
+}
@@ -0,0 +1,176 @@
+// This module describes expenses that repeat on a schedule (rent, a
+// subscription) and works out what they will cost
+
+use crate::dates::{add_months, next_date};
+use crate::expense::Expense;
+
+/// How often a recurring expense comes around
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    /// Same day every month, or the month's last day if it is shorter
+    Monthly,
+    Yearly,
+}
+
+impl Frequency {
+    /// The date of occurrence number `n` (0 is `start` itself), or `None`
+    /// past year 9999 or for a bad `start`
+    fn nth(self, start: &str, n: u32) -> Option<String> {
+        match self {
+            Frequency::Daily => next_date(start, n).ok(),
+            Frequency::Weekly => next_date(start, n.checked_mul(7)?).ok(),
+            // Counted from the start each time, so a 31st doesn't drift to
+            // the 28th after February
+            Frequency::Monthly => add_months(start, n).ok(),
+            Frequency::Yearly => add_months(start, n.checked_mul(12)?).ok(),
+        }
+    }
+}
+
+/// An expense that repeats, first falling on its template's date
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecurringExpense {
+    /// Amount, category and first date of every occurrence
+    pub template: Expense,
+    pub frequency: Frequency,
+}
+
+impl RecurringExpense {
+    /// Creates a recurring expense starting on `template.date`
+    pub fn new(template: Expense, frequency: Frequency) -> RecurringExpense {
+        RecurringExpense {
+            template,
+            frequency,
+        }
+    }
+
+    /// The dates it falls on from `from` to `to`, both included
+    ///
+    /// Empty if the template's date can't be read.
+    pub fn occurrences(&self, from: &str, to: &str) -> Vec<String> {
+        let mut dates = Vec::new();
+        for n in 0.. {
+            match self.frequency.nth(&self.template.date, n) {
+                Some(date) if date.as_str() <= to => {
+                    if date.as_str() >= from {
+                        dates.push(date);
+                    }
+                }
+                _ => break,
+            }
+        }
+        dates
+    }
+
+    /// A copy of the template for every occurrence from `from` to `to`
+    ///
+    /// The copies keep the template's id; give them fresh ones (e.g. with
+    /// `ExpenseTracker::add_expense`) before storing them.
+    pub fn instances(&self, from: &str, to: &str) -> Vec<Expense> {
+        self.occurrences(from, to)
+            .into_iter()
+            .map(|date| Expense {
+                date,
+                ..self.template.clone()
+            })
+            .collect()
+    }
+}
+
+/// What `num_periods` occurrences of `template` cost together
+///
+/// Every occurrence has the template's amount, so this needs no dates;
+/// `frequency` only says how long the periods are.
+pub fn recurring_expense_projection(
+    template: &Expense,
+    _frequency: Frequency,
+    num_periods: u32,
+) -> f64 {
+    template.amount * num_periods as f64
+}
+
+/// What every occurrence of every recurring expense from `from` to `to`
+/// (both included) adds up to
+pub fn total_recurring_expense_list(expenses: &[RecurringExpense], from: &str, to: &str) -> f64 {
+    expenses
+        .iter()
+        .flat_map(|recurring| recurring.instances(from, to))
+        .map(|e| e.amount)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_projection_of_monthly_rent() {
+        let rent = Expense::with_id(1, 1000.0, "rent", "2026-01-01");
+        assert_eq!(
+            recurring_expense_projection(&rent, Frequency::Monthly, 12),
+            12000.0
+        );
+        assert_eq!(
+            recurring_expense_projection(&rent, Frequency::Weekly, 0),
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_occurrences_keep_their_day() {
+        let gym = RecurringExpense::new(
+            Expense::with_id(1, 30.0, "health", "2026-01-31"),
+            Frequency::Monthly,
+        );
+        assert_eq!(
+            gym.occurrences("2026-01-01", "2026-04-30"),
+            vec!["2026-01-31", "2026-02-28", "2026-03-31", "2026-04-30"]
+        );
+        // Only those inside the range
+        assert_eq!(
+            gym.occurrences("2026-02-01", "2026-03-30"),
+            vec!["2026-02-28"]
+        );
+
+        let coffee = RecurringExpense::new(
+            Expense::with_id(2, 4.5, "coffee", "2026-01-05"),
+            Frequency::Weekly,
+        );
+        let instances = coffee.instances("2026-01-01", "2026-01-31");
+        let dates: Vec<&str> = instances.iter().map(|e| e.date.as_str()).collect();
+        assert_eq!(
+            dates,
+            vec!["2026-01-05", "2026-01-12", "2026-01-19", "2026-01-26"]
+        );
+        assert!(instances.iter().all(|e| e.amount == 4.5 && e.id == 2));
+    }
+
+    #[test]
+    fn test_total_recurring_expense_list() {
+        let recurring = vec![
+            RecurringExpense::new(
+                Expense::with_id(1, 1000.0, "rent", "2026-01-01"),
+                Frequency::Monthly,
+            ),
+            RecurringExpense::new(
+                Expense::with_id(2, 120.0, "insurance", "2025-06-15"),
+                Frequency::Yearly,
+            ),
+            RecurringExpense::new(
+                Expense::with_id(3, 2.5, "transport", "2026-12-30"),
+                Frequency::Daily,
+            ),
+        ];
+        // 12 rents, one insurance payment, two bus tickets
+        assert_eq!(
+            total_recurring_expense_list(&recurring, "2026-01-01", "2026-12-31"),
+            12000.0 + 120.0 + 5.0
+        );
+        assert_eq!(
+            total_recurring_expense_list(&recurring, "2027-01-01", "2026-12-31"),
+            0.0
+        );
+    }
+}
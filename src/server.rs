@@ -0,0 +1,436 @@
+// This module serves the tracker as a small HTTP JSON API (enabled by the
+// `server` feature)
+//
+// The HTTP layer is hand-written over `std::net` and only understands what
+// the API needs: one request per connection, bodies sized by
+// `Content-Length`. Requests are parsed into a `Request` and answered by
+// `handle`, so handlers can be tested without a socket.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::dates::validate_date_format;
+use crate::error::ExpenseError;
+use crate::expense::{Expense, validate_amount};
+use crate::operations::*;
+use crate::query::ExpenseQuery;
+use crate::tracker::SharedTracker;
+
+/// Largest request body the server accepts, in bytes
+const MAX_BODY: usize = 1 << 20;
+
+/// An HTTP request, reduced to what the handlers look at
+#[derive(Debug, Clone, PartialEq)]
+pub struct Request {
+    pub method: String,
+    /// Path without the query string, e.g. `/expenses/3`
+    pub path: String,
+    /// Decoded query parameters
+    pub query: HashMap<String, String>,
+    pub body: String,
+}
+
+impl Request {
+    /// Build a request from a method and a target such as `/expenses?category=food`
+    pub fn new(method: &str, target: &str, body: &str) -> Request {
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let query = query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                (percent_decode(key), percent_decode(value))
+            })
+            .collect();
+        Request {
+            method: method.to_ascii_uppercase(),
+            path: path.to_string(),
+            query,
+            body: body.to_string(),
+        }
+    }
+
+    /// Read one request from a connection
+    pub fn read_from(reader: &mut impl BufRead) -> Result<Request, ExpenseError> {
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        let mut parts = request_line.split_whitespace();
+        let (method, target) = match (parts.next(), parts.next()) {
+            (Some(method), Some(target)) => (method, target),
+            _ => return Err(bad_request("malformed request line")),
+        };
+
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+                break;
+            }
+            let Some((name, value)) = line.split_once(':') else {
+                continue;
+            };
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                length = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| bad_request("bad Content-Length"))?;
+            }
+        }
+        if length > MAX_BODY {
+            return Err(bad_request("request body too large"));
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body)?;
+        let body = String::from_utf8(body).map_err(|_| bad_request("body is not UTF-8"))?;
+        Ok(Request::new(method, target, &body))
+    }
+}
+
+/// A JSON response
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub body: serde_json::Value,
+}
+
+impl Response {
+    fn ok(body: serde_json::Value) -> Response {
+        Response { status: 200, body }
+    }
+
+    fn error(status: u16, message: &str) -> Response {
+        Response {
+            status,
+            body: json!({ "error": message }),
+        }
+    }
+
+    /// Write the response as HTTP/1.1
+    pub fn write_to(&self, out: &mut impl Write) -> std::io::Result<()> {
+        let body = self.body.to_string();
+        write!(
+            out,
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.status,
+            reason_phrase(self.status),
+            body.len(),
+            body
+        )?;
+        out.flush()
+    }
+}
+
+impl From<ExpenseError> for Response {
+    fn from(err: ExpenseError) -> Response {
+        let status = match err {
+            ExpenseError::InvalidAmount(_)
+            | ExpenseError::InvalidDate(_)
+            | ExpenseError::ParseError(_) => 400,
+            ExpenseError::NotFound(_) => 404,
+            ExpenseError::PeriodClosed(_) => 409,
+            ExpenseError::IoError(_) | ExpenseError::HttpError(_) => 500,
+        };
+        Response::error(status, &err.to_string())
+    }
+}
+
+/// What the handlers work on
+///
+/// Saving is left to the tracker's hooks (see `SharedTracker::autosave`).
+#[derive(Debug, Clone)]
+pub struct ServerState {
+    pub tracker: SharedTracker,
+}
+
+/// Body of `POST /expenses`
+#[derive(Deserialize)]
+struct NewExpense {
+    amount: f64,
+    category: String,
+    date: String,
+    #[serde(default)]
+    note: Option<String>,
+    #[serde(default)]
+    payee: Option<String>,
+}
+
+/// Answer one request
+///
+/// - `GET /expenses` lists expenses, filtered and sorted by the query
+///   parameters `ExpenseQuery::from_params` understands
+/// - `POST /expenses` adds the expense in the JSON body (201)
+/// - `DELETE /expenses/:id` removes an expense (404 if there is none)
+/// - `GET /report` returns the total spent, the totals per category and
+///   the most expensive expense, with the total income given apart
+pub fn handle(request: &Request, state: &ServerState) -> Response {
+    let segments: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+    let result = match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["expenses"]) => list_expenses(request, state),
+        ("POST", ["expenses"]) => add_expense(request, state),
+        ("DELETE", ["expenses", id]) => delete_expense(id, state),
+        ("GET", ["report"]) => Ok(report(state)),
+        (_, ["expenses"]) | (_, ["expenses", _]) | (_, ["report"]) => {
+            Ok(Response::error(405, "method not allowed"))
+        }
+        _ => Ok(Response::error(404, "no such endpoint")),
+    };
+    result.unwrap_or_else(Response::from)
+}
+
+fn list_expenses(request: &Request, state: &ServerState) -> Result<Response, ExpenseError> {
+    let query = ExpenseQuery::from_params(&request.query)?;
+    let expenses = state.tracker.snapshot();
+    Ok(Response::ok(json!(query.run(&expenses))))
+}
+
+fn add_expense(request: &Request, state: &ServerState) -> Result<Response, ExpenseError> {
+    let new: NewExpense = serde_json::from_str(&request.body)
+        .map_err(|e| ExpenseError::ParseError(format!("bad expense JSON: {}", e)))?;
+    validate_amount(new.amount)?;
+    if new.category.trim().is_empty() {
+        return Err(ExpenseError::ParseError("category is empty".to_string()));
+    }
+    validate_date_format(&new.date)?;
+
+    let mut expense = Expense::with_id(0, new.amount, new.category.trim(), &new.date);
+    expense.note = new.note;
+    expense.payee = new.payee;
+    let expense = state.tracker.add_expense(expense, false)?;
+    Ok(Response {
+        status: 201,
+        body: json!(expense),
+    })
+}
+
+fn delete_expense(id: &str, state: &ServerState) -> Result<Response, ExpenseError> {
+    let id: u64 = id
+        .parse()
+        .map_err(|_| ExpenseError::ParseError(format!("bad id `{}`", id)))?;
+    let removed = state.tracker.remove(id, false)?;
+    Ok(Response::ok(json!(removed)))
+}
+
+/// Spending totals, with income left out of them and given on its own
+fn report(state: &ServerState) -> Response {
+    let (expenses, income): (Vec<Expense>, Vec<Expense>) = state
+        .tracker
+        .snapshot()
+        .into_iter()
+        .partition(|e| e.kind.is_expense());
+    let mut by_category: BTreeMap<&str, f64> = BTreeMap::new();
+    for expense in &expenses {
+        *by_category.entry(expense.category.as_str()).or_insert(0.0) += expense.amount;
+    }
+    Response::ok(json!({
+        "total": calculate_total(&expenses),
+        "income": calculate_total(&income),
+        "count": expenses.len(),
+        "by_category": by_category,
+        "most_expensive": find_max(&expenses),
+    }))
+}
+
+/// Answer requests on `listener` until it fails, one thread per connection
+pub fn serve(listener: TcpListener, state: ServerState) -> Result<(), ExpenseError> {
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let state = state.clone();
+        std::thread::spawn(move || {
+            // A client that hangs up early only loses its own response
+            let _ = handle_connection(stream, &state);
+        });
+    }
+    Ok(())
+}
+
+fn handle_connection(stream: TcpStream, state: &ServerState) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    let response = match Request::read_from(&mut reader) {
+        Ok(request) => handle(&request, state),
+        Err(err) => Response::from(err),
+    };
+    response.write_to(reader.get_mut())
+}
+
+fn bad_request(message: &str) -> ExpenseError {
+    ExpenseError::ParseError(message.to_string())
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        _ => "Internal Server Error",
+    }
+}
+
+/// Decode `%XX` escapes and `+` (space) in a query string part
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+            (b'+', _) => out.push(b' '),
+            (byte, _) => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn state() -> ServerState {
+        let tracker = SharedTracker::new();
+        tracker.add(45.5, "food", "2026-01-08").unwrap();
+        tracker.add(20.0, "transport", "2026-01-09").unwrap();
+        tracker.add(30.0, "food", "2026-02-01").unwrap();
+        ServerState { tracker }
+    }
+
+    #[test]
+    fn test_list_filters_by_category_and_dates() {
+        let state = state();
+        let request = Request::new("GET", "/expenses?category=food&to=2026-01-31", "");
+        let response = handle(&request, &state);
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body.as_array().unwrap().len(), 1);
+        assert_eq!(response.body[0]["amount"], 45.5);
+
+        let bad = Request::new("GET", "/expenses?from=2026-02-30", "");
+        assert_eq!(handle(&bad, &state).status, 400);
+        let unknown = Request::new("GET", "/expenses?categry=food", "");
+        assert_eq!(handle(&unknown, &state).status, 400);
+    }
+
+    #[test]
+    fn test_list_sorts_by_query() {
+        let state = state();
+        let request = Request::new("GET", "/expenses?sort=-amount&min=25", "");
+        let response = handle(&request, &state);
+        let amounts: Vec<f64> = response
+            .body
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["amount"].as_f64().unwrap())
+            .collect();
+        assert_eq!(amounts, vec![45.5, 30.0]);
+    }
+
+    #[test]
+    fn test_post_validates_and_adds() {
+        let state = state();
+        let body =
+            r#"{"amount": 9.5, "category": "coffee", "date": "2026-02-02", "payee": "Café"}"#;
+        let response = handle(&Request::new("POST", "/expenses", body), &state);
+        assert_eq!(response.status, 201);
+        assert_eq!(response.body["payee"], "Café");
+        assert_eq!(state.tracker.len(), 4);
+
+        let negative = r#"{"amount": -1, "category": "coffee", "date": "2026-02-02"}"#;
+        let response = handle(&Request::new("POST", "/expenses", negative), &state);
+        assert_eq!(response.status, 400);
+        let response = handle(&Request::new("POST", "/expenses", "not json"), &state);
+        assert_eq!(response.status, 400);
+        assert_eq!(state.tracker.len(), 4);
+    }
+
+    #[test]
+    fn test_post_into_closed_month_is_409() {
+        let state = state();
+        state.tracker.close_period("2026-01").unwrap();
+        let body = r#"{"amount": 9.5, "category": "coffee", "date": "2026-01-20"}"#;
+        let response = handle(&Request::new("POST", "/expenses", body), &state);
+        assert_eq!(response.status, 409);
+        assert_eq!(state.tracker.len(), 3);
+
+        let mut written = Vec::new();
+        response.write_to(&mut written).unwrap();
+        assert!(written.starts_with(b"HTTP/1.1 409 Conflict\r\n"));
+    }
+
+    #[test]
+    fn test_delete_unknown_id_is_404() {
+        let state = state();
+        let id = state.tracker.snapshot()[0].id;
+        let path = format!("/expenses/{}", id);
+        assert_eq!(
+            handle(&Request::new("DELETE", &path, ""), &state).status,
+            200
+        );
+        assert_eq!(
+            handle(&Request::new("DELETE", &path, ""), &state).status,
+            404
+        );
+        let response = handle(&Request::new("DELETE", "/expenses/abc", ""), &state);
+        assert_eq!(response.status, 400);
+    }
+
+    #[test]
+    fn test_report() {
+        let state = state();
+        let salary = Expense::new(2000.0, "salary", "2026-01-31")
+            .with_kind(crate::expense::TransactionKind::Income);
+        state.tracker.add_expense(salary, false).unwrap();
+        let response = handle(&Request::new("GET", "/report", ""), &state);
+        assert_eq!(response.body["total"], 95.5);
+        assert_eq!(response.body["income"], 2000.0);
+        assert_eq!(response.body["count"], 3);
+        assert_eq!(response.body["by_category"]["food"], 75.5);
+        assert!(response.body["by_category"].get("salary").is_none());
+        assert_eq!(response.body["most_expensive"]["amount"], 45.5);
+    }
+
+    #[test]
+    fn test_query_is_percent_decoded() {
+        let request = Request::new("GET", "/expenses?category=eating+out%21", "");
+        assert_eq!(request.query["category"], "eating out!");
+    }
+
+    #[test]
+    fn test_end_to_end_over_ephemeral_port() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let state = state();
+        std::thread::spawn(move || serve(listener, state));
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        let body = r#"{"amount": 12, "category": "books", "date": "2026-03-01"}"#;
+        write!(
+            stream,
+            "POST /expenses HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 201 Created\r\n"));
+        let json = response.split("\r\n\r\n").nth(1).unwrap();
+        let expense: Expense = serde_json::from_str(json).unwrap();
+        assert_eq!(expense.category, "books");
+    }
+}
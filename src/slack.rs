@@ -0,0 +1,104 @@
+// This module formats expenses as Slack messages: plain mrkdwn text for a
+// single expense and Block Kit JSON for a batch (hand-built, no Slack SDK)
+
+use crate::expense::Expense;
+use crate::operations::calculate_total;
+
+/// One expense as a Slack message, e.g. "*$45.50* food on 2026-01-08"
+pub fn expense_to_slack_message(expense: &Expense) -> String {
+    format!(
+        "*${:.2}* {} on {}",
+        expense.amount,
+        slack_escape(&expense.category),
+        expense.date
+    )
+}
+
+/// A batch of expenses as a Block Kit message: a header block with the
+/// count and total, then one section block per expense
+pub fn batch_to_slack_blocks(expenses: &[Expense]) -> String {
+    let noun = if expenses.len() == 1 {
+        "expense"
+    } else {
+        "expenses"
+    };
+    let header = format!(
+        "{} {} totaling ${:.2}",
+        expenses.len(),
+        noun,
+        calculate_total(expenses)
+    );
+
+    let mut blocks = vec![format!(
+        r#"{{"type":"header","text":{{"type":"plain_text","text":{}}}}}"#,
+        json_string(&header)
+    )];
+    for expense in expenses {
+        blocks.push(format!(
+            r#"{{"type":"section","text":{{"type":"mrkdwn","text":{}}}}}"#,
+            json_string(&expense_to_slack_message(expense))
+        ));
+    }
+    format!(r#"{{"blocks":[{}]}}"#, blocks.join(","))
+}
+
+/// Escape the characters Slack treats as markup in message text
+fn slack_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Quote a string as a JSON string literal
+pub(crate) fn json_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    #[test]
+    fn test_expense_to_slack_message() {
+        let expense = Expense::new(45.5, "food & drink", "2026-01-08");
+        assert_eq!(
+            expense_to_slack_message(&expense),
+            "*$45.50* food &amp; drink on 2026-01-08"
+        );
+    }
+
+    #[test]
+    fn test_batch_to_slack_blocks_is_valid_json() {
+        let expenses = vec![
+            Expense::new(45.5, "food", "2026-01-08"),
+            Expense::new(20.0, "say \"hi\"", "2026-01-09"),
+        ];
+        let json: Value = serde_json::from_str(&batch_to_slack_blocks(&expenses)).unwrap();
+        let blocks = json["blocks"].as_array().unwrap();
+
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[0]["type"], "header");
+        assert_eq!(blocks[0]["text"]["text"], "2 expenses totaling $65.50");
+        assert_eq!(blocks[1]["type"], "section");
+        assert_eq!(blocks[1]["text"]["text"], "*$45.50* food on 2026-01-08");
+        assert_eq!(
+            blocks[2]["text"]["text"],
+            "*$20.00* say \"hi\" on 2026-01-09"
+        );
+    }
+}
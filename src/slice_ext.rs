@@ -0,0 +1,212 @@
+// This module adds filter methods to expense slices, so lookups read as a
+// chain instead of nested function calls
+//
+//     expenses.by_category("food").over(10.0).total()
+//
+// Each method gives the same results as the matching function in
+// `operations`; this is only a different way of writing them.
+
+use crate::expense::Expense;
+
+/// Filter methods on a slice of expenses
+///
+/// ```
+/// use rust_folder::expense::Expense;
+/// use rust_folder::slice_ext::ExpenseSliceExt;
+///
+/// let expenses = vec![
+///     Expense::new(45.5, "food", "2026-01-08"),
+///     Expense::new(20.0, "transport", "2026-01-08"),
+///     Expense::new(8.0, "food", "2026-01-09"),
+/// ];
+/// assert_eq!(expenses.by_category("food").total(), 53.5);
+/// assert_eq!(expenses.by_date("2026-01-08").under(30.0).count(), 1);
+/// let big_food = expenses.by_category("food").over(10.0).max().unwrap();
+/// assert_eq!(big_food.amount, 45.5);
+/// ```
+pub trait ExpenseSliceExt {
+    /// Expenses in `category` (see `get_by_category`)
+    fn by_category(&self, category: &str) -> Filtered<'_>;
+    /// Expenses on `date` (see `view_expenses_by_date`)
+    fn by_date(&self, date: &str) -> Filtered<'_>;
+    /// Expenses from `from` to `to`, both included (see
+    /// `filter_by_date_range`)
+    fn by_date_range(&self, from: &str, to: &str) -> Filtered<'_>;
+    /// Expenses of more than `amount`
+    fn over(&self, amount: f64) -> Filtered<'_>;
+    /// Expenses of less than `amount`
+    fn under(&self, amount: f64) -> Filtered<'_>;
+}
+
+impl ExpenseSliceExt for [Expense] {
+    fn by_category(&self, category: &str) -> Filtered<'_> {
+        Filtered::of(self).by_category(category)
+    }
+
+    fn by_date(&self, date: &str) -> Filtered<'_> {
+        Filtered::of(self).by_date(date)
+    }
+
+    fn by_date_range(&self, from: &str, to: &str) -> Filtered<'_> {
+        Filtered::of(self).by_date_range(from, to)
+    }
+
+    fn over(&self, amount: f64) -> Filtered<'_> {
+        Filtered::of(self).over(amount)
+    }
+
+    fn under(&self, amount: f64) -> Filtered<'_> {
+        Filtered::of(self).under(amount)
+    }
+}
+
+/// The expenses left after one or more filters, in their original order
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filtered<'a> {
+    expenses: Vec<&'a Expense>,
+}
+
+impl<'a> Filtered<'a> {
+    /// Every expense in the slice, to filter from
+    pub fn of(expenses: &'a [Expense]) -> Filtered<'a> {
+        Filtered {
+            expenses: expenses.iter().collect(),
+        }
+    }
+
+    fn keep(mut self, wanted: impl Fn(&Expense) -> bool) -> Filtered<'a> {
+        self.expenses.retain(|e| wanted(e));
+        self
+    }
+
+    /// Only those in `category`
+    pub fn by_category(self, category: &str) -> Filtered<'a> {
+        self.keep(|e| e.category == category)
+    }
+
+    /// Only those on `date`
+    pub fn by_date(self, date: &str) -> Filtered<'a> {
+        self.keep(|e| e.date == date)
+    }
+
+    /// Only those from `from` to `to`, both included
+    pub fn by_date_range(self, from: &str, to: &str) -> Filtered<'a> {
+        self.keep(|e| e.date.as_str() >= from && e.date.as_str() <= to)
+    }
+
+    /// Only those of more than `amount`
+    pub fn over(self, amount: f64) -> Filtered<'a> {
+        self.keep(|e| e.amount > amount)
+    }
+
+    /// Only those of less than `amount`
+    pub fn under(self, amount: f64) -> Filtered<'a> {
+        self.keep(|e| e.amount < amount)
+    }
+
+    /// The sum of their amounts
+    pub fn total(&self) -> f64 {
+        self.expenses.iter().map(|e| e.amount).sum()
+    }
+
+    /// How many are left
+    pub fn count(&self) -> usize {
+        self.expenses.len()
+    }
+
+    /// The most expensive; with a tie, the first of them (as `find_max`)
+    pub fn max(&self) -> Option<&'a Expense> {
+        let max = self
+            .expenses
+            .iter()
+            .map(|e| e.amount)
+            .fold(f64::NAN, f64::max);
+        self.expenses.iter().copied().find(|e| e.amount == max)
+    }
+
+    /// The cheapest; with a tie, the first of them (as `find_min`)
+    pub fn min(&self) -> Option<&'a Expense> {
+        let min = self
+            .expenses
+            .iter()
+            .map(|e| e.amount)
+            .fold(f64::NAN, f64::min);
+        self.expenses.iter().copied().find(|e| e.amount == min)
+    }
+
+    /// Iterate over what is left
+    pub fn iter(&self) -> impl Iterator<Item = &'a Expense> + '_ {
+        self.expenses.iter().copied()
+    }
+
+    /// What is left, as a list
+    pub fn collect_refs(self) -> Vec<&'a Expense> {
+        self.expenses
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operations::*;
+
+    fn sample() -> Vec<Expense> {
+        vec![
+            Expense::new(45.5, "food", "2026-01-08"),
+            Expense::new(20.0, "transport", "2026-01-08"),
+            Expense::new(30.0, "food", "2026-01-07"),
+            Expense::new(45.5, "food", "2026-01-10"),
+            Expense::new(12.25, "food", "2026-02-01"),
+        ]
+    }
+
+    #[test]
+    fn test_methods_match_operations() {
+        let expenses = sample();
+        assert_eq!(
+            expenses.by_category("food").collect_refs(),
+            get_by_category(&expenses, "food")
+        );
+        assert_eq!(
+            expenses.by_date("2026-01-08").collect_refs(),
+            view_expenses_by_date(&expenses, "2026-01-08")
+        );
+        assert_eq!(
+            expenses
+                .by_date_range("2026-01-07", "2026-01-10")
+                .collect_refs(),
+            filter_by_date_range(&expenses, "2026-01-07", "2026-01-10")
+        );
+        assert_eq!(
+            expenses.by_category("food").total(),
+            total_by_category(&expenses, "food")
+        );
+        assert_eq!(
+            expenses.by_category("food").count(),
+            count_by_category(&expenses, "food")
+        );
+        let all = Filtered::of(&expenses);
+        assert_eq!(all.total(), calculate_total(&expenses));
+        assert!(std::ptr::eq(
+            all.max().unwrap(),
+            find_max(&expenses).unwrap()
+        ));
+        assert!(std::ptr::eq(
+            all.min().unwrap(),
+            find_min(&expenses).unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_filters_chain() {
+        let expenses = sample();
+        let food = expenses.by_category("food").over(20.0).under(45.5);
+        assert_eq!(food.count(), 1);
+        assert_eq!(food.iter().next().unwrap().amount, 30.0);
+
+        let january = expenses.by_date_range("2026-01-01", "2026-01-31");
+        assert_eq!(january.clone().by_category("food").total(), 121.0);
+        assert_eq!(january.over(100.0).max(), None);
+        assert_eq!(expenses.under(0.0).min(), None);
+    }
+}
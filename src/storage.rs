@@ -0,0 +1,1007 @@
+// This module saves expenses to and loads them from CSV data files
+//
+// Everything that touches the filesystem is behind the `fs` feature; the
+// CSV text functions and the `Storage` trait work everywhere, including
+// WASM.
+
+use std::collections::BTreeMap;
+use std::fmt;
+#[cfg(feature = "fs")]
+use std::path::{Path, PathBuf};
+
+use log::warn;
+
+#[cfg(feature = "fs")]
+use crate::dates::month_of;
+use crate::dates::validate_date_format;
+use crate::error::ExpenseError;
+use crate::expense::{self, Expense, TransactionKind, parse_amount, reserve_ids_through};
+
+/// Header line of the data file
+pub const CSV_HEADER: &str =
+    "id,amount,category,date,note,payee,tags,currency,kind,subcategory,receipt_number";
+
+/// Separates the tags in the `tags` column
+pub const TAG_SEPARATOR: &str = ";";
+
+/// Start of the optional first line recording the next id to hand out
+///
+/// Keeping it in the file means an id freed by deleting the newest expense
+/// is never handed out again after a reload.
+pub const NEXT_ID_PREFIX: &str = "# next_id=";
+
+/// How CSV text is laid out, for spreadsheets whose locale expects e.g.
+/// `;` between fields and `,` before the cents
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsvOptions {
+    /// Separates the fields of a row
+    pub delimiter: char,
+    /// Separates whole units from cents in amounts
+    pub decimal_separator: char,
+    /// Whether the first row names the columns; without one, the columns
+    /// are those of `CSV_HEADER`, in that order
+    pub include_header: bool,
+}
+
+impl Default for CsvOptions {
+    /// The data file's own layout: `,` between fields, `.` in amounts and
+    /// a header
+    fn default() -> Self {
+        CsvOptions {
+            delimiter: ',',
+            decimal_separator: '.',
+            include_header: true,
+        }
+    }
+}
+
+impl CsvOptions {
+    /// Options for reading `text` when its layout isn't known
+    ///
+    /// The delimiter is whichever of `,`, `;` and tab occurs most in the
+    /// first line (`,` if none does), and with `;` amounts are taken to
+    /// use a decimal comma, as spreadsheets do in such locales.
+    pub fn detect(text: &str) -> CsvOptions {
+        let first = text
+            .lines()
+            .find(|line| !line.starts_with(NEXT_ID_PREFIX))
+            .unwrap_or("");
+        let delimiter = [';', '\t']
+            .into_iter()
+            .fold((',', first.matches(',').count()), |best, c| {
+                let count = first.matches(c).count();
+                if count > best.1 { (c, count) } else { best }
+            })
+            .0;
+        CsvOptions {
+            delimiter,
+            decimal_separator: if delimiter == ';' { ',' } else { '.' },
+            include_header: true,
+        }
+    }
+}
+
+/// Write expenses as CSV text, one expense per line after the header
+///
+/// Takes any iterator of expenses, so a filtered view (e.g. the result of
+/// `get_by_category`) can be written without cloning it into a new list.
+pub fn to_csv<'a>(expenses: impl IntoIterator<Item = &'a Expense>) -> String {
+    to_csv_with_options(expenses, &CsvOptions::default())
+}
+
+/// Write expenses as CSV text laid out as `options` says, e.g. for a
+/// spreadsheet; `from_csv_with_options` reads it back
+pub fn to_csv_with_options<'a>(
+    expenses: impl IntoIterator<Item = &'a Expense>,
+    options: &CsvOptions,
+) -> String {
+    let mut out = String::new();
+    if options.include_header {
+        out.push_str(&CSV_HEADER.replace(',', &options.delimiter.to_string()));
+        out.push('\n');
+    }
+    for expense in expenses {
+        let fields = [
+            expense.id.to_string(),
+            expense
+                .amount
+                .to_string()
+                .replace('.', &options.decimal_separator.to_string()),
+            expense.category.clone(),
+            expense.date.clone(),
+            expense.note.clone().unwrap_or_default(),
+            expense.payee.clone().unwrap_or_default(),
+            expense.tags.join(TAG_SEPARATOR),
+            expense.currency.clone().unwrap_or_default(),
+            // Left empty for plain expenses, the usual case
+            match expense.kind {
+                TransactionKind::Expense => String::new(),
+                kind => kind.to_string(),
+            },
+            expense.subcategory.clone().unwrap_or_default(),
+            expense.receipt_number.clone().unwrap_or_default(),
+        ];
+        out.push_str(&join_csv_fields_with(&fields, options.delimiter));
+        out.push('\n');
+    }
+    out
+}
+
+/// Write expenses as CSV text preceded by a `# next_id=` line
+pub fn to_csv_with_next_id<'a>(
+    expenses: impl IntoIterator<Item = &'a Expense>,
+    next_id: u64,
+) -> String {
+    format!("{}{}\n{}", NEXT_ID_PREFIX, next_id, to_csv(expenses))
+}
+
+/// The id recorded on a `# next_id=` first line, if the text has one
+pub fn read_next_id(text: &str) -> Result<Option<u64>, ExpenseError> {
+    let Some(rest) = text.strip_prefix(NEXT_ID_PREFIX) else {
+        return Ok(None);
+    };
+    let value = rest.lines().next().unwrap_or("").trim();
+    value
+        .parse()
+        .map(Some)
+        .map_err(|_| ExpenseError::ParseError(format!("line 1: bad next_id `{}`", value)))
+}
+
+/// How forgiving `from_csv_with_mode` is with hand-edited files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParseMode {
+    /// Every row must have exactly the header's fields, used as written
+    #[default]
+    Strict,
+    /// Also accept whitespace-only lines, whitespace around fields and
+    /// empty fields past the last column, reporting each row fixed up
+    Lenient,
+}
+
+/// Something lenient parsing fixed up in one row
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Repair {
+    /// The line held only whitespace and was skipped
+    BlankLine,
+    /// Whitespace around one or more fields was removed
+    TrimmedWhitespace,
+    /// This many empty fields after the last column were dropped
+    TrailingEmptyFields(usize),
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Repair::BlankLine => write!(f, "skipped blank line"),
+            Repair::TrimmedWhitespace => write!(f, "trimmed whitespace"),
+            Repair::TrailingEmptyFields(n) => write!(f, "dropped {} trailing empty fields", n),
+        }
+    }
+}
+
+/// A row that lenient parsing had to fix up, for review
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepairedRow {
+    /// Line the row starts on (1 is the header)
+    pub line: usize,
+    pub repairs: Vec<Repair>,
+}
+
+/// Read expenses from CSV text written by `to_csv`
+///
+/// Columns are matched by header name, so files written before a column
+/// existed still load; missing optional columns are left empty. A leading
+/// `# next_id=` line is honoured: later `Expense::new` ids start there.
+/// Parsing is strict (see `from_csv_with_mode`).
+pub fn from_csv(text: &str) -> Result<Vec<Expense>, ExpenseError> {
+    from_csv_with_mode(text, ParseMode::Strict).map(|(expenses, _)| expenses)
+}
+
+/// Read expenses from CSV text laid out as `options` says, e.g. a
+/// spreadsheet export; use `CsvOptions::detect` when the layout isn't
+/// known
+///
+/// Parsing is strict, as in `from_csv`.
+pub fn from_csv_with_options(
+    text: &str,
+    options: &CsvOptions,
+) -> Result<Vec<Expense>, ExpenseError> {
+    read_data_rows(text, ParseMode::Strict, options)
+        .map(|(expenses, _)| expenses)
+        .inspect_err(|err| warn!("rejected data file: {}", err))
+}
+
+/// Read expenses from CSV text like `from_csv`, in the given `mode`
+///
+/// Returns the expenses and, in lenient mode, the rows that were repaired.
+/// Both modes reject bad ids, amounts and dates, and rows with too few
+/// fields, naming the line.
+pub fn from_csv_with_mode(
+    text: &str,
+    mode: ParseMode,
+) -> Result<(Vec<Expense>, Vec<RepairedRow>), ExpenseError> {
+    read_data_rows(text, mode, &CsvOptions::default())
+        .inspect_err(|err| warn!("rejected data file: {}", err))
+}
+
+fn read_data_rows(
+    text: &str,
+    mode: ParseMode,
+    options: &CsvOptions,
+) -> Result<(Vec<Expense>, Vec<RepairedRow>), ExpenseError> {
+    let mut text = std::borrow::Cow::Borrowed(text);
+    if let Some(next_id) = read_next_id(&text)? {
+        if next_id > 0 {
+            reserve_ids_through(next_id - 1);
+        }
+        // Blank the line out so the rows keep their line numbers
+        let body = text.split_once('\n').map(|(_, body)| body).unwrap_or("");
+        text = format!("\n{}", body).into();
+    }
+    let lenient = mode == ParseMode::Lenient;
+    let mut repairs: BTreeMap<usize, Vec<Repair>> = BTreeMap::new();
+    let mut rows = Vec::new();
+    for (line, fields) in parse_csv_with(&text, options.delimiter)? {
+        if !lenient {
+            rows.push((line, fields));
+        } else if fields.iter().all(|f| f.trim().is_empty()) {
+            repairs.entry(line).or_default().push(Repair::BlankLine);
+        } else {
+            let trimmed: Vec<String> = fields.iter().map(|f| f.trim().to_string()).collect();
+            if trimmed != fields {
+                repairs
+                    .entry(line)
+                    .or_default()
+                    .push(Repair::TrimmedWhitespace);
+            }
+            rows.push((line, trimmed));
+        }
+    }
+    let mut rows = rows.into_iter();
+    let mut header = if !options.include_header {
+        CSV_HEADER.split(',').map(str::to_string).collect()
+    } else {
+        match rows.next() {
+            Some((_, header)) => header,
+            None => return Ok((Vec::new(), collect_repairs(repairs))),
+        }
+    };
+    if lenient {
+        drop_trailing_empty(&mut header, 0, 1, &mut repairs);
+    }
+    let column = |name: &str| header.iter().position(|h| h == name);
+    let required = |name: &str| {
+        column(name)
+            .ok_or_else(|| ExpenseError::ParseError(format!("line 1: missing `{}` column", name)))
+    };
+    let id_col = required("id")?;
+    let amount_col = required("amount")?;
+    let category_col = required("category")?;
+    let date_col = required("date")?;
+    let note_col = column("note");
+    let payee_col = column("payee");
+    let tags_col = column("tags");
+    let currency_col = column("currency");
+    let kind_col = column("kind");
+    let subcategory_col = column("subcategory");
+    let receipt_number_col = column("receipt_number");
+
+    let mut expenses = Vec::new();
+    for (line, mut fields) in rows {
+        if lenient {
+            drop_trailing_empty(&mut fields, header.len(), line, &mut repairs);
+        }
+        if fields.len() != header.len() {
+            return Err(ExpenseError::ParseError(format!(
+                "line {}: expected {} fields, found {}",
+                line,
+                header.len(),
+                fields.len()
+            )));
+        }
+        let id = fields[id_col].parse::<u64>().map_err(|_| {
+            ExpenseError::ParseError(format!("line {}: bad id `{}`", line, fields[id_col]))
+        })?;
+        let amount = parse_amount_field_with(&fields[amount_col], line, options.decimal_separator)?;
+        validate_date_format(&fields[date_col])
+            .map_err(|err| err.with_context(&format!("line {}", line)))?;
+        let mut expense = Expense::with_id(id, amount, &fields[category_col], &fields[date_col]);
+        let optional =
+            |col: Option<usize>| col.map(|c| fields[c].clone()).filter(|v| !v.is_empty());
+        expense.note = optional(note_col);
+        expense.payee = optional(payee_col);
+        expense.tags = optional(tags_col)
+            .map(|tags| tags.split(TAG_SEPARATOR).map(str::to_string).collect())
+            .unwrap_or_default();
+        expense.currency = optional(currency_col);
+        expense.kind = match optional(kind_col).as_deref() {
+            None | Some("expense") => TransactionKind::Expense,
+            Some("income") => TransactionKind::Income,
+            Some(other) => {
+                return Err(ExpenseError::ParseError(format!(
+                    "line {}: bad kind `{}`",
+                    line, other
+                )));
+            }
+        };
+        expense.subcategory = optional(subcategory_col);
+        expense.receipt_number = optional(receipt_number_col);
+        expenses.push(expense);
+    }
+    Ok((expenses, collect_repairs(repairs)))
+}
+
+/// Drop empty fields past the first `keep`, recording the repair
+fn drop_trailing_empty(
+    fields: &mut Vec<String>,
+    keep: usize,
+    line: usize,
+    repairs: &mut BTreeMap<usize, Vec<Repair>>,
+) {
+    let last = fields
+        .iter()
+        .rposition(|f| !f.is_empty())
+        .map_or(0, |i| i + 1);
+    let len = last.max(keep);
+    if len < fields.len() {
+        repairs
+            .entry(line)
+            .or_default()
+            .push(Repair::TrailingEmptyFields(fields.len() - len));
+        fields.truncate(len);
+    }
+}
+
+fn collect_repairs(repairs: BTreeMap<usize, Vec<Repair>>) -> Vec<RepairedRow> {
+    repairs
+        .into_iter()
+        .map(|(line, repairs)| RepairedRow { line, repairs })
+        .collect()
+}
+
+/// Somewhere a list of expenses can be loaded from and saved to
+pub trait Storage {
+    /// Read every stored expense
+    fn load(&self) -> Result<Vec<Expense>, ExpenseError>;
+    /// Replace the stored expenses
+    fn save(&mut self, expenses: &[Expense]) -> Result<(), ExpenseError>;
+}
+
+/// Keeps the data file's text in memory, e.g. for a web UI that persists
+/// it in browser storage
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MemoryStorage {
+    /// Contents in the data file format
+    pub text: String,
+}
+
+impl Storage for MemoryStorage {
+    fn load(&self) -> Result<Vec<Expense>, ExpenseError> {
+        from_csv(&self.text)
+    }
+
+    fn save(&mut self, expenses: &[Expense]) -> Result<(), ExpenseError> {
+        self.text = data_file_text(expenses);
+        Ok(())
+    }
+}
+
+/// A CSV data file on disk
+#[cfg(feature = "fs")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileStorage {
+    pub path: String,
+}
+
+#[cfg(feature = "fs")]
+impl FileStorage {
+    /// Lock the file against other processes until the lock is dropped;
+    /// take it around a load, change and save so no write gets lost
+    pub fn lock(&self) -> Result<crate::lock::FileLock, ExpenseError> {
+        crate::lock::FileLock::acquire(&self.path, crate::lock::LOCK_TIMEOUT)
+    }
+}
+
+#[cfg(feature = "fs")]
+impl Storage for FileStorage {
+    fn load(&self) -> Result<Vec<Expense>, ExpenseError> {
+        load_from_file(&self.path)
+    }
+
+    fn save(&mut self, expenses: &[Expense]) -> Result<(), ExpenseError> {
+        save_to_file(expenses, &self.path)
+    }
+}
+
+/// Save expenses to a CSV data file, replacing its contents
+///
+/// The file records the next id `Expense::new` would hand out, so ids stay
+/// unique across reloads.
+#[cfg(feature = "fs")]
+pub fn save_to_file(expenses: &[Expense], path: &str) -> Result<(), ExpenseError> {
+    std::fs::write(path, data_file_text(expenses))?;
+    log::debug!("saved {} expenses to {}", expenses.len(), path);
+    Ok(())
+}
+
+/// Contents of a data file for `expenses`
+fn data_file_text(expenses: &[Expense]) -> String {
+    let next_id = expenses
+        .iter()
+        .map(|e| e.id.saturating_add(1))
+        .fold(expense::next_id(), u64::max);
+    to_csv_with_next_id(expenses, next_id)
+}
+
+/// Write any selection of expenses to a CSV file
+///
+/// The file can be read back with `load_from_file`.
+#[cfg(feature = "fs")]
+pub fn save_to_csv<'a>(
+    expenses: impl IntoIterator<Item = &'a Expense>,
+    path: &str,
+) -> Result<(), ExpenseError> {
+    let text = to_csv(expenses);
+    std::fs::write(path, &text)?;
+    log::debug!(
+        "wrote {} expense rows to {}",
+        text.lines().count() - 1,
+        path
+    );
+    Ok(())
+}
+
+/// Write the result of a query (a list of references) to a CSV file
+#[cfg(feature = "fs")]
+pub fn export_query_csv(query_result: &[&Expense], path: &str) -> Result<(), ExpenseError> {
+    save_to_csv(query_result.iter().copied(), path)
+}
+
+/// Load expenses from a CSV data file
+#[cfg(feature = "fs")]
+pub fn load_from_file(path: &str) -> Result<Vec<Expense>, ExpenseError> {
+    let text = std::fs::read_to_string(path)?;
+    let expenses = from_csv(&text)?;
+    log::debug!("loaded {} expenses from {}", expenses.len(), path);
+    Ok(expenses)
+}
+
+/// A data file in a directory that could not be loaded
+#[cfg(feature = "fs")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadError {
+    /// The file (or, if listing failed, the directory)
+    pub path: PathBuf,
+    /// What went wrong, with the line number for bad rows
+    pub error: ExpenseError,
+}
+
+#[cfg(feature = "fs")]
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.error)
+    }
+}
+
+#[cfg(feature = "fs")]
+impl std::error::Error for LoadError {}
+
+#[cfg(feature = "fs")]
+impl From<LoadError> for ExpenseError {
+    fn from(err: LoadError) -> Self {
+        err.error.with_context(&err.path.display().to_string())
+    }
+}
+
+/// Load every `*.csv` data file in `dir`, each paired with its path,
+/// in file name order
+///
+/// Other files and subdirectories are ignored. The first file that fails
+/// to load stops the whole load.
+#[cfg(feature = "fs")]
+pub fn load_directory_files(dir: &Path) -> Result<Vec<(PathBuf, Vec<Expense>)>, LoadError> {
+    let at = |path: &Path| {
+        let path = path.to_path_buf();
+        move |error: ExpenseError| LoadError { path, error }
+    };
+    let mut paths = Vec::new();
+    for entry in std::fs::read_dir(dir).map_err(|e| at(dir)(e.into()))? {
+        let path = entry.map_err(|e| at(dir)(e.into()))?.path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "csv") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    let mut files = Vec::new();
+    for path in paths {
+        let text = std::fs::read_to_string(&path).map_err(|e| at(&path)(e.into()))?;
+        let expenses = from_csv(&text).map_err(at(&path))?;
+        files.push((path, expenses));
+    }
+    log::debug!("loaded {} data files from {}", files.len(), dir.display());
+    Ok(files)
+}
+
+/// Load every `*.csv` data file in `dir` as one list
+///
+/// Expenses keep the ids stored in their files. Use
+/// `load_directory_files` to know which file each expense came from.
+#[cfg(feature = "fs")]
+pub fn load_directory(dir: &Path) -> Result<Vec<Expense>, LoadError> {
+    let files = load_directory_files(dir)?;
+    Ok(files
+        .into_iter()
+        .flat_map(|(_, expenses)| expenses)
+        .collect())
+}
+
+/// Save expenses to one data file per month in `dir`, named `YYYY-MM.csv`
+///
+/// Creates `dir` if needed and replaces the files for the months present;
+/// files for other months are left alone. Nothing is written if any
+/// expense has a date without a month.
+#[cfg(feature = "fs")]
+pub fn save_partitioned_by_month(expenses: &[Expense], dir: &Path) -> Result<(), ExpenseError> {
+    let mut months: BTreeMap<&str, Vec<Expense>> = BTreeMap::new();
+    for expense in expenses {
+        let month = month_of(&expense.date).ok_or_else(|| {
+            ExpenseError::InvalidDate(format!("expense {}: `{}`", expense.id, expense.date))
+        })?;
+        months.entry(month).or_default().push(expense.clone());
+    }
+    std::fs::create_dir_all(dir)?;
+    for (month, expenses) in &months {
+        let path = dir.join(format!("{}.csv", month));
+        std::fs::write(&path, data_file_text(expenses))?;
+    }
+    log::debug!(
+        "saved {} expenses to {} monthly files in {}",
+        expenses.len(),
+        months.len(),
+        dir.display()
+    );
+    Ok(())
+}
+
+/// Save expenses to a CSV data file without blocking the async runtime
+#[cfg(feature = "async")]
+pub async fn save_to_file_async(expenses: &[Expense], path: &str) -> Result<(), ExpenseError> {
+    tokio::fs::write(path, data_file_text(expenses)).await?;
+    Ok(())
+}
+
+/// Load expenses from a CSV data file without blocking the async runtime
+#[cfg(feature = "async")]
+pub async fn load_from_file_async(path: &str) -> Result<Vec<Expense>, ExpenseError> {
+    let text = tokio::fs::read_to_string(path).await?;
+    from_csv(&text)
+}
+
+/// Parse an amount column, reporting the line on failure
+pub(crate) fn parse_amount_field(field: &str, line: usize) -> Result<f64, ExpenseError> {
+    parse_amount(field)
+        .map_err(|_| ExpenseError::ParseError(format!("line {}: bad amount `{}`", line, field)))
+}
+
+/// Parse an amount column written with `decimal_separator` before the
+/// cents, reporting the line on failure
+pub(crate) fn parse_amount_field_with(
+    field: &str,
+    line: usize,
+    decimal_separator: char,
+) -> Result<f64, ExpenseError> {
+    match decimal_separator {
+        '.' => parse_amount_field(field, line),
+        separator => parse_amount_field(&field.replace(separator, "."), line).map_err(|_| {
+            ExpenseError::ParseError(format!("line {}: bad amount `{}`", line, field))
+        }),
+    }
+}
+
+/// Join fields into one CSV line, quoting where needed
+pub(crate) fn join_csv_fields(fields: &[String]) -> String {
+    join_csv_fields_with(fields, ',')
+}
+
+/// Join fields into one line separated by `delimiter`, quoting where
+/// needed
+fn join_csv_fields_with(fields: &[String], delimiter: char) -> String {
+    fields
+        .iter()
+        .map(|field| quote_csv_field(field, delimiter))
+        .collect::<Vec<_>>()
+        .join(&delimiter.to_string())
+}
+
+/// Quote a field if it contains the delimiter, a quote or a line break
+fn quote_csv_field(field: &str, delimiter: char) -> String {
+    if field.contains([delimiter, '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Split CSV text into records, each tagged with the line it starts on
+///
+/// Blank lines are skipped. Quoted fields may contain commas, doubled
+/// quotes and line breaks.
+pub(crate) fn parse_csv(text: &str) -> Result<Vec<(usize, Vec<String>)>, ExpenseError> {
+    parse_csv_with(text, ',')
+}
+
+/// Split text into records like `parse_csv`, with fields separated by
+/// `delimiter`
+pub(crate) fn parse_csv_with(
+    text: &str,
+    delimiter: char,
+) -> Result<Vec<(usize, Vec<String>)>, ExpenseError> {
+    let mut rows = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut row_start = 1;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                '\n' => {
+                    line += 1;
+                    field.push(c);
+                }
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            c if c == delimiter => fields.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                fields.push(std::mem::take(&mut field));
+                if !(fields.len() == 1 && fields[0].is_empty()) {
+                    rows.push((row_start, std::mem::take(&mut fields)));
+                }
+                fields.clear();
+                line += 1;
+                row_start = line;
+            }
+            _ => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err(ExpenseError::ParseError(format!(
+            "line {}: unterminated quoted field",
+            row_start
+        )));
+    }
+    if !field.is_empty() || !fields.is_empty() {
+        fields.push(field);
+        rows.push((row_start, fields));
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_round_trip() {
+        let expenses = vec![
+            Expense::new(45.5, "food", "2026-01-08"),
+            Expense::new(20.0, "bus, train \"and\" taxi", "2026-01-09").with_note("late, again"),
+            Expense::new(3.2, "coffee", "2026-01-10").with_payee("Corner Café"),
+            Expense::new(120.0, "travel", "2026-01-11")
+                .with_tag("work")
+                .with_tag("trip"),
+            Expense::new(2000.0, "salary", "2026-01-31").with_kind(TransactionKind::Income),
+            Expense::new(12.0, "food", "2026-02-01").with_subcategory("grocery"),
+        ];
+        assert_eq!(from_csv(&to_csv(&expenses)).unwrap(), expenses);
+    }
+
+    #[test]
+    fn test_csv_options_round_trip() {
+        let expenses = vec![
+            Expense::new(45.5, "food", "2026-01-08").with_note("lunch; with team"),
+            Expense::new(1200.25, "rent", "2026-01-01")
+                .with_tag("home")
+                .with_tag("fixed"),
+        ];
+        let semicolon = CsvOptions {
+            delimiter: ';',
+            decimal_separator: ',',
+            include_header: true,
+        };
+        for options in [CsvOptions::default(), semicolon] {
+            let text = to_csv_with_options(&expenses, &options);
+            assert_eq!(from_csv_with_options(&text, &options).unwrap(), expenses);
+            assert_eq!(CsvOptions::detect(&text), options);
+        }
+        assert_eq!(
+            to_csv_with_options(&expenses[..1], &semicolon),
+            format!(
+                "id;amount;category;date;note;payee;tags;currency;kind;subcategory;receipt_number\n{};45,5;food;2026-01-08;\"lunch; with team\";;;;;;\n",
+                expenses[0].id
+            )
+        );
+
+        let headerless = CsvOptions {
+            include_header: false,
+            ..semicolon
+        };
+        let text = to_csv_with_options(&expenses, &headerless);
+        assert_eq!(text.lines().count(), 2);
+        assert_eq!(from_csv_with_options(&text, &headerless).unwrap(), expenses);
+    }
+
+    #[test]
+    fn test_csv_options_bad_amount_names_the_field() {
+        let options = CsvOptions::detect("id;amount;category;date\n");
+        let err = from_csv_with_options(
+            "id;amount;category;date\n1;4,5,0;food;2026-01-01\n",
+            &options,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ExpenseError::ParseError("line 2: bad amount `4,5,0`".to_string())
+        );
+    }
+
+    #[test]
+    fn test_from_csv_reads_files_without_note_column() {
+        let text = "id,amount,category,date\n7,10,food,2026-01-01\n";
+        let expenses = from_csv(text).unwrap();
+        assert_eq!(expenses[0].id, 7);
+        assert_eq!(expenses[0].note, None);
+    }
+
+    #[test]
+    fn test_from_csv_reports_line_number() {
+        let text = "id,amount,category,date\n1,10,food,2026-01-01\n2,ten,food,2026-01-02\n";
+        let err = from_csv(text).unwrap_err();
+        assert_eq!(
+            err,
+            ExpenseError::ParseError("line 3: bad amount `ten`".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_modes_on_hand_edited_file() {
+        let text = "id,amount,category,date,\n\
+                    1,45.5,food,2026-01-08,\n\
+                    \x20\x20\n\
+                    2, 20 ,transport , 2026-01-09\n\
+                    3,30,food,2026-01-10,,\n";
+
+        let err = from_csv_with_mode(text, ParseMode::Strict).unwrap_err();
+        assert_eq!(
+            err,
+            ExpenseError::ParseError("line 3: expected 5 fields, found 1".to_string())
+        );
+
+        let (expenses, repaired) = from_csv_with_mode(text, ParseMode::Lenient).unwrap();
+        assert_eq!(
+            expenses,
+            vec![
+                Expense::with_id(1, 45.5, "food", "2026-01-08"),
+                Expense::with_id(2, 20.0, "transport", "2026-01-09"),
+                Expense::with_id(3, 30.0, "food", "2026-01-10"),
+            ]
+        );
+        let repairs = |line: usize, repairs: Vec<Repair>| RepairedRow { line, repairs };
+        assert_eq!(
+            repaired,
+            vec![
+                repairs(1, vec![Repair::TrailingEmptyFields(1)]),
+                repairs(2, vec![Repair::TrailingEmptyFields(1)]),
+                repairs(3, vec![Repair::BlankLine]),
+                repairs(4, vec![Repair::TrimmedWhitespace]),
+                repairs(5, vec![Repair::TrailingEmptyFields(2)]),
+            ]
+        );
+        assert_eq!(
+            repaired[4].repairs[0].to_string(),
+            "dropped 2 trailing empty fields"
+        );
+    }
+
+    #[test]
+    fn test_parse_modes_each_deviation_alone() {
+        let header = "id,amount,category,date\n";
+        let cases = [
+            ("1,45.5,food,2026-01-08,\n", Repair::TrailingEmptyFields(1)),
+            ("  \n1,45.5,food,2026-01-08\n", Repair::BlankLine),
+            (" 1,45.5,food,2026-01-08\n", Repair::TrimmedWhitespace),
+        ];
+        for (rows, repair) in cases {
+            let text = format!("{}{}", header, rows);
+            assert!(
+                from_csv_with_mode(&text, ParseMode::Strict).is_err(),
+                "{:?}",
+                rows
+            );
+            let (expenses, repaired) = from_csv_with_mode(&text, ParseMode::Lenient).unwrap();
+            assert_eq!(expenses.len(), 1);
+            assert_eq!(repaired.len(), 1);
+            assert_eq!(repaired[0].line, 2);
+            assert_eq!(repaired[0].repairs, vec![repair]);
+        }
+
+        // Lenient still rejects values it can't use
+        for rows in [
+            "1,ten,food,2026-01-08\n",
+            "1,4.5,food,2026-02-30\n",
+            "1,4.5,food\n",
+        ] {
+            let text = format!("{}{}", header, rows);
+            assert!(
+                from_csv_with_mode(&text, ParseMode::Lenient).is_err(),
+                "{:?}",
+                rows
+            );
+        }
+        let text = format!("{}1,4.5,food,2026-02-30\n", header);
+        assert_eq!(
+            from_csv(&text).unwrap_err(),
+            ExpenseError::InvalidDate("line 2: `2026-02-30`: 2026-02 only has 28 days".to_string())
+        );
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_async_file_round_trip() {
+        let path = std::env::temp_dir().join(format!("async_test_{}.csv", std::process::id()));
+        let path = path.to_str().unwrap();
+        let expenses = vec![Expense::new(9.99, "books", "2026-03-01")];
+
+        save_to_file_async(&expenses, path).await.unwrap();
+        let loaded = load_from_file_async(path).await.unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(loaded, expenses);
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn test_export_filtered_subset_round_trips() {
+        use crate::operations::get_by_category;
+
+        let path = std::env::temp_dir().join(format!("export_test_{}.csv", std::process::id()));
+        let path = path.to_str().unwrap();
+        let expenses = vec![
+            Expense::new(45.5, "food", "2026-01-08"),
+            Expense::new(20.0, "transport", "2026-01-08"),
+            Expense::new(30.0, "food", "2026-01-07"),
+        ];
+
+        let food = get_by_category(&expenses, "food");
+        export_query_csv(&food, path).unwrap();
+        let loaded = load_from_file(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(loaded, vec![expenses[0].clone(), expenses[2].clone()]);
+    }
+
+    #[cfg(feature = "fs")]
+    struct TempDir(PathBuf);
+
+    #[cfg(feature = "fs")]
+    impl TempDir {
+        fn new(name: &str) -> TempDir {
+            let path =
+                std::env::temp_dir().join(format!("storage_{}_{}", name, std::process::id()));
+            let _ = std::fs::remove_dir_all(&path);
+            std::fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+    }
+
+    #[cfg(feature = "fs")]
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn test_directory_round_trip_by_month() {
+        let dir = TempDir::new("by_month");
+        let expenses = vec![
+            Expense::with_id(1, 45.5, "food", "2026-01-08"),
+            Expense::with_id(2, 500.0, "rent", "2026-02-01"),
+            Expense::with_id(3, 20.0, "transport", "2026-01-09"),
+        ];
+        save_partitioned_by_month(&expenses, &dir.0).unwrap();
+        std::fs::write(dir.0.join("notes.txt"), "not expenses").unwrap();
+
+        let files = load_directory_files(&dir.0).unwrap();
+        let names: Vec<_> = files
+            .iter()
+            .map(|(path, _)| path.file_name().unwrap())
+            .collect();
+        assert_eq!(names, ["2026-01.csv", "2026-02.csv"]);
+        assert_eq!(files[0].1, vec![expenses[0].clone(), expenses[2].clone()]);
+
+        let loaded = load_directory(&dir.0).unwrap();
+        assert_eq!(
+            loaded,
+            vec![
+                expenses[0].clone(),
+                expenses[2].clone(),
+                expenses[1].clone()
+            ]
+        );
+
+        let bad = [Expense::with_id(4, 1.0, "food", "soon")];
+        assert!(save_partitioned_by_month(&bad, &dir.0).is_err());
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn test_load_directory_names_the_malformed_file() {
+        let dir = TempDir::new("malformed");
+        save_to_file(
+            &[Expense::with_id(1, 4.5, "food", "2026-01-08")],
+            dir.0.join("2026-01.csv").to_str().unwrap(),
+        )
+        .unwrap();
+        save_to_file(
+            &[Expense::with_id(2, 9.0, "food", "2026-02-08")],
+            dir.0.join("2026-02.csv").to_str().unwrap(),
+        )
+        .unwrap();
+        let broken = dir.0.join("2026-03.csv");
+        std::fs::write(
+            &broken,
+            "id,amount,category,date\n3,10,food,2026-03-01\n4,ten,food,2026-03-02\n",
+        )
+        .unwrap();
+
+        let err = load_directory(&dir.0).unwrap_err();
+        assert_eq!(err.path, broken);
+        assert_eq!(
+            err.error,
+            ExpenseError::ParseError("line 3: bad amount `ten`".to_string())
+        );
+        assert!(
+            err.to_string()
+                .ends_with("2026-03.csv: parse error: line 3: bad amount `ten`")
+        );
+
+        std::fs::remove_file(&broken).unwrap();
+        assert_eq!(load_directory(&dir.0).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_next_id_line_survives_deleting_newest() {
+        // Expense 2 was the newest and has been deleted
+        let kept = [Expense::with_id(1, 10.0, "food", "2026-01-01")];
+        let text = to_csv_with_next_id(&kept, 3);
+        assert_eq!(read_next_id(&text).unwrap(), Some(3));
+        assert_eq!(from_csv(&text).unwrap(), kept);
+        assert!(Expense::new(1.0, "food", "2026-01-03").id >= 3);
+    }
+
+    #[test]
+    fn test_memory_storage_round_trip() {
+        let mut storage = MemoryStorage::default();
+        assert_eq!(storage.load().unwrap(), vec![]);
+        let expenses = vec![Expense::new(4.5, "food", "2026-01-08").with_note("soup")];
+        storage.save(&expenses).unwrap();
+        assert!(storage.text.starts_with(NEXT_ID_PREFIX));
+        assert_eq!(storage.load().unwrap(), expenses);
+    }
+}
@@ -0,0 +1,93 @@
+// This module adds expenses from named templates, for purchases that repeat
+// often (the daily bus ticket, the usual coffee)
+//
+// Templates live in the config file:
+//
+//     [templates]
+//     bus = { amount = 2.50, category = "transport" }
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::ExpenseError;
+use crate::expense::Expense;
+
+/// The fixed parts of an expense that is entered again and again
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Template {
+    pub amount: f64,
+    pub category: String,
+}
+
+/// Add an expense from the template called `name`, dated `date`
+///
+/// `amount` overrides the template's amount when given. Unknown names are
+/// reported together with the names that do exist.
+pub fn add_from_template<'a>(
+    expenses: &'a mut Vec<Expense>,
+    templates: &BTreeMap<String, Template>,
+    name: &str,
+    date: &str,
+    amount: Option<f64>,
+) -> Result<&'a Expense, ExpenseError> {
+    let template = templates.get(name).ok_or_else(|| {
+        let available: Vec<&str> = templates.keys().map(|k| k.as_str()).collect();
+        let available = if available.is_empty() {
+            "none configured".to_string()
+        } else {
+            available.join(", ")
+        };
+        ExpenseError::NotFound(format!(
+            "no template named `{}` (available: {})",
+            name, available
+        ))
+    })?;
+    let amount = amount.unwrap_or(template.amount);
+    expenses.push(Expense::new(amount, &template.category, date));
+    Ok(&expenses[expenses.len() - 1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn templates() -> BTreeMap<String, Template> {
+        let mut templates = BTreeMap::new();
+        let bus = Template {
+            amount: 2.5,
+            category: "transport".to_string(),
+        };
+        let coffee = Template {
+            amount: 3.0,
+            category: "food".to_string(),
+        };
+        templates.insert("bus".to_string(), bus);
+        templates.insert("coffee".to_string(), coffee);
+        templates
+    }
+
+    #[test]
+    fn test_add_from_template_with_override() {
+        let mut expenses = Vec::new();
+        let added =
+            add_from_template(&mut expenses, &templates(), "bus", "2026-01-08", Some(3.0)).unwrap();
+        assert_eq!(added.amount, 3.0);
+        assert_eq!(added.category, "transport");
+
+        add_from_template(&mut expenses, &templates(), "bus", "2026-01-09", None).unwrap();
+        assert_eq!(expenses[1].amount, 2.5);
+    }
+
+    #[test]
+    fn test_unknown_template_lists_available() {
+        let mut expenses = Vec::new();
+        let err =
+            add_from_template(&mut expenses, &templates(), "taxi", "2026-01-08", None).unwrap_err();
+        assert_eq!(
+            err,
+            ExpenseError::NotFound("no template named `taxi` (available: bus, coffee)".to_string())
+        );
+        assert!(expenses.is_empty());
+    }
+}
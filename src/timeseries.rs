@@ -0,0 +1,138 @@
+// This module exports spending as a time series, one CSV row per day, week
+// or month, for charting in tools like gnuplot or a spreadsheet
+
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+use crate::dates::{next_date, next_month};
+use crate::expense::Expense;
+use crate::operations::{daily_totals, monthly_totals, weekly_totals};
+
+/// How long each period of a time series is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Granularity {
+    /// Periods are `YYYY-MM-DD` days
+    Daily,
+    /// Periods are ISO weeks, labelled with their Monday
+    Weekly,
+    /// Periods are `YYYY-MM` months
+    Monthly,
+}
+
+impl Granularity {
+    /// Total per period, keyed by the period's label
+    fn totals(self, expenses: &[Expense]) -> BTreeMap<String, f64> {
+        match self {
+            Granularity::Daily => daily_totals(expenses),
+            Granularity::Weekly => weekly_totals(expenses),
+            Granularity::Monthly => monthly_totals(expenses),
+        }
+    }
+
+    /// The label of the period after `period`
+    fn next(self, period: &str) -> Option<String> {
+        match self {
+            Granularity::Daily => next_date(period, 1).ok(),
+            Granularity::Weekly => next_date(period, 7).ok(),
+            Granularity::Monthly => next_month(period),
+        }
+    }
+}
+
+/// Write `period,total,cumulative` CSV rows, one per period from the first
+/// expense's to the last one's
+///
+/// Periods without expenses get a total of 0.00, so a chart of the series
+/// has no holes. Amounts have two decimals. Expenses whose date can't be
+/// read are left out; with none left only the header is written.
+pub fn export_timeseries_csv(
+    expenses: &[Expense],
+    mut writer: impl Write,
+    granularity: Granularity,
+) -> io::Result<()> {
+    writeln!(writer, "period,total,cumulative")?;
+    let totals = granularity.totals(expenses);
+    let (Some(first), Some(last)) = (totals.keys().next(), totals.keys().next_back()) else {
+        return Ok(());
+    };
+    let mut period = first.clone();
+    let mut cumulative = 0.0;
+    loop {
+        let total = totals.get(&period).copied().unwrap_or(0.0);
+        cumulative += total;
+        writeln!(writer, "{},{:.2},{:.2}", period, total, cumulative)?;
+        // Labels of one granularity sort in time order as strings
+        match granularity.next(&period) {
+            Some(next) if next <= *last => period = next,
+            _ => return Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn export(expenses: &[Expense], granularity: Granularity) -> String {
+        let mut out = Vec::new();
+        export_timeseries_csv(expenses, &mut out, granularity).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_export_timeseries_fills_gaps() {
+        let expenses = vec![
+            Expense::new(10.0, "food", "2026-01-30"),
+            Expense::new(4.5, "coffee", "2026-01-30"),
+            Expense::new(20.0, "transport", "2026-02-01"),
+            Expense::new(500.0, "rent", "2026-03-02"),
+            Expense::new(1.0, "food", "not a date"),
+        ];
+        assert_eq!(
+            export(&expenses[..3], Granularity::Daily),
+            "period,total,cumulative
+2026-01-30,14.50,14.50
+2026-01-31,0.00,14.50
+2026-02-01,20.00,34.50
+"
+        );
+        assert_eq!(
+            export(&expenses, Granularity::Weekly),
+            "period,total,cumulative
+2026-01-26,34.50,34.50
+2026-02-02,0.00,34.50
+2026-02-09,0.00,34.50
+2026-02-16,0.00,34.50
+2026-02-23,0.00,34.50
+2026-03-02,500.00,534.50
+"
+        );
+        assert_eq!(
+            export(&expenses, Granularity::Monthly),
+            "period,total,cumulative
+2026-01,14.50,14.50
+2026-02,20.00,34.50
+2026-03,500.00,534.50
+"
+        );
+        assert_eq!(export(&[], Granularity::Daily), "period,total,cumulative\n");
+    }
+
+    #[test]
+    fn test_export_skips_months_that_dont_exist() {
+        let expenses = vec![
+            Expense::new(10.0, "food", "2026-01-30"),
+            Expense::new(1.0, "food", "2026-00-05"),
+            Expense::new(2.0, "food", "2026-13-01"),
+            Expense::new(3.0, "food", "2026-02-30"),
+            Expense::new(20.0, "transport", "2026-02-01"),
+        ];
+        assert_eq!(
+            export(&expenses, Granularity::Monthly),
+            "period,total,cumulative
+2026-01,10.00,10.00
+2026-02,20.00,30.00
+"
+        );
+    }
+}
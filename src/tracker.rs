@@ -0,0 +1,1042 @@
+// This module keeps a list of expenses together with the next id to hand
+// out, so ids are never reused, even across saves and reloads
+//
+// Side effects such as autosaving hang off the tracker as event hooks, so
+// the tracker itself only ever changes its list (and the edit history kept
+// alongside it).
+//
+// Months can be closed once they are reconciled: adding, editing or
+// removing an expense dated in a closed month is then refused unless the
+// caller forces it.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::budget::{Budget, BudgetWarning, budget_warnings};
+use crate::clock::{Clock, SystemClock};
+use crate::dates::{month_of, validate_month_format};
+use crate::error::ExpenseError;
+use crate::expense::{Expense, TransactionKind, reserve_ids_through};
+use crate::operations::calculate_total;
+use crate::storage::{TAG_SEPARATOR, from_csv, read_next_id, to_csv_with_next_id};
+#[cfg(feature = "fs")]
+use crate::storage::{join_csv_fields, parse_csv};
+
+/// Header line of the edit history file
+#[cfg(feature = "fs")]
+const HISTORY_HEADER: &str = "id,field,old,new,at";
+
+/// A change made to an `ExpenseTracker`, passed to its hooks
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrackerEvent<'a> {
+    /// An expense was added
+    Added(&'a Expense),
+    /// An expense was removed
+    Removed(Expense),
+    /// An expense was changed in place
+    Updated { before: Expense, after: &'a Expense },
+}
+
+/// What `ExpenseTracker::add` did
+#[derive(Debug, Clone, PartialEq)]
+pub struct AddResult {
+    /// A copy of the expense as added, with its id
+    pub expense: Expense,
+    /// Limits of the attached budget that the expense's week or month is
+    /// nearing or over; empty without a budget
+    pub budget_warnings: Vec<BudgetWarning>,
+}
+
+/// One field changed by `ExpenseTracker::update`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditRecord {
+    /// The field's name, as in the data file header (e.g. `amount`)
+    pub field: String,
+    /// The value before the edit, as written in the data file (empty for
+    /// no value)
+    pub old: String,
+    /// The value after the edit, written the same way
+    pub new: String,
+    /// The day (`YYYY-MM-DD`) of the edit, from the tracker's clock
+    pub at: String,
+}
+
+/// A callback run after every change (see `ExpenseTracker::on_event`)
+pub type Hook = Box<dyn FnMut(&TrackerEvent) + Send + Sync>;
+
+/// A list of expenses that owns id assignment
+///
+/// Comparisons look at the expenses and the id counter only; a clone
+/// keeps the budget and the edit history but starts without hooks.
+pub struct ExpenseTracker {
+    expenses: Vec<Expense>,
+    next_id: u64,
+    budget: Option<Budget>,
+    /// Edits by expense id, oldest first; kept after an expense is removed
+    history: BTreeMap<u64, Vec<EditRecord>>,
+    persist_history: bool,
+    /// `YYYY-MM` months whose expenses can't change without `force`
+    closed_periods: BTreeSet<String>,
+    clock: Arc<dyn Clock + Send + Sync>,
+    hooks: Vec<Hook>,
+}
+
+impl fmt::Debug for ExpenseTracker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExpenseTracker")
+            .field("expenses", &self.expenses)
+            .field("next_id", &self.next_id)
+            .field("budget", &self.budget)
+            .field("history", &self.history)
+            .field("persist_history", &self.persist_history)
+            .field("closed_periods", &self.closed_periods)
+            .field("hooks", &self.hooks.len())
+            .finish()
+    }
+}
+
+impl Clone for ExpenseTracker {
+    fn clone(&self) -> Self {
+        ExpenseTracker {
+            expenses: self.expenses.clone(),
+            next_id: self.next_id,
+            budget: self.budget.clone(),
+            history: self.history.clone(),
+            persist_history: self.persist_history,
+            closed_periods: self.closed_periods.clone(),
+            clock: Arc::clone(&self.clock),
+            hooks: Vec::new(),
+        }
+    }
+}
+
+impl PartialEq for ExpenseTracker {
+    fn eq(&self, other: &Self) -> bool {
+        self.expenses == other.expenses && self.next_id == other.next_id
+    }
+}
+
+impl Default for ExpenseTracker {
+    fn default() -> Self {
+        ExpenseTracker::new()
+    }
+}
+
+impl ExpenseTracker {
+    /// Creates an empty tracker whose first expense gets id 1
+    pub fn new() -> ExpenseTracker {
+        ExpenseTracker {
+            expenses: Vec::new(),
+            next_id: 1,
+            budget: None,
+            history: BTreeMap::new(),
+            persist_history: false,
+            closed_periods: BTreeSet::new(),
+            clock: Arc::new(SystemClock),
+            hooks: Vec::new(),
+        }
+    }
+
+    /// Read a tracker from text in the data file format
+    ///
+    /// The id counter resumes from the `# next_id=` line, or from one past
+    /// the highest id for text written without it.
+    pub fn from_text(text: &str) -> Result<ExpenseTracker, ExpenseError> {
+        let mut tracker = ExpenseTracker::new();
+        tracker.next_id = read_next_id(text)?.unwrap_or(1);
+        for expense in from_csv(text)? {
+            tracker.insert(expense);
+        }
+        Ok(tracker)
+    }
+
+    /// Run `hook` after every successful change, after any earlier hooks
+    ///
+    /// A hook that panics is reported in the log and doesn't stop the
+    /// other hooks or the change itself.
+    pub fn on_event(&mut self, hook: Hook) {
+        self.hooks.push(hook);
+    }
+
+    /// Save to `path` after every change
+    ///
+    /// The hook keeps its own copy of the list, updated from the events, so
+    /// it never needs to lock or borrow the tracker. Failed saves are
+    /// logged as errors.
+    #[cfg(feature = "fs")]
+    pub fn autosave(&mut self, path: &str) {
+        let path = path.to_string();
+        let mut copy = self.clone();
+        self.on_event(Box::new(move |event| {
+            match event {
+                TrackerEvent::Added(expense) => {
+                    copy.insert((*expense).clone());
+                }
+                TrackerEvent::Removed(expense) => {
+                    copy.expenses.retain(|e| e.id != expense.id);
+                }
+                TrackerEvent::Updated { before, after } => {
+                    if let Some(e) = copy.expenses.iter_mut().find(|e| e.id == after.id) {
+                        *e = (*after).clone();
+                    }
+                    let records = edit_records(before, after, &copy.clock.today());
+                    copy.add_history(after.id, records);
+                }
+            }
+            if let Err(err) = copy.save(&path) {
+                log::error!("autosave to {} failed: {}", path, err);
+            }
+        }));
+    }
+
+    /// The expenses and the id counter in the data file format
+    pub fn to_text(&self) -> String {
+        to_csv_with_next_id(&self.expenses, self.next_id)
+    }
+
+    /// Load a tracker from a CSV data file (see `from_text`)
+    ///
+    /// If an edit history file was saved next to it (see
+    /// `set_persist_history`), the history is loaded too and later saves
+    /// keep it up to date.
+    #[cfg(feature = "fs")]
+    pub fn load(path: &str) -> Result<ExpenseTracker, ExpenseError> {
+        let mut tracker = ExpenseTracker::from_text(&std::fs::read_to_string(path)?)?;
+        let history = history_path(path);
+        if std::path::Path::new(&history).exists() {
+            let text = std::fs::read_to_string(&history)?;
+            tracker.history = history_from_csv(&text).map_err(|e| e.with_context(&history))?;
+            tracker.persist_history = true;
+        }
+        log::debug!(
+            "loaded {} expenses from {} (next id {})",
+            tracker.expenses.len(),
+            path,
+            tracker.next_id
+        );
+        Ok(tracker)
+    }
+
+    /// Save the expenses and the id counter to a CSV data file
+    ///
+    /// With `set_persist_history(true)`, the edit history is saved to
+    /// `history_path(path)` as well.
+    #[cfg(feature = "fs")]
+    pub fn save(&self, path: &str) -> Result<(), ExpenseError> {
+        std::fs::write(path, self.to_text())?;
+        if self.persist_history {
+            std::fs::write(history_path(path), history_to_csv(&self.history))?;
+        }
+        log::debug!("saved {} expenses to {}", self.expenses.len(), path);
+        Ok(())
+    }
+
+    /// Check every `add` against `budget`, or stop checking with `None`
+    pub fn set_budget(&mut self, budget: Option<Budget>) {
+        self.budget = budget;
+    }
+
+    /// The budget `add` checks against, if one is attached
+    pub fn budget(&self) -> Option<&Budget> {
+        self.budget.as_ref()
+    }
+
+    /// Save the edit history next to the data file, or stop saving it
+    ///
+    /// Off by default, since the history file grows with every edit.
+    pub fn set_persist_history(&mut self, persist: bool) {
+        self.persist_history = persist;
+    }
+
+    /// Date edits with `clock` rather than the system clock
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock + Send + Sync>) {
+        self.clock = clock;
+    }
+
+    /// Every change `update` made to the expense with `id`, oldest first
+    pub fn edit_history(&self, id: u64) -> &[EditRecord] {
+        self.history.get(&id).map_or(&[], Vec::as_slice)
+    }
+
+    fn add_history(&mut self, id: u64, records: Vec<EditRecord>) {
+        if !records.is_empty() {
+            self.history.entry(id).or_default().extend(records);
+        }
+    }
+
+    /// Close the `YYYY-MM` month, so its expenses can't be added, edited or
+    /// removed without `force`
+    pub fn close_period(&mut self, month: &str) -> Result<(), ExpenseError> {
+        validate_month_format(month)?;
+        self.closed_periods.insert(month.to_string());
+        Ok(())
+    }
+
+    /// Open a closed month again, saying whether it was closed
+    pub fn reopen_period(&mut self, month: &str) -> bool {
+        self.closed_periods.remove(month)
+    }
+
+    /// Replace the closed months, e.g. with the ones in the config
+    pub fn set_closed_periods(&mut self, months: BTreeSet<String>) {
+        self.closed_periods = months;
+    }
+
+    /// The closed `YYYY-MM` months, in order
+    pub fn closed_periods(&self) -> &BTreeSet<String> {
+        &self.closed_periods
+    }
+
+    fn check_open(&self, date: &str, force: bool) -> Result<(), ExpenseError> {
+        if force {
+            return Ok(());
+        }
+        check_period_open(&self.closed_periods, date)
+    }
+
+    /// Add a new expense with the next unused id
+    ///
+    /// With a budget attached, the result also warns about the new
+    /// expense's category and the overall limit (see `budget_warnings`),
+    /// counting the new expense. A date in a closed month is refused; use
+    /// `add_expense` to force it.
+    pub fn add(
+        &mut self,
+        amount: f64,
+        category: &str,
+        date: &str,
+    ) -> Result<AddResult, ExpenseError> {
+        self.check_open(date, false)?;
+        let expense = Expense::with_id(self.next_id, amount, category, date);
+        let expense = self.push(expense).clone();
+        let budget_warnings = match &self.budget {
+            Some(budget) => budget_warnings(&self.expenses, budget, category, date),
+            None => Vec::new(),
+        };
+        Ok(AddResult {
+            expense,
+            budget_warnings,
+        })
+    }
+
+    /// Add an expense built elsewhere, giving it the next unused id
+    ///
+    /// A date in a closed month is refused unless `force` is set.
+    pub fn add_expense(
+        &mut self,
+        mut expense: Expense,
+        force: bool,
+    ) -> Result<&Expense, ExpenseError> {
+        self.check_open(&expense.date, force)?;
+        expense.id = self.next_id;
+        reserve_ids_through(expense.id);
+        Ok(self.push(expense))
+    }
+
+    /// Add an expense that already has an id, moving the counter past it
+    ///
+    /// Meant for restoring saved expenses, so closed months aren't checked.
+    pub fn push(&mut self, expense: Expense) -> &Expense {
+        self.insert(expense);
+        let added = self.expenses.last().unwrap();
+        emit(&mut self.hooks, TrackerEvent::Added(added));
+        added
+    }
+
+    /// Push without telling the hooks
+    fn insert(&mut self, expense: Expense) {
+        self.next_id = self.next_id.max(expense.id.saturating_add(1));
+        self.expenses.push(expense);
+    }
+
+    fn index_of(&self, id: u64) -> Result<usize, ExpenseError> {
+        self.expenses
+            .iter()
+            .position(|e| e.id == id)
+            .ok_or_else(|| ExpenseError::NotFound(format!("no expense with id {}", id)))
+    }
+
+    /// Remove the expense with `id`; its id is not handed out again
+    ///
+    /// An expense dated in a closed month stays unless `force` is set.
+    pub fn remove(&mut self, id: u64, force: bool) -> Result<Expense, ExpenseError> {
+        let index = self.index_of(id)?;
+        self.check_open(&self.expenses[index].date, force)?;
+        let removed = self.expenses.remove(index);
+        emit(&mut self.hooks, TrackerEvent::Removed(removed.clone()));
+        Ok(removed)
+    }
+
+    /// Change the expense with `id` in place; its id can't be changed
+    ///
+    /// Every field that ends up different is added to the expense's
+    /// `edit_history`. Unless `force` is set, an expense dated in a closed
+    /// month can't be changed, nor moved into one.
+    pub fn update(
+        &mut self,
+        id: u64,
+        force: bool,
+        change: impl FnOnce(&mut Expense),
+    ) -> Result<&Expense, ExpenseError> {
+        let index = self.index_of(id)?;
+        let before = self.expenses[index].clone();
+        let mut changed = before.clone();
+        change(&mut changed);
+        changed.id = id;
+        self.check_open(&before.date, force)?;
+        self.check_open(&changed.date, force)?;
+        self.expenses[index] = changed;
+        let records = edit_records(&before, &self.expenses[index], &self.clock.today());
+        self.add_history(id, records);
+        let after = &self.expenses[index];
+        emit(&mut self.hooks, TrackerEvent::Updated { before, after });
+        Ok(after)
+    }
+
+    /// All expenses, in the order they were added
+    pub fn expenses(&self) -> &[Expense] {
+        &self.expenses
+    }
+
+    /// The id the next added expense will get
+    pub fn next_id(&self) -> u64 {
+        self.next_id
+    }
+}
+
+/// Refuse a change to an expense dated `date` if its month is one of
+/// `closed_periods`
+pub fn check_period_open(
+    closed_periods: &BTreeSet<String>,
+    date: &str,
+) -> Result<(), ExpenseError> {
+    match month_of(date) {
+        Some(month) if closed_periods.contains(month) => Err(ExpenseError::PeriodClosed(format!(
+            "{} (expense dated {})",
+            month, date
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Where `save` keeps the edit history for the data file at `path`
+#[cfg(feature = "fs")]
+pub fn history_path(path: &str) -> String {
+    format!("{}.history", path)
+}
+
+/// The fields an edit can change, written as in the data file
+fn field_values(expense: &Expense) -> [(&'static str, String); 10] {
+    [
+        ("amount", expense.amount.to_string()),
+        ("category", expense.category.clone()),
+        ("date", expense.date.clone()),
+        ("note", expense.note.clone().unwrap_or_default()),
+        ("payee", expense.payee.clone().unwrap_or_default()),
+        ("tags", expense.tags.join(TAG_SEPARATOR)),
+        ("currency", expense.currency.clone().unwrap_or_default()),
+        (
+            "kind",
+            match expense.kind {
+                TransactionKind::Expense => String::new(),
+                kind => kind.to_string(),
+            },
+        ),
+        (
+            "subcategory",
+            expense.subcategory.clone().unwrap_or_default(),
+        ),
+        (
+            "receipt_number",
+            expense.receipt_number.clone().unwrap_or_default(),
+        ),
+    ]
+}
+
+/// A record for every field that differs between `before` and `after`
+fn edit_records(before: &Expense, after: &Expense, at: &str) -> Vec<EditRecord> {
+    field_values(before)
+        .into_iter()
+        .zip(field_values(after))
+        .filter(|((_, old), (_, new))| old != new)
+        .map(|((field, old), (_, new))| EditRecord {
+            field: field.to_string(),
+            old,
+            new,
+            at: at.to_string(),
+        })
+        .collect()
+}
+
+/// Write the edit history as CSV, one edit per line after the header
+#[cfg(feature = "fs")]
+fn history_to_csv(history: &BTreeMap<u64, Vec<EditRecord>>) -> String {
+    let mut out = format!("{}\n", HISTORY_HEADER);
+    for (id, records) in history {
+        for record in records {
+            let fields = [
+                id.to_string(),
+                record.field.clone(),
+                record.old.clone(),
+                record.new.clone(),
+                record.at.clone(),
+            ];
+            out.push_str(&join_csv_fields(&fields));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Read an edit history written by `history_to_csv`
+#[cfg(feature = "fs")]
+fn history_from_csv(text: &str) -> Result<BTreeMap<u64, Vec<EditRecord>>, ExpenseError> {
+    let mut history: BTreeMap<u64, Vec<EditRecord>> = BTreeMap::new();
+    for (line, fields) in parse_csv(text)?.into_iter().skip(1) {
+        let [id, field, old, new, at] = <[String; 5]>::try_from(fields).map_err(|fields| {
+            ExpenseError::ParseError(format!(
+                "line {}: expected 5 fields, found {}",
+                line,
+                fields.len()
+            ))
+        })?;
+        let id = id
+            .parse::<u64>()
+            .map_err(|_| ExpenseError::ParseError(format!("line {}: bad id `{}`", line, id)))?;
+        history.entry(id).or_default().push(EditRecord {
+            field,
+            old,
+            new,
+            at,
+        });
+    }
+    Ok(history)
+}
+
+/// Pass an event to every hook in order, isolating panics
+fn emit(hooks: &mut [Hook], event: TrackerEvent) {
+    for (index, hook) in hooks.iter_mut().enumerate() {
+        if let Err(panic) = catch_unwind(AssertUnwindSafe(|| hook(&event))) {
+            let message = panic
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            log::error!("tracker hook {} panicked: {}", index, message);
+        }
+    }
+}
+
+/// An `ExpenseTracker` that can be shared between threads
+///
+/// Cloning gives another handle to the same tracker. Reads take a shared
+/// lock, so they run alongside each other and only wait for writers; the
+/// write lock is held just long enough to change the list.
+#[derive(Debug, Clone, Default)]
+pub struct SharedTracker {
+    inner: Arc<RwLock<ExpenseTracker>>,
+}
+
+impl From<ExpenseTracker> for SharedTracker {
+    fn from(tracker: ExpenseTracker) -> Self {
+        SharedTracker {
+            inner: Arc::new(RwLock::new(tracker)),
+        }
+    }
+}
+
+impl SharedTracker {
+    /// Creates an empty shared tracker
+    pub fn new() -> SharedTracker {
+        SharedTracker::from(ExpenseTracker::new())
+    }
+
+    /// Load a shared tracker from a CSV data file
+    #[cfg(feature = "fs")]
+    pub fn load(path: &str) -> Result<SharedTracker, ExpenseError> {
+        ExpenseTracker::load(path).map(SharedTracker::from)
+    }
+
+    // A thread that panicked while holding the lock can't have left the
+    // list half-changed (every change is a single push or remove), so a
+    // poisoned lock is still safe to use.
+    fn read(&self) -> RwLockReadGuard<'_, ExpenseTracker> {
+        self.inner.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, ExpenseTracker> {
+        self.inner.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Save the expenses and the id counter to a CSV data file
+    #[cfg(feature = "fs")]
+    pub fn save(&self, path: &str) -> Result<(), ExpenseError> {
+        self.read().save(path)
+    }
+
+    /// Add a new expense with the next unused id (see `ExpenseTracker::add`)
+    pub fn add(&self, amount: f64, category: &str, date: &str) -> Result<AddResult, ExpenseError> {
+        self.write().add(amount, category, date)
+    }
+
+    /// Check every `add` against `budget`, or stop checking with `None`
+    pub fn set_budget(&self, budget: Option<Budget>) {
+        self.write().set_budget(budget);
+    }
+
+    /// Add an expense built elsewhere under the next unused id and return
+    /// a copy of it
+    pub fn add_expense(&self, expense: Expense, force: bool) -> Result<Expense, ExpenseError> {
+        self.write().add_expense(expense, force).cloned()
+    }
+
+    /// Add an expense that already has an id
+    pub fn push(&self, expense: Expense) {
+        self.write().push(expense);
+    }
+
+    /// Remove the expense with `id`
+    pub fn remove(&self, id: u64, force: bool) -> Result<Expense, ExpenseError> {
+        self.write().remove(id, force)
+    }
+
+    /// Change the expense with `id` in place and return a copy of it
+    pub fn update(
+        &self,
+        id: u64,
+        force: bool,
+        change: impl FnOnce(&mut Expense),
+    ) -> Result<Expense, ExpenseError> {
+        self.write().update(id, force, change).cloned()
+    }
+
+    /// Close the `YYYY-MM` month (see `ExpenseTracker::close_period`)
+    pub fn close_period(&self, month: &str) -> Result<(), ExpenseError> {
+        self.write().close_period(month)
+    }
+
+    /// Open a closed month again, saying whether it was closed
+    pub fn reopen_period(&self, month: &str) -> bool {
+        self.write().reopen_period(month)
+    }
+
+    /// Replace the closed months (see `ExpenseTracker::set_closed_periods`)
+    pub fn set_closed_periods(&self, months: BTreeSet<String>) {
+        self.write().set_closed_periods(months);
+    }
+
+    /// A copy of the edit history of the expense with `id`
+    pub fn edit_history(&self, id: u64) -> Vec<EditRecord> {
+        self.read().edit_history(id).to_vec()
+    }
+
+    /// Run `hook` after every change (see `ExpenseTracker::on_event`)
+    ///
+    /// Hooks run while the write lock is held, so they must not call back
+    /// into this tracker.
+    pub fn on_event(&self, hook: Hook) {
+        self.write().on_event(hook);
+    }
+
+    /// Save to `path` after every change (see `ExpenseTracker::autosave`)
+    #[cfg(feature = "fs")]
+    pub fn autosave(&self, path: &str) {
+        self.write().autosave(path);
+    }
+
+    /// A copy of the expense with `id`
+    pub fn get(&self, id: u64) -> Option<Expense> {
+        self.read().expenses().iter().find(|e| e.id == id).cloned()
+    }
+
+    /// Number of expenses
+    pub fn len(&self) -> usize {
+        self.read().expenses().len()
+    }
+
+    /// Whether there are no expenses
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Sum of all amounts
+    pub fn total(&self) -> f64 {
+        calculate_total(self.read().expenses())
+    }
+
+    /// The id the next added expense will get
+    pub fn next_id(&self) -> u64 {
+        self.read().next_id()
+    }
+
+    /// A copy of every expense, all taken at the same moment
+    ///
+    /// Use this for reports that need several figures to agree with each
+    /// other, instead of calling `total`, `len`, … one after another.
+    pub fn snapshot(&self) -> Vec<Expense> {
+        self.read().expenses().to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FixedClock;
+
+    #[cfg(feature = "fs")]
+    struct TempFile(String);
+
+    #[cfg(feature = "fs")]
+    impl TempFile {
+        fn new(name: &str) -> TempFile {
+            let path =
+                std::env::temp_dir().join(format!("tracker_{}_{}.csv", name, std::process::id()));
+            TempFile(path.to_str().unwrap().to_string())
+        }
+    }
+
+    #[cfg(feature = "fs")]
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn test_ids_unique_after_deleting_newest_and_reloading() {
+        let file = TempFile::new("reload");
+        let mut tracker = ExpenseTracker::new();
+        tracker.add(10.0, "food", "2026-01-01").unwrap();
+        tracker.add(20.0, "food", "2026-01-02").unwrap();
+        let newest = tracker.add(30.0, "rent", "2026-01-03").unwrap().expense.id;
+        tracker.save(&file.0).unwrap();
+
+        let mut tracker = ExpenseTracker::load(&file.0).unwrap();
+        tracker.remove(newest, false).unwrap();
+        tracker.save(&file.0).unwrap();
+
+        let mut tracker = ExpenseTracker::load(&file.0).unwrap();
+        let added = tracker.add(5.0, "coffee", "2026-01-04").unwrap().expense.id;
+        assert!(added > newest);
+        tracker.save(&file.0).unwrap();
+
+        let tracker = ExpenseTracker::load(&file.0).unwrap();
+        let mut ids: Vec<u64> = tracker.expenses().iter().map(|e| e.id).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), 3);
+        assert_eq!(tracker.next_id(), added + 1);
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn test_load_without_next_id_line_uses_highest_id() {
+        let file = TempFile::new("legacy");
+        std::fs::write(
+            &file.0,
+            "id,amount,category,date\n4,1,food,2026-01-01\n9,2,food,2026-01-02\n",
+        )
+        .unwrap();
+        let tracker = ExpenseTracker::load(&file.0).unwrap();
+        assert_eq!(tracker.next_id(), 10);
+    }
+
+    #[test]
+    fn test_shared_tracker_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<SharedTracker>();
+        assert_send_sync::<ExpenseTracker>();
+    }
+
+    #[test]
+    fn test_concurrent_adds_and_reads() {
+        let tracker = SharedTracker::new();
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let tracker = tracker.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..100 {
+                        tracker.add(1.5, "food", "2026-01-01").unwrap();
+                        let snapshot = tracker.snapshot();
+                        assert_eq!(calculate_total(&snapshot), 1.5 * snapshot.len() as f64);
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(tracker.len(), 800);
+        assert_eq!(tracker.total(), 1200.0);
+        let mut ids: Vec<u64> = tracker.snapshot().iter().map(|e| e.id).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), 800);
+    }
+
+    #[test]
+    fn test_hooks_run_in_order_and_survive_panics() {
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut tracker = ExpenseTracker::new();
+
+        let first = Arc::clone(&seen);
+        tracker.on_event(Box::new(move |event| {
+            let name = match event {
+                TrackerEvent::Added(e) => format!("first: added {}", e.id),
+                TrackerEvent::Removed(e) => format!("first: removed {}", e.id),
+                TrackerEvent::Updated { before, after } => {
+                    format!("first: updated {} -> {}", before.amount, after.amount)
+                }
+            };
+            first.lock().unwrap().push(name);
+        }));
+        tracker.on_event(Box::new(|event| {
+            if let TrackerEvent::Removed(_) = event {
+                panic!("second hook fails on remove");
+            }
+        }));
+        let third = Arc::clone(&seen);
+        tracker.on_event(Box::new(move |_| {
+            third.lock().unwrap().push("third".to_string())
+        }));
+
+        let id = tracker.add(10.0, "food", "2026-01-01").unwrap().expense.id;
+        tracker.update(id, false, |e| e.amount = 12.0).unwrap();
+        assert!(tracker.remove(id, false).is_ok());
+        assert!(tracker.update(id, false, |_| {}).is_err());
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![
+                "first: added 1",
+                "third",
+                "first: updated 10 -> 12",
+                "third",
+                "first: removed 1",
+                "third",
+            ]
+        );
+        assert!(tracker.expenses().is_empty());
+    }
+
+    #[test]
+    fn test_update_records_each_changed_field() {
+        let mut tracker = ExpenseTracker::new();
+        tracker.set_clock(Arc::new(FixedClock::new("2026-03-14")));
+        let id = tracker.add(10.0, "food", "2026-03-01").unwrap().expense.id;
+
+        tracker.update(id, false, |e| e.amount = 12.5).unwrap();
+        tracker.set_clock(Arc::new(FixedClock::new("2026-03-15")));
+        tracker.update(id, false, |e| e.amount = 15.0).unwrap();
+        tracker.update(id, false, |e| e.amount = 15.0).unwrap();
+
+        let record = |old: &str, new: &str, at: &str| EditRecord {
+            field: "amount".to_string(),
+            old: old.to_string(),
+            new: new.to_string(),
+            at: at.to_string(),
+        };
+        assert_eq!(
+            tracker.edit_history(id),
+            [
+                record("10", "12.5", "2026-03-14"),
+                record("12.5", "15", "2026-03-15"),
+            ]
+        );
+
+        tracker
+            .update(id, false, |e| {
+                e.category = "coffee".to_string();
+                e.note = Some("flat white".to_string());
+            })
+            .unwrap();
+        let fields: Vec<(&str, &str, &str)> = tracker.edit_history(id)[2..]
+            .iter()
+            .map(|r| (r.field.as_str(), r.old.as_str(), r.new.as_str()))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![("category", "food", "coffee"), ("note", "", "flat white")]
+        );
+
+        tracker
+            .update(id, false, |e| {
+                e.subcategory = Some("espresso".to_string());
+                e.kind = TransactionKind::Income;
+            })
+            .unwrap();
+        let fields: Vec<(&str, &str, &str)> = tracker.edit_history(id)[4..]
+            .iter()
+            .map(|r| (r.field.as_str(), r.old.as_str(), r.new.as_str()))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![("kind", "", "income"), ("subcategory", "", "espresso")]
+        );
+        assert!(tracker.edit_history(id + 1).is_empty());
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn test_edit_history_saved_only_when_opted_in() {
+        let file = TempFile::new("history");
+        let history = TempFile(history_path(&file.0));
+        let mut tracker = ExpenseTracker::new();
+        let id = tracker.add(10.0, "food", "2026-03-01").unwrap().expense.id;
+        tracker
+            .update(id, false, |e| {
+                e.note = Some("lunch, with \"team\"".to_string())
+            })
+            .unwrap();
+
+        tracker.save(&file.0).unwrap();
+        assert!(!std::path::Path::new(&history.0).exists());
+        assert!(
+            ExpenseTracker::load(&file.0)
+                .unwrap()
+                .edit_history(id)
+                .is_empty()
+        );
+
+        tracker.set_persist_history(true);
+        tracker.save(&file.0).unwrap();
+        let mut loaded = ExpenseTracker::load(&file.0).unwrap();
+        assert_eq!(loaded.edit_history(id), tracker.edit_history(id));
+
+        // Reloaded with a history file, saving keeps it up to date
+        loaded.update(id, false, |e| e.amount = 11.0).unwrap();
+        loaded.save(&file.0).unwrap();
+        assert_eq!(
+            ExpenseTracker::load(&file.0)
+                .unwrap()
+                .edit_history(id)
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_closed_period_refuses_changes_unless_forced() {
+        let mut tracker = ExpenseTracker::new();
+        let january = tracker.add(10.0, "food", "2026-01-15").unwrap().expense.id;
+        let february = tracker.add(20.0, "food", "2026-02-03").unwrap().expense.id;
+        tracker.close_period("2026-01").unwrap();
+        assert!(tracker.close_period("2026-13").is_err());
+
+        let closed =
+            |result: Result<_, ExpenseError>| matches!(result, Err(ExpenseError::PeriodClosed(_)));
+        assert!(closed(tracker.add(5.0, "food", "2026-01-31").map(|_| ())));
+        let late = Expense::new(5.0, "food", "2026-01-31");
+        assert!(closed(tracker.add_expense(late.clone(), false).map(|_| ())));
+        assert!(closed(
+            tracker
+                .update(january, false, |e| e.amount = 12.0)
+                .map(|_| ())
+        ));
+        // Moving an open month's expense into a closed one is refused too
+        assert!(closed(
+            tracker
+                .update(february, false, |e| e.date = "2026-01-30".to_string())
+                .map(|_| ())
+        ));
+        assert!(closed(tracker.remove(january, false).map(|_| ())));
+        assert_eq!(tracker.expenses()[0].amount, 10.0);
+        assert_eq!(tracker.expenses()[1].date, "2026-02-03");
+        assert!(tracker.edit_history(february).is_empty());
+
+        // Open months are unaffected
+        tracker.add(5.0, "food", "2026-02-10").unwrap();
+        tracker
+            .update(february, false, |e| e.amount = 25.0)
+            .unwrap();
+
+        tracker.add_expense(late, true).unwrap();
+        tracker.update(january, true, |e| e.amount = 12.0).unwrap();
+        tracker.remove(january, true).unwrap();
+        assert_eq!(tracker.expenses().len(), 3);
+
+        assert!(tracker.reopen_period("2026-01"));
+        assert!(!tracker.reopen_period("2026-01"));
+        tracker.add(5.0, "food", "2026-01-31").unwrap();
+    }
+
+    #[test]
+    fn test_add_warns_when_crossing_budget_thresholds() {
+        let mut budget = Budget::new("2026-01");
+        budget.set_limit("food", 100.0);
+        let mut tracker = ExpenseTracker::new();
+        tracker.set_budget(Some(budget));
+
+        assert!(
+            tracker
+                .add(50.0, "food", "2026-01-05")
+                .unwrap()
+                .budget_warnings
+                .is_empty()
+        );
+        assert!(
+            tracker
+                .add(400.0, "rent", "2026-01-05")
+                .unwrap()
+                .budget_warnings
+                .is_empty()
+        );
+
+        let near = tracker
+            .add(35.0, "food", "2026-01-10")
+            .unwrap()
+            .budget_warnings;
+        assert_eq!(near.len(), 1);
+        assert_eq!(near[0].category.as_deref(), Some("food"));
+        assert_eq!((near[0].spent, near[0].over), (85.0, false));
+
+        let over = tracker
+            .add(20.0, "food", "2026-01-20")
+            .unwrap()
+            .budget_warnings;
+        assert_eq!(over.len(), 1);
+        assert_eq!((over[0].spent, over[0].over), (105.0, true));
+
+        // A new month starts from nothing spent
+        assert!(
+            tracker
+                .add(20.0, "food", "2026-02-01")
+                .unwrap()
+                .budget_warnings
+                .is_empty()
+        );
+
+        tracker.set_budget(None);
+        assert!(
+            tracker
+                .add(90.0, "food", "2026-02-02")
+                .unwrap()
+                .budget_warnings
+                .is_empty()
+        );
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn test_autosave_hook_writes_every_change() {
+        let file = TempFile::new("autosave");
+        let mut tracker = ExpenseTracker::new();
+        tracker.add(1.0, "food", "2026-01-01").unwrap();
+        tracker.autosave(&file.0);
+
+        let id = tracker.add(2.0, "rent", "2026-01-02").unwrap().expense.id;
+        assert_eq!(ExpenseTracker::load(&file.0).unwrap(), tracker);
+        tracker
+            .update(id, false, |e| e.note = Some("deposit".to_string()))
+            .unwrap();
+        assert_eq!(ExpenseTracker::load(&file.0).unwrap(), tracker);
+        tracker.remove(id, false).unwrap();
+        let saved = ExpenseTracker::load(&file.0).unwrap();
+        assert_eq!(saved, tracker);
+        assert_eq!(saved.next_id(), id + 1);
+    }
+}
@@ -0,0 +1,90 @@
+// This module reloads a data file whenever it changes on disk, for live
+// dashboards (enabled by the `watch` feature)
+
+use std::path::{Path, PathBuf};
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::error::ExpenseError;
+use crate::expense::Expense;
+use crate::storage::load_from_file;
+
+/// Keeps a file watch running; dropping it stops the watch
+pub struct FileWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+/// Call `on_change` with the freshly loaded expenses every time the data
+/// file at `path` is written
+///
+/// The callback runs on a background thread. The directory is watched
+/// rather than the file itself, so editors that save by replacing the file
+/// are picked up too. Changes that leave the file unreadable (e.g. a save
+/// that is still in progress) are skipped.
+pub fn watch_file_for_changes(
+    path: &str,
+    on_change: impl Fn(Vec<Expense>) + Send + 'static,
+) -> Result<FileWatcher, ExpenseError> {
+    let file = std::fs::canonicalize(path)?;
+    let dir = file
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."));
+    let watched = file.clone();
+
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| {
+        let Ok(event) = res else { return };
+        let relevant = matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+            && event.paths.iter().any(|p| p == &watched);
+        if !relevant {
+            return;
+        }
+        if let Ok(expenses) = load_from_file(&watched.to_string_lossy()) {
+            on_change(expenses);
+        }
+    })
+    .map_err(|e| ExpenseError::IoError(e.to_string()))?;
+    watcher
+        .watch(&dir, RecursiveMode::NonRecursive)
+        .map_err(|e| ExpenseError::IoError(e.to_string()))?;
+
+    Ok(FileWatcher { _watcher: watcher })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::save_to_file;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    #[test]
+    fn test_callback_fires_on_write() {
+        let dir = std::env::temp_dir().join(format!("watch_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("expenses.csv");
+        let path = path.to_str().unwrap();
+        save_to_file(&[], path).unwrap();
+
+        let (sender, receiver) = mpsc::channel();
+        let watcher = watch_file_for_changes(path, move |expenses| {
+            let _ = sender.send(expenses);
+        })
+        .unwrap();
+
+        let expenses = vec![Expense::new(12.0, "food", "2026-01-08")];
+        save_to_file(&expenses, path).unwrap();
+
+        let mut seen = Vec::new();
+        while let Ok(loaded) = receiver.recv_timeout(Duration::from_secs(5)) {
+            if !loaded.is_empty() {
+                seen = loaded;
+                break;
+            }
+        }
+        drop(watcher);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(seen, expenses);
+    }
+}
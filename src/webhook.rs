@@ -0,0 +1,86 @@
+// This module pushes expenses to a webhook (Zapier, Slack, a custom
+// server) as JSON (enabled by the `http` feature)
+
+use crate::error::ExpenseError;
+use crate::expense::Expense;
+
+/// POST the expenses to `url` as a JSON array
+///
+/// Fails with `ExpenseError::HttpError` if the server can't be reached or
+/// answers with an error status.
+pub fn send_to_webhook(expenses: &[Expense], url: &str) -> Result<(), ExpenseError> {
+    let body =
+        serde_json::to_string(expenses).map_err(|e| ExpenseError::ParseError(e.to_string()))?;
+    ureq::post(url)
+        .set("Content-Type", "application/json")
+        .send_string(&body)
+        .map_err(|e| ExpenseError::HttpError(e.to_string()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread::{self, JoinHandle};
+
+    /// Serve one request with `status`, handing back its headers and body
+    fn mock_server(status: &'static str) -> (String, JoinHandle<(Vec<String>, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut headers = Vec::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                headers.push(line.trim().to_string());
+            }
+            let length: usize = headers
+                .iter()
+                .find_map(|h| {
+                    let (name, value) = h.split_once(':')?;
+                    name.eq_ignore_ascii_case("content-length")
+                        .then(|| value.trim().parse().unwrap())
+                })
+                .unwrap_or(0);
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            let response = format!("HTTP/1.1 {}\r\nContent-Length: 0\r\n\r\n", status);
+            reader.get_mut().write_all(response.as_bytes()).unwrap();
+            (headers, String::from_utf8(body).unwrap())
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn test_send_to_webhook_posts_json() {
+        let (url, server) = mock_server("200 OK");
+        let expenses = vec![Expense::new(45.5, "food", "2026-01-08")];
+
+        send_to_webhook(&expenses, &url).unwrap();
+        let (headers, body) = server.join().unwrap();
+
+        assert!(headers[0].starts_with("POST /hook "));
+        assert!(
+            headers
+                .iter()
+                .any(|h| h.eq_ignore_ascii_case("content-type: application/json"))
+        );
+        let sent: Vec<Expense> = serde_json::from_str(&body).unwrap();
+        assert_eq!(sent, expenses);
+    }
+
+    #[test]
+    fn test_send_to_webhook_reports_error_status() {
+        let (url, server) = mock_server("500 Internal Server Error");
+        let result = send_to_webhook(&[], &url);
+        server.join().unwrap();
+        assert!(matches!(result, Err(ExpenseError::HttpError(_))));
+    }
+}